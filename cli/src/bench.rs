@@ -0,0 +1,432 @@
+use anyhow::Context;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use tracing::info;
+
+use crate::init::Backend;
+use crate::prove::{prover_args, ProverArgs};
+
+/// Benchmark cases of a project, read from `bench.toml` in the project directory.
+///
+/// ```toml
+/// iterations = 5
+///
+/// [[case]]
+/// name = "circom-multiplier2-arkworks"
+/// backend = "circom"
+/// zkey = "test-vectors/multiplier2_final.zkey"
+/// proof_lib = "arkworks"
+/// inputs = { a = ["3"], b = ["5"] }
+/// ```
+///
+/// Paths are relative to the project directory.
+pub const BENCH_CONFIG_FILE: &str = "bench.toml";
+
+const DEFAULT_ITERATIONS: usize = 5;
+
+/// Prefix of the line the `prover` binary prints its measurements on.
+const BENCH_REPORT_PREFIX: &str = "bench-report: ";
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct BenchConfig {
+    iterations: Option<usize>,
+    #[serde(rename = "case", default)]
+    cases: Vec<BenchCase>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct BenchCase {
+    name: String,
+    backend: Backend,
+    /// Circuit inputs, in the JSON layout `mopro prove` expects for the backend
+    inputs: toml::Value,
+    zkey: Option<PathBuf>,
+    proof_lib: Option<String>,
+    srs: Option<PathBuf>,
+    pk: Option<PathBuf>,
+    vk: Option<PathBuf>,
+    circuit: Option<PathBuf>,
+    #[serde(default)]
+    on_chain: bool,
+    #[serde(default)]
+    low_memory: bool,
+}
+
+/// Measurements of a single prove and verify run.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Sample {
+    pub prove_ms: f64,
+    pub verify_ms: f64,
+    pub proof_size_bytes: u64,
+    /// Not available on every OS
+    pub peak_rss_kb: Option<u64>,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct Stats {
+    pub mean: f64,
+    pub min: f64,
+    pub max: f64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CaseReport {
+    pub name: String,
+    pub backend: Backend,
+    pub prove_ms: Stats,
+    pub verify_ms: Stats,
+    pub proof_size_bytes: u64,
+    pub peak_rss_kb: Option<u64>,
+    pub samples: Vec<Sample>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BenchReport {
+    pub iterations: usize,
+    pub cases: Vec<CaseReport>,
+}
+
+/// A metric of a case that changed by more than the threshold compared to the baseline.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Regression {
+    pub case: String,
+    pub metric: &'static str,
+    pub baseline: f64,
+    pub current: f64,
+    pub change_percent: f64,
+}
+
+impl BenchConfig {
+    pub fn load(project_dir: &Path) -> anyhow::Result<Self> {
+        let path = project_dir.join(BENCH_CONFIG_FILE);
+        let content =
+            fs::read_to_string(&path).context(format!("Failed to read {}", path.display()))?;
+        Self::parse(&content).context(format!("Failed to parse {}", path.display()))
+    }
+
+    fn parse(content: &str) -> anyhow::Result<Self> {
+        Ok(toml::from_str(content)?)
+    }
+}
+
+/// Runs every case of the config, or only the `selected` ones, `iterations` times each.
+/// Every iteration runs in a new `prover` process, so peak memory usage is measured per run.
+pub fn run_bench(
+    project_dir: &Path,
+    config: &BenchConfig,
+    iterations: Option<usize>,
+    selected: &[String],
+) -> anyhow::Result<BenchReport> {
+    let iterations = iterations
+        .or(config.iterations)
+        .unwrap_or(DEFAULT_ITERATIONS);
+    if iterations == 0 {
+        return Err(anyhow::anyhow!("At least one iteration is required"));
+    }
+    if let Some(name) = selected
+        .iter()
+        .find(|name| !config.cases.iter().any(|case| &case.name == *name))
+    {
+        return Err(anyhow::anyhow!("Unknown benchmark case '{name}'"));
+    }
+    let cases: Vec<&BenchCase> = config
+        .cases
+        .iter()
+        .filter(|case| selected.is_empty() || selected.contains(&case.name))
+        .collect();
+    if cases.is_empty() {
+        return Err(anyhow::anyhow!(
+            "No benchmark cases found in {BENCH_CONFIG_FILE}"
+        ));
+    }
+
+    let prover = build_prover(project_dir)?;
+    let work_dir = std::env::temp_dir().join(format!("mopro-bench-{}", std::process::id()));
+
+    let mut reports = Vec::new();
+    for case in cases {
+        let case_dir = work_dir.join(&case.name);
+        let result = run_case(&prover, project_dir, &case_dir, case, iterations);
+        let _ = fs::remove_dir_all(&case_dir);
+        reports.push(result?);
+    }
+    let _ = fs::remove_dir_all(&work_dir);
+
+    Ok(BenchReport {
+        iterations,
+        cases: reports,
+    })
+}
+
+/// Builds the project's `prover` binary in release mode and returns its path.
+fn build_prover(project_dir: &Path) -> anyhow::Result<PathBuf> {
+    info!("Building the prover of {}", project_dir.display());
+    let output = Command::new("cargo")
+        .args(["build", "--release", "--bin", "prover"])
+        .arg("--manifest-path")
+        .arg(project_dir.join("Cargo.toml"))
+        .args(["--message-format", "json-render-diagnostics"])
+        .output()
+        .context("Failed to run cargo")?;
+    if !output.status.success() {
+        return Err(anyhow::anyhow!(
+            "Failed to build the prover:\n{}",
+            String::from_utf8_lossy(&output.stderr)
+        ));
+    }
+
+    String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .filter_map(|line| serde_json::from_str::<serde_json::Value>(line).ok())
+        .find(|message| message["target"]["name"] == "prover")
+        .and_then(|message| message["executable"].as_str().map(PathBuf::from))
+        .ok_or(anyhow::anyhow!(
+            "cargo did not report the prover executable"
+        ))
+}
+
+fn run_case(
+    prover: &Path,
+    project_dir: &Path,
+    case_dir: &Path,
+    case: &BenchCase,
+    iterations: usize,
+) -> anyhow::Result<CaseReport> {
+    fs::create_dir_all(case_dir).context(format!("Failed to create {}", case_dir.display()))?;
+    let inputs_path = case_dir.join("inputs.json");
+    fs::write(&inputs_path, serde_json::to_string(&case.inputs)?)
+        .context(format!("Failed to write {}", inputs_path.display()))?;
+
+    let args = ProverArgs {
+        project_dir: project_dir.to_path_buf(),
+        zkey: case.zkey.clone(),
+        proof_lib: case.proof_lib.clone(),
+        srs: case.srs.clone(),
+        pk: case.pk.clone(),
+        vk: case.vk.clone(),
+        circuit: case.circuit.clone(),
+        on_chain: case.on_chain,
+        low_memory: case.low_memory,
+        proof_dir: case_dir.join("proof"),
+    };
+
+    let mut samples = Vec::with_capacity(iterations);
+    for iteration in 1..=iterations {
+        info!("{} ({iteration}/{iterations})", case.name);
+        let output = Command::new(prover)
+            .args(prover_args(
+                case.backend,
+                "bench",
+                &args,
+                Some(&inputs_path),
+            ))
+            .current_dir(project_dir)
+            .output()
+            .context(format!("Failed to run {}", prover.display()))?;
+        if !output.status.success() {
+            return Err(anyhow::anyhow!(
+                "{} failed:\n{}",
+                case.name,
+                String::from_utf8_lossy(&output.stderr)
+            ));
+        }
+
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        let report = stdout
+            .lines()
+            .find_map(|line| line.strip_prefix(BENCH_REPORT_PREFIX))
+            .ok_or(anyhow::anyhow!("{} did not report measurements", case.name))?;
+        samples.push(serde_json::from_str(report)?);
+    }
+
+    Ok(CaseReport {
+        name: case.name.clone(),
+        backend: case.backend,
+        prove_ms: Stats::of(samples.iter().map(|s: &Sample| s.prove_ms)),
+        verify_ms: Stats::of(samples.iter().map(|s| s.verify_ms)),
+        proof_size_bytes: samples
+            .iter()
+            .map(|s| s.proof_size_bytes)
+            .max()
+            .unwrap_or(0),
+        peak_rss_kb: samples.iter().filter_map(|s| s.peak_rss_kb).max(),
+        samples,
+    })
+}
+
+impl Stats {
+    fn of(values: impl Iterator<Item = f64>) -> Self {
+        let values: Vec<f64> = values.collect();
+        Self {
+            mean: values.iter().sum::<f64>() / values.len() as f64,
+            min: values.iter().copied().fold(f64::INFINITY, f64::min),
+            max: values.iter().copied().fold(f64::NEG_INFINITY, f64::max),
+        }
+    }
+}
+
+impl CaseReport {
+    /// Metrics compared against a baseline, where larger values are worse.
+    fn metrics(&self) -> Vec<(&'static str, Option<f64>)> {
+        vec![
+            ("prove time (ms)", Some(self.prove_ms.mean)),
+            ("verify time (ms)", Some(self.verify_ms.mean)),
+            ("peak RSS (KB)", self.peak_rss_kb.map(|kb| kb as f64)),
+            ("proof size (bytes)", Some(self.proof_size_bytes as f64)),
+        ]
+    }
+}
+
+/// Compares the cases found in both reports and returns the metrics that grew by more
+/// than `threshold_percent`.
+pub fn compare(
+    current: &BenchReport,
+    baseline: &BenchReport,
+    threshold_percent: f64,
+) -> Vec<Regression> {
+    let mut regressions = Vec::new();
+    for case in &current.cases {
+        let Some(baseline_case) = baseline.cases.iter().find(|c| c.name == case.name) else {
+            continue;
+        };
+        for ((metric, current), (_, baseline)) in
+            case.metrics().into_iter().zip(baseline_case.metrics())
+        {
+            let (Some(current), Some(baseline)) = (current, baseline) else {
+                continue;
+            };
+            if baseline <= 0.0 {
+                continue;
+            }
+            let change_percent = (current - baseline) / baseline * 100.0;
+            if change_percent > threshold_percent {
+                regressions.push(Regression {
+                    case: case.name.clone(),
+                    metric,
+                    baseline,
+                    current,
+                    change_percent,
+                });
+            }
+        }
+    }
+    regressions
+}
+
+/// Renders the report as a markdown table, followed by the regressions if a baseline was given.
+pub fn markdown(report: &BenchReport, regressions: Option<&[Regression]>) -> String {
+    let mut content = String::from(
+        "| Case | Backend | Prove mean (ms) | Prove min / max (ms) | Verify mean (ms) | Peak RSS (MB) | Proof size (bytes) |\n\
+         | ---- | ------- | --------------- | -------------------- | ---------------- | ------------- | ------------------ |\n"
+    );
+    for case in &report.cases {
+        content.push_str(&format!(
+            "| {} | {} | {:.2} | {:.2} / {:.2} | {:.2} | {} | {} |\n",
+            case.name,
+            case.backend.feature(),
+            case.prove_ms.mean,
+            case.prove_ms.min,
+            case.prove_ms.max,
+            case.verify_ms.mean,
+            case.peak_rss_kb
+                .map(|kb| format!("{:.1}", kb as f64 / 1024.0))
+                .unwrap_or("n/a".to_string()),
+            case.proof_size_bytes,
+        ));
+    }
+    content.push_str(&format!("\n{} iterations per case\n", report.iterations));
+
+    if let Some(regressions) = regressions {
+        if regressions.is_empty() {
+            content.push_str("\nNo regressions compared to the baseline\n");
+        } else {
+            content.push_str(
+                "\n### Regressions\n\n| Case | Metric | Baseline | Current | Change |\n| ---- | ------ | -------- | ------- | ------ |\n",
+            );
+            for regression in regressions {
+                content.push_str(&format!(
+                    "| {} | {} | {:.2} | {:.2} | +{:.1}% |\n",
+                    regression.case,
+                    regression.metric,
+                    regression.baseline,
+                    regression.current,
+                    regression.change_percent
+                ));
+            }
+        }
+    }
+    content
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn report(prove_ms: f64, peak_rss_kb: Option<u64>) -> BenchReport {
+        let sample = Sample {
+            prove_ms,
+            verify_ms: 10.0,
+            proof_size_bytes: 800,
+            peak_rss_kb,
+        };
+        BenchReport {
+            iterations: 1,
+            cases: vec![CaseReport {
+                name: "circom-multiplier2-arkworks".to_string(),
+                backend: Backend::Circom,
+                prove_ms: Stats::of([prove_ms].into_iter()),
+                verify_ms: Stats::of([10.0].into_iter()),
+                proof_size_bytes: 800,
+                peak_rss_kb,
+                samples: vec![sample],
+            }],
+        }
+    }
+
+    #[test]
+    fn test_compare_flags_regressions_past_threshold() {
+        let baseline = report(100.0, Some(1000));
+
+        assert!(compare(&report(109.0, Some(1000)), &baseline, 10.0).is_empty());
+        assert!(compare(&report(50.0, None), &baseline, 10.0).is_empty());
+
+        let regressions = compare(&report(125.0, Some(1200)), &baseline, 10.0);
+        assert_eq!(regressions.len(), 2);
+        assert_eq!(regressions[0].metric, "prove time (ms)");
+        assert_eq!(regressions[0].change_percent, 25.0);
+        assert_eq!(regressions[1].metric, "peak RSS (KB)");
+    }
+
+    #[test]
+    fn test_parse_bench_config() {
+        let config = BenchConfig::parse(
+            r#"
+            iterations = 3
+
+            [[case]]
+            name = "noir-multiplier2-keccak"
+            backend = "noir"
+            circuit = "test-vectors/noir_multiplier2.json"
+            inputs = ["3", "5"]
+            on_chain = true
+            "#,
+        )
+        .unwrap();
+
+        assert_eq!(config.iterations, Some(3));
+        assert_eq!(config.cases[0].backend, Backend::Noir);
+        assert!(config.cases[0].on_chain);
+        assert_eq!(
+            serde_json::to_string(&config.cases[0].inputs).unwrap(),
+            r#"["3","5"]"#
+        );
+        assert!(
+            BenchConfig::parse("[[case]]\nname = \"x\"\nbackend = \"groth16\"\ninputs = []")
+                .is_err()
+        );
+    }
+}
@@ -0,0 +1,103 @@
+use mopro_ffi::bindings::{
+    self,
+    config::MoproConfig,
+    constants::{
+        AndroidPlatform, Arch, DesktopPlatform, FlutterPlatform, IosPlatform, JvmPlatform,
+        NodePlatform, PlatformBuilder, PythonPlatform, ReactNativePlatform, WebPlatform,
+    },
+    doctor::ToolCheck,
+};
+
+use crate::Platform;
+
+/// Checks the tools needed to build the given platforms and prints a pass/fail table,
+/// followed by a fix for every failed check. Returns whether all checks passed.
+pub fn run_doctor(platforms: &[Platform], config: &MoproConfig) -> anyhow::Result<bool> {
+    let mut rows: Vec<(Platform, ToolCheck)> = Vec::new();
+    for platform in platforms {
+        let checks = match platform {
+            Platform::Ios => toolchain_checks::<IosPlatform>(config)?,
+            Platform::Android => toolchain_checks::<AndroidPlatform>(config)?,
+            Platform::Web => toolchain_checks::<WebPlatform>(config)?,
+            Platform::Flutter => toolchain_checks::<FlutterPlatform>(config)?,
+            Platform::Desktop => toolchain_checks::<DesktopPlatform>(config)?,
+            Platform::Python => toolchain_checks::<PythonPlatform>(config)?,
+            Platform::ReactNative => toolchain_checks::<ReactNativePlatform>(config)?,
+            Platform::Node => toolchain_checks::<NodePlatform>(config)?,
+            Platform::Jvm => toolchain_checks::<JvmPlatform>(config)?,
+        };
+        rows.extend(checks.into_iter().map(|check| (*platform, check)));
+    }
+
+    print_table(&rows);
+
+    let failed: Vec<&(Platform, ToolCheck)> =
+        rows.iter().filter(|(_, check)| !check.passed()).collect();
+    if failed.is_empty() {
+        println!();
+        println!("All checks passed");
+        return Ok(true);
+    }
+
+    println!();
+    println!("To fix:");
+    for (platform, check) in &failed {
+        println!("  [{platform}] {}: {}", check.name, check.fix);
+    }
+    Ok(false)
+}
+
+/// Runs the checks of the builder for the architectures configured for the project,
+/// or for the platform's default architectures.
+fn toolchain_checks<Builder: PlatformBuilder>(
+    config: &MoproConfig,
+) -> anyhow::Result<Vec<ToolCheck>> {
+    let archs: Vec<Builder::Arch> = bindings::resolve_target_archs::<Builder>(&[], config)?
+        .iter()
+        .map(Builder::Arch::parse_from_str)
+        .collect::<Result<_, _>>()?;
+    Ok(Builder::toolchain_checks(&archs))
+}
+
+fn print_table(rows: &[(Platform, ToolCheck)]) {
+    let header = ["PLATFORM", "CHECK", "STATUS", "DETAILS"];
+    let cells: Vec<[String; 4]> = rows
+        .iter()
+        .map(|(platform, check)| {
+            let (status, details) = match &check.result {
+                Ok(details) => ("✅ ok", details),
+                Err(details) => ("❌ fail", details),
+            };
+            [
+                platform.to_string(),
+                check.name.clone(),
+                status.to_string(),
+                details.clone(),
+            ]
+        })
+        .collect();
+
+    let mut widths = header.map(|h| h.chars().count());
+    for row in &cells {
+        for (width, cell) in widths.iter_mut().zip(row) {
+            *width = (*width).max(cell.chars().count());
+        }
+    }
+
+    let format_row = |row: [&str; 4]| {
+        format!(
+            "{:<w0$}  {:<w1$}  {:<w2$}  {}",
+            row[0],
+            row[1],
+            row[2],
+            row[3],
+            w0 = widths[0],
+            w1 = widths[1],
+            w2 = widths[2],
+        )
+    };
+    println!("{}", format_row(header));
+    for row in &cells {
+        println!("{}", format_row(row.each_ref().map(String::as_str)));
+    }
+}
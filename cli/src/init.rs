@@ -0,0 +1,523 @@
+use anyhow::Context;
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use tracing::info;
+
+// The scaffold is a copy of the `test-e2e` crate, the reference crate the bindings builders
+// are tested against. `test_templates_match_test_e2e` keeps the two in sync.
+const CIRCOM_MODULE: &str = include_str!("../templates/src/circom.rs");
+const HALO2_MODULE: &str = include_str!("../templates/src/halo2.rs");
+const NOIR_MODULE: &str = include_str!("../templates/src/noir.rs");
+const BUILD_SCRIPT: &str = include_str!("../templates/build.rs");
+const GITIGNORE: &str = include_str!("../templates/gitignore");
+
+const BINARIES: [(&str, &str); 9] = [
+    ("ios", include_str!("../templates/bin/ios.rs")),
+    ("android", include_str!("../templates/bin/android.rs")),
+    ("web", include_str!("../templates/bin/web.rs")),
+    ("flutter", include_str!("../templates/bin/flutter.rs")),
+    (
+        "react-native",
+        include_str!("../templates/bin/react-native.rs"),
+    ),
+    ("jvm", include_str!("../templates/bin/jvm.rs")),
+    ("desktop", include_str!("../templates/bin/desktop.rs")),
+    ("python", include_str!("../templates/bin/python.rs")),
+    ("prover", include_str!("../templates/bin/prover.rs")),
+];
+
+/// Library name of the `test-e2e` crate, replaced by the new crate's in the binaries.
+const TEMPLATE_LIB_NAME: &str = "test_e2e";
+
+/// Repository `mopro-ffi` is developed in.
+const MOPRO_FFI_GIT: &str = "https://github.com/zkmopro/mopro";
+
+/// Where the generated crate takes `mopro-ffi` from.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MoproFfiSource {
+    /// A local checkout of the crate
+    Path(PathBuf),
+    /// A git repository containing the crate
+    Git(String),
+}
+
+impl Default for MoproFfiSource {
+    /// The `mopro-ffi` the CLI is built with: its checkout next to the CLI's when there is one,
+    /// otherwise the repository. The binaries of the scaffold use builders the crates.io release
+    /// does not have yet.
+    fn default() -> Self {
+        let checkout = Path::new(env!("CARGO_MANIFEST_DIR")).join("../mopro-ffi");
+        if checkout.join("Cargo.toml").is_file() {
+            MoproFfiSource::Path(checkout)
+        } else {
+            MoproFfiSource::Git(MOPRO_FFI_GIT.to_string())
+        }
+    }
+}
+
+impl MoproFfiSource {
+    /// Dependency entry for `mopro-ffi` in the generated `Cargo.toml`.
+    fn dependency(&self) -> anyhow::Result<String> {
+        let source = match self {
+            MoproFfiSource::Path(path) => {
+                let path = path
+                    .canonicalize()
+                    .context(format!("Failed to find mopro-ffi at {}", path.display()))?;
+                format!(
+                    "path = {}",
+                    toml::Value::String(path.to_string_lossy().to_string())
+                )
+            }
+            MoproFfiSource::Git(url) => format!("git = {}", toml::Value::String(url.clone())),
+        };
+        Ok(format!("{{ {source}, features = [\"flutter\"] }}"))
+    }
+}
+
+#[derive(
+    Debug,
+    Clone,
+    Copy,
+    PartialEq,
+    Eq,
+    PartialOrd,
+    Ord,
+    clap::ValueEnum,
+    serde::Serialize,
+    serde::Deserialize,
+)]
+#[serde(rename_all = "lowercase")]
+pub enum Backend {
+    Circom,
+    Halo2,
+    Noir,
+}
+
+impl Backend {
+    pub fn feature(&self) -> &'static str {
+        match self {
+            Backend::Circom => "circom",
+            Backend::Halo2 => "halo2",
+            Backend::Noir => "noir",
+        }
+    }
+
+    /// Optional dependencies enabled by the backend feature.
+    fn dependencies(&self) -> Vec<(&'static str, &'static str)> {
+        match self {
+            Backend::Circom => vec![
+                (
+                    "circom-prover",
+                    r#"{ git = "https://github.com/zkmopro/mopro", optional = true }"#,
+                ),
+                ("rust-witness", r#"{ version = "0.1", optional = true }"#),
+                ("serde_json", r#"{ version = "1.0.143", optional = true }"#),
+                ("num-bigint", r#"{ version = "0.4.6", optional = true }"#),
+                ("lazy_static", r#"{ version = "1.4.0", optional = true }"#),
+            ],
+            Backend::Halo2 => vec![
+                ("anyhow", r#"{ version = "1.0.99", optional = true }"#),
+                ("serde_json", r#"{ version = "1.0.143", optional = true }"#),
+                (
+                    "plonk-fibonacci",
+                    r#"{ package = "plonk-fibonacci", git = "https://github.com/sifnoc/plonkish-fibonacci-sample.git", optional = true }"#,
+                ),
+            ],
+            Backend::Noir => vec![
+                (
+                    "noir_rs",
+                    r#"{ package = "noir", git = "https://github.com/zkmopro/noir-rs", features = ["barretenberg"], branch = "v1.0.0-beta.8-3", optional = true }"#,
+                ),
+                ("serde_json", r#"{ version = "1.0.143", optional = true }"#),
+                ("anyhow", r#"{ version = "1.0.99", optional = true }"#),
+            ],
+        }
+    }
+
+    fn feature_dependencies(&self) -> Vec<&'static str> {
+        self.dependencies()
+            .into_iter()
+            .map(|(name, _)| name)
+            .collect()
+    }
+
+    fn module_source(&self) -> &'static str {
+        match self {
+            Backend::Circom => CIRCOM_MODULE,
+            Backend::Halo2 => HALO2_MODULE,
+            Backend::Noir => NOIR_MODULE,
+        }
+    }
+
+    fn module_exports(&self) -> &'static str {
+        match self {
+            Backend::Circom => {
+                "generate_circom_proof, verify_circom_proof, CircomProof, CircomProofResult, ProofLib, G1, G2"
+            }
+            Backend::Halo2 => "generate_halo2_proof, verify_halo2_proof, Halo2ProofResult",
+            Backend::Noir => "generate_noir_proof, get_noir_verification_key, verify_noir_proof",
+        }
+    }
+
+    /// Sample circuit artifacts copied into `test-vectors/`.
+    fn test_vectors(&self) -> Vec<(&'static str, &'static [u8])> {
+        match self {
+            Backend::Circom => vec![
+                (
+                    "multiplier2.wasm",
+                    include_bytes!("../templates/test-vectors/multiplier2.wasm"),
+                ),
+                (
+                    "multiplier2_final.zkey",
+                    include_bytes!("../templates/test-vectors/multiplier2_final.zkey"),
+                ),
+            ],
+            Backend::Halo2 => vec![
+                (
+                    "plonk_fibonacci_srs.bin",
+                    include_bytes!("../templates/test-vectors/plonk_fibonacci_srs.bin"),
+                ),
+                (
+                    "plonk_fibonacci_pk.bin",
+                    include_bytes!("../templates/test-vectors/plonk_fibonacci_pk.bin"),
+                ),
+                (
+                    "plonk_fibonacci_vk.bin",
+                    include_bytes!("../templates/test-vectors/plonk_fibonacci_vk.bin"),
+                ),
+            ],
+            Backend::Noir => vec![
+                (
+                    "noir_multiplier2.json",
+                    include_bytes!("../templates/test-vectors/noir_multiplier2.json"),
+                ),
+                (
+                    "noir_multiplier2.srs",
+                    include_bytes!("../templates/test-vectors/noir_multiplier2.srs"),
+                ),
+                (
+                    "noir_multiplier2.vk",
+                    include_bytes!("../templates/test-vectors/noir_multiplier2.vk"),
+                ),
+            ],
+        }
+    }
+}
+
+/// Scaffolds a new proving crate named `name` inside `parent_dir`.
+pub fn init_project(
+    name: &str,
+    parent_dir: &Path,
+    backends: &[Backend],
+    mopro_ffi: &MoproFfiSource,
+    force: bool,
+) -> anyhow::Result<PathBuf> {
+    validate_crate_name(name)?;
+    if backends.is_empty() {
+        return Err(anyhow::anyhow!("At least one backend must be selected"));
+    }
+
+    let mut backends = backends.to_vec();
+    backends.sort();
+    backends.dedup();
+
+    let project_dir = parent_dir.join(name);
+    if project_dir.exists() && !force {
+        return Err(anyhow::anyhow!(
+            "{} already exists, use --force to overwrite it",
+            project_dir.display()
+        ));
+    }
+
+    let mopro_ffi_dependency = mopro_ffi.dependency()?;
+
+    info!("Creating {} in {}", name, project_dir.display());
+
+    write_file(
+        &project_dir.join("Cargo.toml"),
+        cargo_toml(name, &backends, &mopro_ffi_dependency).as_bytes(),
+    )?;
+    write_file(&project_dir.join(".gitignore"), GITIGNORE.as_bytes())?;
+    write_file(
+        &project_dir.join("src/lib.rs"),
+        lib_rs(&backends).as_bytes(),
+    )?;
+    for (bin_name, source) in BINARIES {
+        write_file(
+            &project_dir.join(format!("bin/{bin_name}.rs")),
+            source
+                .replace(TEMPLATE_LIB_NAME, &name.replace('-', "_"))
+                .as_bytes(),
+        )?;
+    }
+    if backends.contains(&Backend::Circom) {
+        write_file(&project_dir.join("build.rs"), BUILD_SCRIPT.as_bytes())?;
+    }
+
+    for backend in &backends {
+        write_file(
+            &project_dir.join(format!("src/{}.rs", backend.feature())),
+            backend.module_source().as_bytes(),
+        )?;
+        for (file_name, content) in backend.test_vectors() {
+            write_file(&project_dir.join("test-vectors").join(file_name), content)?;
+        }
+    }
+
+    Ok(project_dir)
+}
+
+fn validate_crate_name(name: &str) -> anyhow::Result<()> {
+    let valid = name.chars().next().is_some_and(|c| c.is_ascii_alphabetic())
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+    if !valid {
+        return Err(anyhow::anyhow!(
+            "Invalid crate name '{name}': it must start with a letter and only contain letters, digits, '-' and '_'"
+        ));
+    }
+    Ok(())
+}
+
+fn write_file(path: &Path, content: &[u8]) -> anyhow::Result<()> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)
+            .context(format!("Failed to create directory {}", parent.display()))?;
+    }
+    fs::write(path, content).context(format!("Failed to write {}", path.display()))
+}
+
+fn cargo_toml(name: &str, backends: &[Backend], mopro_ffi_dependency: &str) -> String {
+    let mut content = format!(
+        r#"[package]
+name = "{name}"
+version = "0.1.0"
+edition = "2021"
+
+[lib]
+crate-type = ["cdylib", "staticlib", "rlib"]
+"#
+    );
+
+    for (bin_name, _) in BINARIES {
+        content.push_str(&format!(
+            "\n[[bin]]\nname = \"{bin_name}\"\npath = \"bin/{bin_name}.rs\"\n"
+        ));
+    }
+
+    // The binaries check every backend feature, also the ones that were not selected
+    content.push_str(
+        "\n[lints.rust]\nunexpected_cfgs = { level = \"warn\", check-cfg = ['cfg(frb_expand)', 'cfg(feature, values(\"circom\", \"halo2\", \"noir\"))'] }\n",
+    );
+
+    let default_features: Vec<String> = backends
+        .iter()
+        .map(|backend| format!("\"{}\"", backend.feature()))
+        .collect();
+    content.push_str(&format!(
+        "\n[features]\ndefault = [{}]\n",
+        default_features.join(", ")
+    ));
+    for backend in backends {
+        let deps: Vec<String> = backend
+            .feature_dependencies()
+            .iter()
+            .map(|dep| format!("\"dep:{dep}\""))
+            .collect();
+        content.push_str(&format!("{} = [{}]\n", backend.feature(), deps.join(", ")));
+    }
+
+    // Backends share some dependencies, keep a single entry for each of them
+    let dependencies: BTreeMap<&str, &str> = backends
+        .iter()
+        .flat_map(|backend| backend.dependencies())
+        .collect();
+    content.push_str(&format!(
+        "\n[dependencies]\nmopro-ffi = {mopro_ffi_dependency}\n"
+    ));
+    for (dep, spec) in dependencies {
+        content.push_str(&format!("{dep} = {spec}\n"));
+    }
+
+    if backends.contains(&Backend::Circom) {
+        content.push_str(
+            "\n[build-dependencies]\nrust-witness = { version = \"0.1\", optional = true }\n",
+        );
+    }
+    if backends.contains(&Backend::Noir) {
+        content.push_str("\n[dev-dependencies]\nserial_test = \"3.0.0\"\n");
+    }
+
+    content
+}
+
+fn lib_rs(backends: &[Backend]) -> String {
+    let mut content = String::from("mopro_ffi::config!();\n");
+    for backend in backends {
+        let feature = backend.feature();
+        content.push_str(&format!(
+            r#"
+// {feature} module
+#[cfg(feature = "{feature}")]
+mod {feature};
+#[cfg(feature = "{feature}")]
+pub use {feature}::{{{exports}}};
+"#,
+            exports = backend.module_exports()
+        ));
+    }
+    content
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::process::Command;
+
+    /// Temporary directory removed when dropped, also when an assertion fails.
+    struct TempDir(PathBuf);
+
+    impl TempDir {
+        fn new(name: &str) -> Self {
+            let dir =
+                std::env::temp_dir().join(format!("mopro-init-{name}-{}", std::process::id()));
+            let _ = fs::remove_dir_all(&dir);
+            fs::create_dir_all(&dir).unwrap();
+            Self(dir)
+        }
+    }
+
+    impl Drop for TempDir {
+        fn drop(&mut self) {
+            let _ = fs::remove_dir_all(&self.0);
+        }
+    }
+
+    #[test]
+    fn test_init_selected_backends() {
+        let parent = TempDir::new("backends");
+        let project_dir = init_project(
+            "my-app",
+            &parent.0,
+            &[Backend::Noir, Backend::Halo2],
+            &MoproFfiSource::Git(MOPRO_FFI_GIT.to_string()),
+            false,
+        )
+        .unwrap();
+
+        assert!(project_dir.join("src/halo2.rs").exists());
+        assert!(project_dir.join("src/noir.rs").exists());
+        assert!(!project_dir.join("src/circom.rs").exists());
+        assert!(!project_dir.join("build.rs").exists());
+        assert!(project_dir
+            .join("test-vectors/plonk_fibonacci_pk.bin")
+            .exists());
+        assert!(project_dir.join("bin/ios.rs").exists());
+        let prover = fs::read_to_string(project_dir.join("bin/prover.rs")).unwrap();
+        assert!(prover.contains("use my_app::{generate_halo2_proof"));
+        assert!(!prover.contains(TEMPLATE_LIB_NAME));
+
+        let cargo_toml = fs::read_to_string(project_dir.join("Cargo.toml")).unwrap();
+        assert!(cargo_toml.contains("default = [\"halo2\", \"noir\"]"));
+        assert_eq!(cargo_toml.matches("anyhow = ").count(), 1);
+        let cargo_toml = cargo_toml.parse::<toml::Value>().unwrap();
+        assert_eq!(
+            cargo_toml["dependencies"]["mopro-ffi"]["git"].as_str(),
+            Some(MOPRO_FFI_GIT)
+        );
+    }
+
+    #[test]
+    fn test_mopro_ffi_source() {
+        let git = MoproFfiSource::Git("https://github.com/zkmopro/mopro".to_string())
+            .dependency()
+            .unwrap();
+        assert_eq!(
+            git,
+            r#"{ git = "https://github.com/zkmopro/mopro", features = ["flutter"] }"#
+        );
+        assert!(
+            MoproFfiSource::Path(PathBuf::from("/nonexistent/mopro-ffi"))
+                .dependency()
+                .is_err()
+        );
+
+        // In the repository the default is the mopro-ffi crate next to the CLI
+        let mopro_ffi_dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("../mopro-ffi");
+        let expected = if mopro_ffi_dir.join("Cargo.toml").is_file() {
+            MoproFfiSource::Path(mopro_ffi_dir)
+        } else {
+            MoproFfiSource::Git(MOPRO_FFI_GIT.to_string())
+        };
+        assert_eq!(MoproFfiSource::default(), expected);
+    }
+
+    #[test]
+    fn test_scaffold_builds() {
+        let parent = TempDir::new("check");
+        let project_dir = init_project(
+            "my-app",
+            &parent.0,
+            &[Backend::Halo2],
+            &MoproFfiSource::default(),
+            false,
+        )
+        .unwrap();
+        let cargo = || {
+            let mut command = Command::new(env!("CARGO"));
+            command
+                .current_dir(&project_dir)
+                .env("CARGO_TARGET_DIR", parent.0.join("target"))
+                .env("CARGO_NET_RETRY", "0");
+            command
+        };
+
+        // The dependencies of the samples come from git and crates.io, skip when offline
+        let fetched = cargo().arg("fetch").status().unwrap();
+        if !fetched.success() {
+            eprintln!("Skipping, the dependencies of the scaffold could not be fetched");
+            return;
+        }
+        let checked = cargo()
+            .args(["check", "--offline", "--lib", "--bins"])
+            .status()
+            .unwrap();
+        assert!(checked.success(), "The scaffold does not build");
+    }
+
+    #[test]
+    fn test_templates_match_test_e2e() {
+        let test_e2e = Path::new(env!("CARGO_MANIFEST_DIR")).join("../test-e2e");
+        // Only checked in the repository, the published crate has the templates alone
+        if !test_e2e.exists() {
+            return;
+        }
+        let read = |path: &str| fs::read(test_e2e.join(path)).unwrap();
+
+        assert_eq!(CIRCOM_MODULE.as_bytes(), read("src/circom.rs"));
+        assert_eq!(HALO2_MODULE.as_bytes(), read("src/halo2.rs"));
+        assert_eq!(NOIR_MODULE.as_bytes(), read("src/noir.rs"));
+        assert_eq!(BUILD_SCRIPT.as_bytes(), read("build.rs"));
+        assert_eq!(GITIGNORE.as_bytes(), read(".gitignore"));
+        for (bin_name, source) in BINARIES {
+            assert_eq!(source.as_bytes(), read(&format!("bin/{bin_name}.rs")));
+        }
+        for backend in [Backend::Circom, Backend::Halo2, Backend::Noir] {
+            for (file_name, content) in backend.test_vectors() {
+                assert_eq!(content, read(&format!("test-vectors/{file_name}")));
+            }
+        }
+    }
+
+    #[test]
+    fn test_init_refuses_existing_directory() {
+        let parent = TempDir::new("existing");
+        fs::create_dir_all(parent.0.join("my-app")).unwrap();
+
+        let mopro_ffi = MoproFfiSource::Git(MOPRO_FFI_GIT.to_string());
+        assert!(init_project("my-app", &parent.0, &[Backend::Circom], &mopro_ffi, false).is_err());
+        assert!(init_project("my-app", &parent.0, &[Backend::Circom], &mopro_ffi, true).is_ok());
+    }
+}
@@ -1,6 +1,31 @@
-use clap::{Parser, Subcommand};
+mod bench;
+mod doctor;
+mod init;
+mod platforms;
+mod prove;
+mod watch;
+
+use anyhow::Context;
+use clap::{error::ErrorKind, CommandFactory, Parser, Subcommand};
+use mopro_ffi::bindings::{
+    self,
+    config::MoproConfig,
+    constants::{
+        AndroidArch, AndroidPlatform, Arch, BuildContext, BuildOptions, CargoFlags, DesktopArch,
+        DesktopPlatform, FlutterArch, FlutterPlatform, IosArch, IosPlatform, JvmArch, JvmPlatform,
+        Mode, NodeArch, NodePlatform, PlatformBuilder, PythonArch, PythonPlatform, ReactNativeArch,
+        ReactNativePlatform, WebArch, WebPlatform,
+    },
+    observer::{BuildObserver, ConsoleObserver, JsonLinesObserver},
+    plan::BuildPlan,
+    runner::{CommandRunner, DryRunRunner, SystemRunner},
+};
+use std::path::{Path, PathBuf};
 use std::process::Command;
-use tracing::{info, error};
+use tracing::{error, info};
+
+use crate::init::{Backend, MoproFfiSource};
+use crate::prove::ProverArgs;
 
 #[derive(Parser)]
 #[command(name = "mopro")]
@@ -15,18 +40,196 @@ struct Cli {
 enum Commands {
     /// Build FFI bindings for a specific platform
     Build {
-        /// Target platform (ios, android, web, flutter, react-native)
+        /// Target platform (ios, android, web, flutter, react-native),
+        /// defaults to the platforms listed in mopro.toml
         #[arg(value_enum)]
-        platform: Platform,
-        
-        /// Output directory for generated bindings
-        #[arg(short, long, default_value = "bindings")]
-        output: String,
+        platform: Option<Platform>,
+
+        /// Path to the crate to generate bindings for, or to its workspace
+        #[arg(long, default_value = ".")]
+        project_dir: PathBuf,
+
+        /// Package of the workspace to generate bindings for (defaults to `package` in
+        /// mopro.toml, or the crate at the project directory)
+        #[arg(short, long)]
+        package: Option<String>,
+
+        /// Output directory for generated bindings (defaults to the project directory)
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+
+        /// Target architecture to build, can be repeated (defaults to the platform's default architectures).
+        /// Only accepted when a single platform is built
+        #[arg(long = "arch", value_name = "ARCH")]
+        archs: Vec<String>,
+
+        /// Build in release mode
+        #[arg(long, conflicts_with = "debug")]
+        release: bool,
+
+        /// Build in debug mode (default)
+        #[arg(long)]
+        debug: bool,
+
+        /// Build with a custom cargo profile of the crate, e.g. `mobile` for `[profile.mobile]`
+        #[arg(long, value_parser = parse_profile, conflicts_with_all = ["release", "debug"])]
+        profile: Option<Mode>,
+
+        /// Cargo features to enable, comma separated or repeated (replaces `features` in
+        /// mopro.toml)
+        #[arg(short = 'F', long, value_delimiter = ',')]
+        features: Vec<String>,
+
+        /// Do not enable the default features of the crate
+        #[arg(long)]
+        no_default_features: bool,
+
+        /// Enable every feature of the crate
+        #[arg(long)]
+        all_features: bool,
+
+        /// RUSTFLAGS of every target, e.g. "-C target-cpu=native" (replaces `rustflags` in
+        /// mopro.toml)
+        #[arg(long, allow_hyphen_values = true)]
+        rustflags: Option<String>,
+
+        /// Number of architectures compiled in parallel (defaults to `jobs` in mopro.toml, or 1)
+        #[arg(short, long)]
+        jobs: Option<usize>,
+
+        /// Fail with the list of missing rust targets and tools instead of installing them
+        /// (the default in CI)
+        #[arg(long)]
+        offline: bool,
+
+        /// Install missing rust targets and tools, also in CI
+        #[arg(long, conflicts_with = "offline")]
+        allow_install: bool,
+
+        /// Rebuild even if the sources did not change since the last build
+        #[arg(long)]
+        force: bool,
+
+        /// Keep running and rebuild whenever the crate sources, Cargo.toml or test vectors change
+        #[arg(long)]
+        watch: bool,
+
+        /// Print the commands of the build with their environment and working directory
+        /// instead of running them
+        #[arg(long, conflicts_with = "watch")]
+        dry_run: bool,
+
+        /// Print the plan of the build (commands, architecture groups, generated bindings and
+        /// destination paths) without building anything
+        #[arg(long, conflicts_with_all = ["watch", "dry_run"])]
+        plan: bool,
+
+        /// Print the plan as JSON
+        #[arg(long, requires = "plan")]
+        json: bool,
+
+        /// Format of the build progress: `human`, or `json` for one JSON event per line on
+        /// stdout, with the output of the tools on stderr
+        #[arg(long, value_enum, default_value = "human", conflicts_with_all = ["dry_run", "plan"])]
+        message_format: MessageFormat,
+    },
+
+    /// Check that the tools needed to build the platform's bindings are installed
+    Doctor {
+        /// Platform to check, defaults to the platforms listed in mopro.toml or all platforms
+        #[arg(value_enum)]
+        platform: Option<Platform>,
+
+        /// Path to the crate whose mopro.toml selects the architectures to check
+        #[arg(long, default_value = ".")]
+        project_dir: PathBuf,
     },
-    
+
+    /// Create a new proving crate set up for mopro bindings
+    Init {
+        /// Name of the crate to create
+        name: String,
+
+        /// Proving backends to include, each with a sample circuit
+        #[arg(
+            long,
+            value_enum,
+            value_delimiter = ',',
+            default_value = "circom,halo2,noir"
+        )]
+        backends: Vec<Backend>,
+
+        /// Path to a local mopro-ffi crate to depend on instead of the one the CLI is built with
+        #[arg(long, conflicts_with = "mopro_ffi_git")]
+        mopro_ffi_path: Option<PathBuf>,
+
+        /// Git repository of the mopro-ffi crate to depend on instead of the one the CLI is built with
+        #[arg(long)]
+        mopro_ffi_git: Option<String>,
+
+        /// Overwrite the files of an existing directory
+        #[arg(long)]
+        force: bool,
+    },
+
+    /// Generate a proof on this machine with the project's proving functions
+    Prove {
+        /// Proving backend
+        #[arg(value_enum)]
+        backend: Backend,
+
+        /// JSON file with the circuit inputs
+        #[arg(long)]
+        inputs: PathBuf,
+
+        #[command(flatten)]
+        args: ProverArgs,
+    },
+
+    /// Verify a proof written by `mopro prove`
+    Verify {
+        /// Proving backend
+        #[arg(value_enum)]
+        backend: Backend,
+
+        #[command(flatten)]
+        args: ProverArgs,
+    },
+
+    /// Benchmark the proving backends configured in the project's bench.toml
+    Bench {
+        /// Path to the crate with the bench.toml and the proving functions
+        #[arg(long, default_value = ".")]
+        project_dir: PathBuf,
+
+        /// Number of runs per case (defaults to `iterations` in bench.toml, or 5)
+        #[arg(short = 'n', long)]
+        iterations: Option<usize>,
+
+        /// Case to run, can be repeated (defaults to all cases)
+        #[arg(long = "case", value_name = "NAME")]
+        cases: Vec<String>,
+
+        /// Directory the report.json and report.md are written to
+        #[arg(short, long, default_value = "bench-results")]
+        output: PathBuf,
+
+        /// report.json of an earlier run to compare against
+        #[arg(long)]
+        baseline: Option<PathBuf>,
+
+        /// Growth of a metric compared to the baseline, in percent, that counts as a regression
+        #[arg(long, default_value_t = 10.0)]
+        threshold: f64,
+    },
+
     /// List available platforms
-    Platforms,
-    
+    Platforms {
+        /// Print the platforms as JSON
+        #[arg(long)]
+        json: bool,
+    },
+
     /// Run tests
     Test {
         /// Run end-to-end tests
@@ -42,6 +245,16 @@ enum Platform {
     Web,
     Flutter,
     ReactNative,
+    Node,
+    Jvm,
+    Desktop,
+    Python,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+enum MessageFormat {
+    Human,
+    Json,
 }
 
 impl std::fmt::Display for Platform {
@@ -52,23 +265,274 @@ impl std::fmt::Display for Platform {
             Platform::Web => write!(f, "web"),
             Platform::Flutter => write!(f, "flutter"),
             Platform::ReactNative => write!(f, "react-native"),
+            Platform::Node => write!(f, "node"),
+            Platform::Jvm => write!(f, "jvm"),
+            Platform::Desktop => write!(f, "desktop"),
+            Platform::Python => write!(f, "python"),
         }
     }
 }
 
+impl Platform {
+    /// Supported architectures of the platform with their descriptions.
+    fn archs(&self) -> Vec<(String, String)> {
+        match self {
+            Platform::Ios => IosArch::all_display_strings(),
+            Platform::Android => AndroidArch::all_display_strings(),
+            Platform::Web => WebArch::all_display_strings(),
+            Platform::Flutter => FlutterArch::all_display_strings(),
+            Platform::ReactNative => ReactNativeArch::all_display_strings(),
+            Platform::Node => NodeArch::all_display_strings(),
+            Platform::Jvm => JvmArch::all_display_strings(),
+            Platform::Desktop => DesktopArch::all_display_strings(),
+            Platform::Python => PythonArch::all_display_strings(),
+        }
+    }
+
+    /// Checks the requested architectures against the platform's architecture table.
+    fn validate_archs(&self, archs: &[String]) -> Result<(), clap::Error> {
+        let supported = self.archs();
+        let invalid = archs.iter().find(|arch| {
+            !supported
+                .iter()
+                .any(|(name, _)| name.eq_ignore_ascii_case(arch))
+        });
+
+        if let Some(arch) = invalid {
+            let mut message =
+                format!("invalid value '{arch}' for '--arch <ARCH>' on platform '{self}'\n\n");
+            message.push_str("  possible values:\n");
+            for (name, description) in supported {
+                message.push_str(&format!("    {name:<26} {description}\n"));
+            }
+            return Err(build_error(ErrorKind::InvalidValue, message));
+        }
+
+        Ok(())
+    }
+}
+
+/// Usage error of the `build` subcommand, printed with its help like clap's own errors.
+fn build_error(kind: ErrorKind, message: impl std::fmt::Display) -> clap::Error {
+    let mut cli = Cli::command();
+    cli.build();
+    let build = cli
+        .find_subcommand_mut("build")
+        .expect("build subcommand is defined");
+    build.error(kind, message)
+}
+
 #[tokio::main]
 async fn main() -> anyhow::Result<()> {
-    // Initialize logging
-    tracing_subscriber::fmt::init();
-    
     let cli = Cli::parse();
-    
+
+    // Initialize logging, on stderr when stdout carries the JSON build events
+    let json_events = matches!(
+        cli.command,
+        Commands::Build {
+            message_format: MessageFormat::Json,
+            ..
+        }
+    );
+    if json_events {
+        tracing_subscriber::fmt()
+            .with_writer(std::io::stderr)
+            .init();
+    } else {
+        tracing_subscriber::fmt::init();
+    }
+
     match cli.command {
-        Commands::Build { platform, output } => {
-            build_bindings(platform, &output).await?;
+        Commands::Build {
+            platform,
+            project_dir,
+            package,
+            output,
+            archs,
+            release,
+            debug,
+            profile,
+            features,
+            no_default_features,
+            all_features,
+            rustflags,
+            jobs,
+            offline,
+            allow_install,
+            force,
+            watch,
+            dry_run,
+            plan,
+            json,
+            message_format,
+        } => {
+            let project_dir = project_dir.canonicalize().context(format!(
+                "Project directory {} does not exist",
+                project_dir.display()
+            ))?;
+            let config = MoproConfig::load(&project_dir)?;
+
+            let platforms = match platform {
+                Some(platform) => vec![platform],
+                None => config_platforms(&config)?,
+            };
+            // Architecture names differ between platforms
+            if !archs.is_empty() && platforms.len() != 1 {
+                build_error(
+                    ErrorKind::ArgumentConflict,
+                    format!(
+                        "'--arch <ARCH>' needs a single platform, but {} are selected; pass the platform to build",
+                        platforms.len()
+                    ),
+                )
+                .exit();
+            }
+            for platform in &platforms {
+                if let Err(e) = platform.validate_archs(&archs) {
+                    e.exit();
+                }
+            }
+
+            let cli_mode = match (release, debug) {
+                (true, _) => Some(Mode::Release),
+                (_, true) => Some(Mode::Debug),
+                _ => profile,
+            };
+            let cli_cargo = CargoFlags {
+                features,
+                no_default_features,
+                all_features,
+                rustflags: rustflags
+                    .iter()
+                    .flat_map(|flags| flags.split_whitespace())
+                    .map(str::to_string)
+                    .collect(),
+                ..CargoFlags::default()
+            };
+            let options = BuildOptions {
+                mode: bindings::resolve_mode(cli_mode, &config)?,
+                jobs: bindings::resolve_jobs(jobs, &config)?,
+                offline: bindings::resolve_offline(
+                    (offline || allow_install).then_some(offline),
+                    &config,
+                )?,
+                cargo: bindings::resolve_cargo_flags(&cli_cargo, &config),
+                package: package.or(config.package.clone()),
+            };
+            let output = output
+                .map(std::path::absolute)
+                .transpose()
+                .context("Failed to resolve output directory")?;
+            let output = bindings::resolve_output_dir(output.as_deref(), &project_dir, &config);
+
+            let runner: &dyn CommandRunner = match message_format {
+                _ if dry_run => &DryRunRunner,
+                MessageFormat::Human => &SystemRunner::new(),
+                MessageFormat::Json => &SystemRunner::new().stdout_to_stderr(),
+            };
+            let observer: &dyn BuildObserver = match message_format {
+                MessageFormat::Human => &ConsoleObserver::default(),
+                MessageFormat::Json => &JsonLinesObserver::new(std::io::stdout()),
+            };
+            let settings = BuildSettings {
+                options,
+                archs: &archs,
+                project_dir: &project_dir,
+                output: &output,
+                config: &config,
+                force,
+                context: BuildContext::new(runner, observer),
+            };
+            if plan {
+                let plans = platforms
+                    .iter()
+                    .map(|platform| plan_bindings(*platform, &settings))
+                    .collect::<anyhow::Result<Vec<_>>>()?;
+                if json {
+                    println!("{}", serde_json::to_string_pretty(&plans)?);
+                } else {
+                    for plan in &plans {
+                        print!("{plan}");
+                    }
+                }
+                return Ok(());
+            }
+
+            let (settings, platforms) = (&settings, &platforms);
+            let build_all = move || async move {
+                for platform in platforms {
+                    build_bindings(*platform, settings).await?;
+                }
+                Ok(())
+            };
+            if watch {
+                watch::watch(&project_dir, build_all).await?;
+            } else {
+                build_all().await?;
+            }
+        }
+        Commands::Doctor {
+            platform,
+            project_dir,
+        } => {
+            let config = MoproConfig::load(&project_dir)?;
+            let platforms = match platform {
+                Some(platform) => vec![platform],
+                None if !config.platforms.is_empty() => config_platforms(&config)?,
+                None => <Platform as clap::ValueEnum>::value_variants().to_vec(),
+            };
+            if !doctor::run_doctor(&platforms, &config)? {
+                std::process::exit(1);
+            }
         }
-        Commands::Platforms => {
-            list_platforms();
+        Commands::Init {
+            name,
+            backends,
+            mopro_ffi_path,
+            mopro_ffi_git,
+            force,
+        } => {
+            let mopro_ffi = match (mopro_ffi_path, mopro_ffi_git) {
+                (Some(path), _) => MoproFfiSource::Path(path),
+                (None, Some(url)) => MoproFfiSource::Git(url),
+                (None, None) => MoproFfiSource::default(),
+            };
+            let project_dir =
+                init::init_project(&name, Path::new("."), &backends, &mopro_ffi, force)?;
+            info!("✅ Created {}", project_dir.display());
+            info!("Run `cargo test` in {} to check the setup", name);
+        }
+        Commands::Prove {
+            backend,
+            inputs,
+            args,
+        } => {
+            prove::run_prover(backend, "prove", &args, Some(&inputs))?;
+            info!("✅ Proof written to {}", args.proof_dir.display());
+        }
+        Commands::Verify { backend, args } => {
+            prove::run_prover(backend, "verify", &args, None)?;
+            info!("✅ Proof verified");
+        }
+        Commands::Bench {
+            project_dir,
+            iterations,
+            cases,
+            output,
+            baseline,
+            threshold,
+        } => {
+            run_bench(
+                &project_dir,
+                iterations,
+                &cases,
+                &output,
+                baseline.as_deref(),
+                threshold,
+            )?;
+        }
+        Commands::Platforms { json } => {
+            platforms::list_platforms(json)?;
         }
         Commands::Test { e2e } => {
             if e2e {
@@ -78,74 +542,209 @@ async fn main() -> anyhow::Result<()> {
             }
         }
     }
-    
+
     Ok(())
 }
 
-async fn build_bindings(platform: Platform, _output: &str) -> anyhow::Result<()> {
-    info!("Building bindings for platform: {}", platform);
-    
-    // Change to mopro-ffi directory
-    std::env::set_current_dir("mopro-ffi")?;
-    
-    let status = Command::new("cargo")
-        .args(["run", "--bin", &platform.to_string()])
-        .status()?;
-    
-    if status.success() {
-        info!("✅ Successfully built bindings for {}", platform);
-    } else {
-        error!("❌ Failed to build bindings for {}", platform);
-        std::process::exit(1);
+/// Platforms listed in the `[build]` section of mopro.toml.
+fn config_platforms(config: &MoproConfig) -> anyhow::Result<Vec<Platform>> {
+    if config.platforms.is_empty() {
+        return Err(anyhow::anyhow!(
+            "No platform given. Pass one to `mopro build` or list them in `platforms` under [build] in mopro.toml"
+        ));
     }
-    
-    Ok(())
+    config
+        .platforms
+        .iter()
+        .map(|name| {
+            <Platform as clap::ValueEnum>::from_str(name, true)
+                .map_err(|_| anyhow::anyhow!("Unsupported platform '{name}' in mopro.toml"))
+        })
+        .collect()
+}
+
+/// Parses the `--profile` of a build.
+fn parse_profile(profile: &str) -> Result<Mode, String> {
+    Mode::parse(profile).ok_or(format!("'{profile}' is not a valid cargo profile name"))
+}
+
+/// Settings of `mopro build` shared by the builds of every platform.
+struct BuildSettings<'a> {
+    options: BuildOptions,
+    /// Architectures given on the command line
+    archs: &'a [String],
+    project_dir: &'a Path,
+    output: &'a Path,
+    config: &'a MoproConfig,
+    force: bool,
+    context: BuildContext<'a>,
+}
+
+async fn build_bindings(platform: Platform, settings: &BuildSettings<'_>) -> anyhow::Result<()> {
+    info!(
+        "Building bindings for platform: {} ({})",
+        platform,
+        settings.options.mode.as_str()
+    );
+
+    let result = match platform {
+        Platform::Ios => build_platform::<IosPlatform>(settings),
+        Platform::Android => build_platform::<AndroidPlatform>(settings),
+        Platform::Web => build_platform::<WebPlatform>(settings),
+        Platform::Flutter => build_platform::<FlutterPlatform>(settings),
+        Platform::ReactNative => build_platform::<ReactNativePlatform>(settings),
+        Platform::Node => build_platform::<NodePlatform>(settings),
+        Platform::Jvm => build_platform::<JvmPlatform>(settings),
+        Platform::Desktop => build_platform::<DesktopPlatform>(settings),
+        Platform::Python => build_platform::<PythonPlatform>(settings),
+    };
+
+    match result {
+        Ok(_) if settings.context.runner.is_dry_run() => {
+            info!("✅ Dry run for {} finished, nothing was built", platform);
+            Ok(())
+        }
+        Ok(bindings_dir) => {
+            info!(
+                "✅ Successfully built bindings for {} in {}",
+                platform,
+                bindings_dir.display()
+            );
+            Ok(())
+        }
+        Err(e) => {
+            error!("❌ Failed to build bindings for {}", platform);
+            Err(e)
+        }
+    }
+}
+
+fn plan_bindings(platform: Platform, settings: &BuildSettings<'_>) -> anyhow::Result<BuildPlan> {
+    match platform {
+        Platform::Ios => plan_platform::<IosPlatform>(settings),
+        Platform::Android => plan_platform::<AndroidPlatform>(settings),
+        Platform::Web => plan_platform::<WebPlatform>(settings),
+        Platform::Flutter => plan_platform::<FlutterPlatform>(settings),
+        Platform::ReactNative => plan_platform::<ReactNativePlatform>(settings),
+        Platform::Node => plan_platform::<NodePlatform>(settings),
+        Platform::Jvm => plan_platform::<JvmPlatform>(settings),
+        Platform::Desktop => plan_platform::<DesktopPlatform>(settings),
+        Platform::Python => plan_platform::<PythonPlatform>(settings),
+    }
+}
+
+/// Computes the plan of [`build_platform`] without running anything.
+fn plan_platform<Builder: PlatformBuilder>(
+    settings: &BuildSettings<'_>,
+) -> anyhow::Result<BuildPlan> {
+    let target_archs = bindings::resolve_target_archs::<Builder>(settings.archs, settings.config)?;
+    let params = bindings::resolve_params::<Builder>(settings.config)?;
+
+    let plan = bindings::plan_from_str_arch::<Builder>(
+        &settings.options,
+        settings.project_dir,
+        settings.output,
+        target_archs.iter().collect(),
+        &params,
+        settings.force,
+    )?;
+    Ok(plan)
 }
 
-fn list_platforms() {
-    println!("Available platforms:");
-    println!("  ios          - Generate Swift bindings for iOS");
-    println!("  android      - Generate Kotlin bindings for Android");
-    println!("  web          - Generate JavaScript bindings for Web/WASM");
-    println!("  flutter      - Generate Dart bindings for Flutter");
-    println!("  react-native - Generate JavaScript bindings for React Native");
-    println!();
-    println!("Usage: mopro build <platform>");
+/// Builds the bindings in-process for the given architectures. Architectures and
+/// platform parameters not given on the command line are taken from mopro.toml.
+fn build_platform<Builder: PlatformBuilder>(
+    settings: &BuildSettings<'_>,
+) -> anyhow::Result<PathBuf> {
+    let target_archs = bindings::resolve_target_archs::<Builder>(settings.archs, settings.config)?;
+    let params = bindings::resolve_params::<Builder>(settings.config)?;
+
+    let bindings_dir = bindings::build_from_str_arch::<Builder>(
+        &settings.options,
+        settings.project_dir,
+        settings.output,
+        target_archs.iter().collect(),
+        params,
+        settings.force,
+        settings.context,
+    )?;
+    Ok(bindings_dir)
+}
+
+fn run_bench(
+    project_dir: &Path,
+    iterations: Option<usize>,
+    cases: &[String],
+    output: &Path,
+    baseline: Option<&Path>,
+    threshold: f64,
+) -> anyhow::Result<()> {
+    // Load the baseline first, so a wrong path does not waste a benchmark run
+    let baseline: Option<bench::BenchReport> = baseline
+        .map(|path| -> anyhow::Result<_> {
+            let content = std::fs::read_to_string(path)
+                .context(format!("Failed to read {}", path.display()))?;
+            serde_json::from_str(&content).context(format!("Failed to parse {}", path.display()))
+        })
+        .transpose()?;
+
+    let config = bench::BenchConfig::load(project_dir)?;
+    let report = bench::run_bench(project_dir, &config, iterations, cases)?;
+    let regressions = baseline
+        .as_ref()
+        .map(|baseline| bench::compare(&report, baseline, threshold));
+    let markdown = bench::markdown(&report, regressions.as_deref());
+
+    std::fs::create_dir_all(output).context(format!("Failed to create {}", output.display()))?;
+    std::fs::write(
+        output.join("report.json"),
+        serde_json::to_string_pretty(&report)?,
+    )?;
+    std::fs::write(output.join("report.md"), &markdown)?;
+    println!("{markdown}");
+    info!("✅ Benchmark report written to {}", output.display());
+
+    match regressions {
+        Some(regressions) if !regressions.is_empty() => {
+            error!(
+                "❌ {} regression(s) past the {threshold}% threshold",
+                regressions.len()
+            );
+            std::process::exit(1);
+        }
+        _ => Ok(()),
+    }
 }
 
 async fn run_unit_tests() -> anyhow::Result<()> {
     info!("Running unit tests...");
-    
-    let status = Command::new("cargo")
-        .args(["test"])
-        .status()?;
-    
+
+    let status = Command::new("cargo").args(["test"]).status()?;
+
     if status.success() {
         info!("✅ All unit tests passed");
     } else {
         error!("❌ Some unit tests failed");
         std::process::exit(1);
     }
-    
+
     Ok(())
 }
 
 async fn run_e2e_tests() -> anyhow::Result<()> {
     info!("Running end-to-end tests...");
-    
+
     // Change to test-e2e directory
     std::env::set_current_dir("test-e2e")?;
-    
-    let status = Command::new("cargo")
-        .args(["test"])
-        .status()?;
-    
+
+    let status = Command::new("cargo").args(["test"]).status()?;
+
     if status.success() {
         info!("✅ All end-to-end tests passed");
     } else {
         error!("❌ Some end-to-end tests failed");
         std::process::exit(1);
     }
-    
+
     Ok(())
 }
@@ -0,0 +1,78 @@
+use mopro_ffi::bindings::constants::{
+    AndroidPlatform, Arch, DesktopPlatform, FlutterPlatform, IosPlatform, JvmPlatform,
+    NodePlatform, PlatformBuilder, PythonPlatform, ReactNativePlatform, WebPlatform,
+    BUILD_MODE_ENV,
+};
+use serde::Serialize;
+
+/// Entry of `mopro platforms`, generated from the platform builders and their arch tables.
+#[derive(Serialize)]
+struct PlatformListing {
+    name: &'static str,
+    identifier: &'static str,
+    cargo_feature: &'static str,
+    default_bindings_dir: &'static str,
+    archs_env_var: &'static str,
+    mode_env_var: &'static str,
+    archs: Vec<ArchListing>,
+}
+
+#[derive(Serialize)]
+struct ArchListing {
+    name: String,
+    description: String,
+}
+
+fn platform_listing<Builder: PlatformBuilder>() -> PlatformListing {
+    PlatformListing {
+        name: Builder::name(),
+        identifier: Builder::identifier(),
+        cargo_feature: Builder::cargo_feature(),
+        default_bindings_dir: Builder::default_bindings_dir(),
+        archs_env_var: Builder::Arch::env_var_name(),
+        mode_env_var: BUILD_MODE_ENV,
+        archs: Builder::Arch::all_display_strings()
+            .into_iter()
+            .map(|(name, description)| ArchListing { name, description })
+            .collect(),
+    }
+}
+
+/// Prints the platforms that have a bindings builder, as text or as JSON for scripts.
+pub fn list_platforms(json: bool) -> anyhow::Result<()> {
+    let platforms = vec![
+        platform_listing::<IosPlatform>(),
+        platform_listing::<AndroidPlatform>(),
+        platform_listing::<WebPlatform>(),
+        platform_listing::<FlutterPlatform>(),
+        platform_listing::<ReactNativePlatform>(),
+        platform_listing::<NodePlatform>(),
+        platform_listing::<JvmPlatform>(),
+        platform_listing::<DesktopPlatform>(),
+        platform_listing::<PythonPlatform>(),
+    ];
+
+    if json {
+        println!("{}", serde_json::to_string_pretty(&platforms)?);
+        return Ok(());
+    }
+
+    println!("Available platforms:");
+    for platform in &platforms {
+        println!();
+        println!("  {} - {}", platform.name, platform.identifier);
+        println!("    cargo feature: {}", platform.cargo_feature);
+        println!("    bindings dir:  {}", platform.default_bindings_dir);
+        println!(
+            "    env vars:      {}, {}",
+            platform.archs_env_var, platform.mode_env_var
+        );
+        println!("    archs:");
+        for arch in &platform.archs {
+            println!("      {:<26} {}", arch.name, arch.description);
+        }
+    }
+    println!();
+    println!("Usage: mopro build <platform> [--arch <ARCH>]...");
+    Ok(())
+}
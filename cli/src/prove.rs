@@ -0,0 +1,126 @@
+use anyhow::Context;
+use std::ffi::OsString;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use tracing::info;
+
+use crate::init::Backend;
+
+/// Key, circuit and prover settings shared by `mopro prove` and `mopro verify`.
+#[derive(clap::Args)]
+pub struct ProverArgs {
+    /// Path to the crate that exports the proving functions
+    #[arg(long, default_value = ".")]
+    pub project_dir: PathBuf,
+
+    /// Circom: path to the zkey
+    #[arg(long)]
+    pub zkey: Option<PathBuf>,
+
+    /// Circom: proving library
+    #[arg(long, value_parser = ["arkworks", "rapidsnark"])]
+    pub proof_lib: Option<String>,
+
+    /// Halo2 and Noir: path to the SRS
+    #[arg(long)]
+    pub srs: Option<PathBuf>,
+
+    /// Halo2: path to the proving key
+    #[arg(long)]
+    pub pk: Option<PathBuf>,
+
+    /// Halo2 and Noir: path to the verification key (generated from the circuit for Noir if omitted)
+    #[arg(long)]
+    pub vk: Option<PathBuf>,
+
+    /// Noir: path to the compiled circuit JSON
+    #[arg(long)]
+    pub circuit: Option<PathBuf>,
+
+    /// Noir: use the Keccak oracle hash, for proofs verified on-chain
+    #[arg(long)]
+    pub on_chain: bool,
+
+    /// Noir: use barretenberg's low memory mode
+    #[arg(long)]
+    pub low_memory: bool,
+
+    /// Directory the proof files are written to and read from
+    #[arg(long, default_value = "proof")]
+    pub proof_dir: PathBuf,
+}
+
+/// Runs the project's `prover` binary, which calls the same proving functions the bindings
+/// export. Paths are passed through as given, relative to the current directory.
+pub fn run_prover(
+    backend: Backend,
+    command: &str,
+    args: &ProverArgs,
+    inputs: Option<&Path>,
+) -> anyhow::Result<()> {
+    let manifest_path = args.project_dir.join("Cargo.toml");
+    if !manifest_path.exists() {
+        return Err(anyhow::anyhow!(
+            "No Cargo.toml found in {}",
+            args.project_dir.display()
+        ));
+    }
+
+    let mut cmd = Command::new("cargo");
+    cmd.args(["run", "--release", "--quiet", "--bin", "prover"])
+        .arg("--manifest-path")
+        .arg(&manifest_path)
+        .arg("--")
+        .args(prover_args(backend, command, args, inputs));
+
+    info!(
+        "Running the {} prover of {}",
+        backend.feature(),
+        args.project_dir.display()
+    );
+    let status = cmd.status().context("Failed to run cargo")?;
+    if !status.success() {
+        return Err(anyhow::anyhow!(
+            "{} {command} failed ({status})",
+            backend.feature()
+        ));
+    }
+    Ok(())
+}
+
+/// Command line of the `prover` binary for the given command.
+pub fn prover_args(
+    backend: Backend,
+    command: &str,
+    args: &ProverArgs,
+    inputs: Option<&Path>,
+) -> Vec<OsString> {
+    let mut prover_args: Vec<OsString> = vec![backend.feature().into(), command.into()];
+
+    let paths = [
+        ("zkey", args.zkey.as_deref()),
+        ("srs", args.srs.as_deref()),
+        ("pk", args.pk.as_deref()),
+        ("vk", args.vk.as_deref()),
+        ("circuit", args.circuit.as_deref()),
+        ("inputs", inputs),
+        ("proof-dir", Some(args.proof_dir.as_path())),
+    ];
+    for (name, path) in paths {
+        if let Some(path) = path {
+            prover_args.push(format!("--{name}").into());
+            prover_args.push(path.into());
+        }
+    }
+    if let Some(proof_lib) = &args.proof_lib {
+        prover_args.push("--proof-lib".into());
+        prover_args.push(proof_lib.into());
+    }
+    if args.on_chain {
+        prover_args.push("--on-chain".into());
+    }
+    if args.low_memory {
+        prover_args.push("--low-memory".into());
+    }
+    prover_args
+}
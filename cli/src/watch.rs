@@ -0,0 +1,138 @@
+use notify::{EventKind, RecursiveMode, Watcher};
+use std::future::Future;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use tokio::sync::mpsc::{self, UnboundedReceiver};
+use tracing::{error, info};
+
+/// Quiet period after the last change before the bindings are rebuilt.
+pub const DEBOUNCE: Duration = Duration::from_millis(500);
+
+/// Directories of the project crate watched recursively.
+const WATCHED_DIRS: [&str; 2] = ["src", "test-vectors"];
+
+/// Files in the project root watched for changes.
+const WATCHED_FILES: [&str; 2] = ["Cargo.toml", "mopro.toml"];
+
+/// Builds the bindings, then rebuilds them whenever the sources, manifest or test vectors
+/// of the project change. Failed builds are reported and watching goes on.
+pub async fn watch<F, Fut>(project_dir: &Path, build: F) -> anyhow::Result<()>
+where
+    F: FnMut() -> Fut,
+    Fut: Future<Output = anyhow::Result<()>>,
+{
+    let (sender, events) = mpsc::unbounded_channel();
+    let watched_root = project_dir.to_path_buf();
+    let mut watcher = notify::recommended_watcher(move |event: notify::Result<notify::Event>| {
+        let Ok(event) = event else {
+            return;
+        };
+        if matches!(event.kind, EventKind::Access(_)) {
+            return;
+        }
+        for path in event.paths {
+            if is_watched(&watched_root, &path) {
+                // The receiver is only gone once watching stopped
+                let _ = sender.send(path);
+            }
+        }
+    })?;
+
+    // The root is watched non-recursively, so that files replaced by editors are still seen
+    watcher.watch(project_dir, RecursiveMode::NonRecursive)?;
+    for dir in WATCHED_DIRS {
+        let dir = project_dir.join(dir);
+        if dir.is_dir() {
+            watcher.watch(&dir, RecursiveMode::Recursive)?;
+        }
+    }
+
+    run_watch_loop(project_dir, events, DEBOUNCE, build).await;
+    Ok(())
+}
+
+/// Runs `build` once and then once per burst of changes. Changes made while a build is
+/// running are queued, so they trigger exactly one follow-up build once it is done.
+/// Returns when the channel of changes is closed.
+async fn run_watch_loop<F, Fut>(
+    project_dir: &Path,
+    mut events: UnboundedReceiver<PathBuf>,
+    debounce: Duration,
+    mut build: F,
+) where
+    F: FnMut() -> Fut,
+    Fut: Future<Output = anyhow::Result<()>>,
+{
+    loop {
+        if let Err(e) = build().await {
+            error!("❌ Build failed: {e:#}");
+        }
+        info!("Watching {} for changes...", project_dir.display());
+
+        let Some(path) = events.recv().await else {
+            return;
+        };
+        info!("Change detected in {}", path.display());
+        while let Ok(Some(_)) = tokio::time::timeout(debounce, events.recv()).await {}
+    }
+}
+
+fn is_watched(project_dir: &Path, path: &Path) -> bool {
+    let Ok(relative) = path.strip_prefix(project_dir) else {
+        return false;
+    };
+    WATCHED_FILES.iter().any(|file| relative == Path::new(file))
+        || WATCHED_DIRS.iter().any(|dir| relative.starts_with(dir))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::cell::{Cell, RefCell};
+
+    #[test]
+    fn test_is_watched() {
+        let project_dir = Path::new("/project");
+        assert!(is_watched(project_dir, Path::new("/project/src/lib.rs")));
+        assert!(is_watched(project_dir, Path::new("/project/Cargo.toml")));
+        assert!(is_watched(
+            project_dir,
+            Path::new("/project/test-vectors/circom/multiplier2_final.zkey")
+        ));
+        assert!(!is_watched(project_dir, Path::new("/project/Cargo.lock")));
+        assert!(!is_watched(
+            project_dir,
+            Path::new("/project/MoproiOSBindings/mopro.swift")
+        ));
+        assert!(!is_watched(project_dir, Path::new("/other/src/lib.rs")));
+    }
+
+    #[tokio::test]
+    async fn test_changes_during_build_trigger_one_follow_up() {
+        let (sender, events) = mpsc::unbounded_channel();
+        let sender = RefCell::new(Some(sender));
+        let builds = Cell::new(0);
+
+        run_watch_loop(Path::new("."), events, Duration::from_millis(10), || {
+            builds.set(builds.get() + 1);
+            if builds.get() == 1 {
+                // A burst of changes while the first build is running
+                let sender = sender.borrow();
+                for _ in 0..3 {
+                    sender
+                        .as_ref()
+                        .unwrap()
+                        .send(PathBuf::from("src/lib.rs"))
+                        .unwrap();
+                }
+            } else {
+                // Stop watching after the follow-up build
+                sender.borrow_mut().take();
+            }
+            async { Err(anyhow::anyhow!("build failed")) }
+        })
+        .await;
+
+        assert_eq!(builds.get(), 2);
+    }
+}
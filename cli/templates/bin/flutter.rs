@@ -0,0 +1,3 @@
+pub fn main() {
+    mopro_ffi::bindings::flutter::build();
+}
\ No newline at end of file
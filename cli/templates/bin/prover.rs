@@ -0,0 +1,441 @@
+//! Generates and verifies proofs on the host with the same functions the bindings export,
+//! used by `mopro prove` and `mopro verify`.
+//!
+//! ```text
+//! prover <circom|halo2|noir> <prove|verify|bench> [--<option> <value>]...
+//! ```
+//!
+//! `bench` proves and verifies once and prints the proving and verification time, proof size
+//! and peak memory usage as a JSON line, used by `mopro bench`.
+//!
+//! Inputs are read from a JSON file given with `--inputs`:
+//! - circom: an object of signal names to arrays of decimal strings, e.g. `{"a": ["3"], "b": ["5"]}`
+//! - halo2: an object of input names to arrays of decimal strings, e.g. `{"out": ["55"]}`
+//! - noir: an array of decimal strings in the order of the circuit's parameters, e.g. `["3", "5"]`
+//!
+//! Proofs are written to (and read from) the `--proof-dir` directory:
+//! - circom: `proof.json` with the `a`, `b`, `c` points, `protocol` and `curve`,
+//!   and `public.json` with the public inputs as an array of decimal strings
+//! - halo2: `proof.bin` and `public.bin` with the raw bytes returned by the prover
+//! - noir: `proof.bin` with the raw proof bytes, which include the public inputs,
+//!   and `vk.bin` with the verification key the proof was generated with
+
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+struct Args {
+    backend: String,
+    command: String,
+    /// Option values by name, flags without a value map to an empty string
+    options: HashMap<String, String>,
+}
+
+impl Args {
+    fn parse() -> Result<Self, String> {
+        let mut args = std::env::args().skip(1);
+        let backend = args.next().ok_or(USAGE)?;
+        let command = args.next().ok_or(USAGE)?;
+
+        let mut options = HashMap::new();
+        let mut args = args.peekable();
+        while let Some(arg) = args.next() {
+            let name = arg
+                .strip_prefix("--")
+                .ok_or(format!("Unexpected argument '{arg}'\n{USAGE}"))?
+                .to_string();
+            let value = match args.peek() {
+                Some(value) if !value.starts_with("--") => args.next().unwrap(),
+                _ => String::new(),
+            };
+            options.insert(name, value);
+        }
+
+        Ok(Self {
+            backend,
+            command,
+            options,
+        })
+    }
+
+    fn path(&self, name: &str) -> Result<PathBuf, String> {
+        self.optional_path(name).ok_or(format!(
+            "--{name} <PATH> is required for {} {}",
+            self.backend, self.command
+        ))
+    }
+
+    fn optional_path(&self, name: &str) -> Option<PathBuf> {
+        self.options
+            .get(name)
+            .filter(|value| !value.is_empty())
+            .map(PathBuf::from)
+    }
+
+    fn proof_dir(&self) -> PathBuf {
+        self.optional_path("proof-dir")
+            .unwrap_or_else(|| PathBuf::from("proof"))
+    }
+}
+
+const USAGE: &str =
+    "Usage: prover <circom|halo2|noir> <prove|verify|bench> [--<option> <value>]...";
+
+/// Prefix of the line `bench` prints its measurements on, read by `mopro bench`
+const BENCH_REPORT_PREFIX: &str = "bench-report: ";
+
+type ProverFn = fn(&Args) -> Result<Duration, String>;
+
+fn main() {
+    if let Err(e) = Args::parse().and_then(|args| run(&args)) {
+        eprintln!("{e}");
+        std::process::exit(1);
+    }
+}
+
+fn run(args: &Args) -> Result<(), String> {
+    let (prove, verify, proof_file): (ProverFn, ProverFn, &str) = match args.backend.as_str() {
+        "circom" => (circom::prove, circom::verify, circom::PROOF_FILE),
+        "halo2" => (halo2::prove, halo2::verify, halo2::PROOF_FILE),
+        "noir" => (noir::prove, noir::verify, noir::PROOF_FILE),
+        _ => return Err(USAGE.to_string()),
+    };
+
+    match args.command.as_str() {
+        "prove" => prove(args).map(|_| ()),
+        "verify" => verify(args).map(|_| ()),
+        "bench" => bench(args, prove, verify, proof_file),
+        _ => Err(USAGE.to_string()),
+    }
+}
+
+/// Proves and verifies once, then prints a [`BENCH_REPORT_PREFIX`] line with the measurements.
+fn bench(args: &Args, prove: ProverFn, verify: ProverFn, proof_file: &str) -> Result<(), String> {
+    let prove_time = prove(args)?;
+    let verify_time = verify(args)?;
+    let proof_path = args.proof_dir().join(proof_file);
+    let proof_size = fs::metadata(&proof_path)
+        .map_err(|e| format!("Failed to read {}: {e}", proof_path.display()))?
+        .len();
+
+    let report = serde_json::json!({
+        "prove_ms": prove_time.as_secs_f64() * 1000.0,
+        "verify_ms": verify_time.as_secs_f64() * 1000.0,
+        "proof_size_bytes": proof_size,
+        "peak_rss_kb": peak_rss_kb(),
+    });
+    println!("{BENCH_REPORT_PREFIX}{report}");
+    Ok(())
+}
+
+/// Peak resident set size of this process, only available on Linux.
+fn peak_rss_kb() -> Option<u64> {
+    let status = fs::read_to_string("/proc/self/status").ok()?;
+    status
+        .lines()
+        .find_map(|line| line.strip_prefix("VmHWM:"))
+        .and_then(|value| value.trim().trim_end_matches("kB").trim().parse().ok())
+}
+
+fn read(path: &Path) -> Result<Vec<u8>, String> {
+    fs::read(path).map_err(|e| format!("Failed to read {}: {e}", path.display()))
+}
+
+fn read_json(path: &Path) -> Result<serde_json::Value, String> {
+    serde_json::from_slice(&read(path)?)
+        .map_err(|e| format!("Failed to parse {}: {e}", path.display()))
+}
+
+fn write(path: &Path, content: &[u8]) -> Result<(), String> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)
+            .map_err(|e| format!("Failed to create {}: {e}", parent.display()))?;
+    }
+    fs::write(path, content).map_err(|e| format!("Failed to write {}: {e}", path.display()))
+}
+
+fn path_string(path: PathBuf) -> String {
+    path.to_string_lossy().to_string()
+}
+
+/// Runs `f` and prints how long it took.
+fn timed<T>(label: &str, f: impl FnOnce() -> T) -> (T, Duration) {
+    let start = Instant::now();
+    let result = f();
+    let elapsed = start.elapsed();
+    println!("{label} in {elapsed:.3?}");
+    (result, elapsed)
+}
+
+fn report_verification(valid: bool) -> Result<(), String> {
+    if valid {
+        println!("Proof is valid");
+        Ok(())
+    } else {
+        Err("Proof is invalid".to_string())
+    }
+}
+
+#[cfg(feature = "circom")]
+mod circom {
+    use serde_json::{json, Value};
+    use test_e2e::{
+        generate_circom_proof, verify_circom_proof, CircomProof, CircomProofResult, ProofLib, G1,
+        G2,
+    };
+
+    use super::*;
+
+    pub const PROOF_FILE: &str = "proof.json";
+
+    fn proof_lib(args: &Args) -> Result<ProofLib, String> {
+        match args.options.get("proof-lib").map(String::as_str) {
+            None | Some("arkworks") => Ok(ProofLib::Arkworks),
+            Some("rapidsnark") => Ok(ProofLib::Rapidsnark),
+            Some(other) => Err(format!(
+                "Unsupported proof lib '{other}', expected arkworks or rapidsnark"
+            )),
+        }
+    }
+
+    pub fn prove(args: &Args) -> Result<Duration, String> {
+        let zkey = path_string(args.path("zkey")?);
+        let inputs = read_json(&args.path("inputs")?)?.to_string();
+        let proof_lib = proof_lib(args)?;
+
+        let (result, elapsed) = timed("Proof generated", || {
+            generate_circom_proof(zkey, inputs, proof_lib)
+        });
+
+        let proof_dir = args.proof_dir();
+        write_json(&proof_dir.join(PROOF_FILE), &proof_to_json(&result.proof))?;
+        write_json(&proof_dir.join("public.json"), &json!(result.inputs))?;
+        println!("Proof written to {}", proof_dir.display());
+        Ok(elapsed)
+    }
+
+    pub fn verify(args: &Args) -> Result<Duration, String> {
+        let zkey = path_string(args.path("zkey")?);
+        let proof_lib = proof_lib(args)?;
+        let proof_dir = args.proof_dir();
+        let proof = proof_from_json(&read_json(&proof_dir.join(PROOF_FILE))?)?;
+        let inputs = strings(&read_json(&proof_dir.join("public.json"))?)?;
+
+        let (valid, elapsed) = timed("Proof verified", || {
+            verify_circom_proof(zkey, CircomProofResult { proof, inputs }, proof_lib)
+        });
+        report_verification(valid)?;
+        Ok(elapsed)
+    }
+
+    fn write_json(path: &Path, value: &Value) -> Result<(), String> {
+        write(
+            path,
+            serde_json::to_string_pretty(value).unwrap().as_bytes(),
+        )
+    }
+
+    fn proof_to_json(proof: &CircomProof) -> Value {
+        let g1 = |p: &G1| json!({ "x": p.x, "y": p.y, "z": p.z });
+        json!({
+            "a": g1(&proof.a),
+            "b": { "x": proof.b.x, "y": proof.b.y, "z": proof.b.z },
+            "c": g1(&proof.c),
+            "protocol": proof.protocol,
+            "curve": proof.curve,
+        })
+    }
+
+    fn proof_from_json(value: &Value) -> Result<CircomProof, String> {
+        let string = |v: &Value| {
+            v.as_str()
+                .map(str::to_string)
+                .ok_or("Invalid circom proof: expected a string".to_string())
+        };
+        let g1 = |v: &Value| -> Result<G1, String> {
+            Ok(G1 {
+                x: string(&v["x"])?,
+                y: string(&v["y"])?,
+                z: string(&v["z"])?,
+            })
+        };
+        Ok(CircomProof {
+            a: g1(&value["a"])?,
+            b: G2 {
+                x: strings(&value["b"]["x"])?,
+                y: strings(&value["b"]["y"])?,
+                z: strings(&value["b"]["z"])?,
+            },
+            c: g1(&value["c"])?,
+            protocol: string(&value["protocol"])?,
+            curve: string(&value["curve"])?,
+        })
+    }
+
+    fn strings(value: &Value) -> Result<Vec<String>, String> {
+        value
+            .as_array()
+            .and_then(|values| {
+                values
+                    .iter()
+                    .map(|v| v.as_str().map(str::to_string))
+                    .collect()
+            })
+            .ok_or("Expected an array of strings".to_string())
+    }
+}
+
+#[cfg(feature = "halo2")]
+mod halo2 {
+    use test_e2e::{generate_halo2_proof, verify_halo2_proof};
+
+    use super::*;
+
+    pub const PROOF_FILE: &str = "proof.bin";
+
+    pub fn prove(args: &Args) -> Result<Duration, String> {
+        let srs = path_string(args.path("srs")?);
+        let pk = path_string(args.path("pk")?);
+        let inputs_path = args.path("inputs")?;
+        let inputs: HashMap<String, Vec<String>> = serde_json::from_value(read_json(&inputs_path)?)
+            .map_err(|e| {
+                format!(
+                    "{} must map input names to arrays of strings: {e}",
+                    inputs_path.display()
+                )
+            })?;
+
+        let (result, elapsed) = timed("Proof generated", || generate_halo2_proof(srs, pk, inputs));
+
+        let proof_dir = args.proof_dir();
+        write(&proof_dir.join(PROOF_FILE), &result.proof)?;
+        write(&proof_dir.join("public.bin"), &result.inputs)?;
+        println!("Proof written to {}", proof_dir.display());
+        Ok(elapsed)
+    }
+
+    pub fn verify(args: &Args) -> Result<Duration, String> {
+        let srs = path_string(args.path("srs")?);
+        let vk = path_string(args.path("vk")?);
+        let proof_dir = args.proof_dir();
+        let proof = read(&proof_dir.join(PROOF_FILE))?;
+        let inputs = read(&proof_dir.join("public.bin"))?;
+
+        let (valid, elapsed) = timed("Proof verified", || {
+            verify_halo2_proof(srs, vk, proof, inputs)
+        });
+        report_verification(valid)?;
+        Ok(elapsed)
+    }
+}
+
+#[cfg(feature = "noir")]
+mod noir {
+    use test_e2e::{generate_noir_proof, get_noir_verification_key, verify_noir_proof};
+
+    use super::*;
+
+    pub const PROOF_FILE: &str = "proof.bin";
+
+    fn verification_key(args: &Args, circuit: &str) -> Result<Vec<u8>, String> {
+        match args.optional_path("vk") {
+            Some(vk) => read(&vk),
+            None => {
+                timed("Verification key generated", || {
+                    get_noir_verification_key(
+                        circuit.to_string(),
+                        args.optional_path("srs").map(path_string),
+                        args.options.contains_key("on-chain"),
+                        args.options.contains_key("low-memory"),
+                    )
+                })
+                .0
+            }
+        }
+    }
+
+    pub fn prove(args: &Args) -> Result<Duration, String> {
+        let circuit = path_string(args.path("circuit")?);
+        let inputs_path = args.path("inputs")?;
+        let inputs: Vec<String> = serde_json::from_value(read_json(&inputs_path)?)
+            .map_err(|e| format!("{} must be an array of strings: {e}", inputs_path.display()))?;
+        let vk = verification_key(args, &circuit)?;
+
+        let (proof, elapsed) = timed("Proof generated", || {
+            generate_noir_proof(
+                circuit,
+                args.optional_path("srs").map(path_string),
+                inputs,
+                args.options.contains_key("on-chain"),
+                vk.clone(),
+                args.options.contains_key("low-memory"),
+            )
+        });
+        let proof = proof?;
+
+        let proof_dir = args.proof_dir();
+        write(&proof_dir.join(PROOF_FILE), &proof)?;
+        write(&proof_dir.join("vk.bin"), &vk)?;
+        println!("Proof written to {}", proof_dir.display());
+        Ok(elapsed)
+    }
+
+    pub fn verify(args: &Args) -> Result<Duration, String> {
+        let circuit = path_string(args.path("circuit")?);
+        let proof_dir = args.proof_dir();
+        let proof = read(&proof_dir.join(PROOF_FILE))?;
+        let vk = match args.optional_path("vk") {
+            Some(vk) => read(&vk)?,
+            None => read(&proof_dir.join("vk.bin"))?,
+        };
+
+        let (valid, elapsed) = timed("Proof verified", || {
+            verify_noir_proof(
+                circuit,
+                proof,
+                args.options.contains_key("on-chain"),
+                vk,
+                args.options.contains_key("low-memory"),
+            )
+        });
+        report_verification(valid?)?;
+        Ok(elapsed)
+    }
+}
+
+/// Stub for feature "circom"
+#[cfg(not(feature = "circom"))]
+mod circom {
+    pub const PROOF_FILE: &str = "";
+
+    pub fn prove(_args: &super::Args) -> Result<std::time::Duration, String> {
+        Err("\"circom\" feature is not enabled".to_string())
+    }
+
+    pub use prove as verify;
+}
+
+/// Stub for feature "halo2"
+#[cfg(not(feature = "halo2"))]
+mod halo2 {
+    pub const PROOF_FILE: &str = "";
+
+    pub fn prove(_args: &super::Args) -> Result<std::time::Duration, String> {
+        Err("\"halo2\" feature is not enabled".to_string())
+    }
+
+    pub use prove as verify;
+}
+
+/// Stub for feature "noir"
+#[cfg(not(feature = "noir"))]
+mod noir {
+    pub const PROOF_FILE: &str = "";
+
+    pub fn prove(_args: &super::Args) -> Result<std::time::Duration, String> {
+        Err("\"noir\" feature is not enabled".to_string())
+    }
+
+    pub use prove as verify;
+}
@@ -0,0 +1,3 @@
+fn main() {
+    mopro_ffi::bindings::web::build();
+}
@@ -1,104 +1,240 @@
-use anyhow::Context;
-use camino::Utf8Path;
 use std::fs;
-use std::io::{Error, ErrorKind};
-use std::path::{Path, PathBuf};
+use std::path::Path;
 use std::process::Command;
-use uniffi::generate_bindings_library_mode;
-use uniffi::CargoMetadataConfigSupplier;
-use uniffi::KotlinBindingGenerator;
 
-use super::{project_name_from_toml, PlatformBuilder};
+use super::PlatformBuilder;
 
-use super::cleanup_tmp_local;
+use super::config::{FromPlatformConfig, PlatformConfig};
 use super::constants::{
-    AndroidArch, AndroidPlatform, Arch, Mode, ANDROID_BINDINGS_DIR, ANDROID_KT_FILE,
-    ANDROID_PACKAGE_NAME, ARCH_ARM_64_V8, ARCH_ARM_V7_ABI, ARCH_I686, ARCH_X86_64,
+    AndroidArch, AndroidPlatform, Arch, BuildOptions, Platform, ANDROID_BINDINGS_DIR,
+    ANDROID_JNILIBS_DIR, ANDROID_KT_FILE, ANDROID_PACKAGE_NAME, ARCH_ARM_64_V8, ARCH_ARM_V7_ABI,
+    ARCH_I686, ARCH_X86_64,
 };
+use super::doctor::{check_android_ndk, check_command, check_rustup_targets, ToolCheck};
+use super::error::BindingsError;
 use super::install_arch;
 use super::install_ndk;
-use super::mktemp_local;
+use super::manifest::{ArchPath, UNIFFI_VERSION};
+use super::plan::{BindingsLanguage, BuildPlan, BuildStep};
+use super::project::Project;
+use super::{arch_target_dir, work_dir_path};
+
+/// Patch moving the generated Kotlin file into the configured package
+pub(crate) const KOTLIN_PACKAGE_PATCH: &str = "kotlin-package";
 
 // Maintained for backwards compatibility
 #[inline]
 pub fn build() {
-    super::build_from_env::<AndroidPlatform>()
+    super::build_from_env::<AndroidPlatform>().unwrap_or_else(|e| {
+        panic!(
+            "Failed to build {} bindings: {e}",
+            AndroidPlatform::identifier()
+        )
+    })
+}
+
+#[derive(Hash)]
+pub struct AndroidBindingsParams {
+    /// Name of the generated bindings directory
+    pub bindings_dir: String,
+    /// Kotlin package the bindings are generated in, as `uniffi.<package_name>`
+    pub package_name: String,
 }
 
-pub type AndroidBindingsParams = ();
+impl Default for AndroidBindingsParams {
+    fn default() -> Self {
+        Self {
+            bindings_dir: ANDROID_BINDINGS_DIR.to_string(),
+            package_name: ANDROID_PACKAGE_NAME.to_string(),
+        }
+    }
+}
+
+impl FromPlatformConfig for AndroidBindingsParams {
+    fn from_platform_config(config: &PlatformConfig) -> anyhow::Result<Self> {
+        config.check_params(&["package_name"])?;
+        let default = Self::default();
+        Ok(Self {
+            bindings_dir: config.bindings_dir.clone().unwrap_or(default.bindings_dir),
+            package_name: config
+                .string_param("package_name")?
+                .unwrap_or(default.package_name),
+        })
+    }
+}
 
 impl PlatformBuilder for AndroidPlatform {
     type Arch = AndroidArch;
     type Params = AndroidBindingsParams;
 
-    fn build(
-        mode: Mode,
-        project_dir: &Path,
+    fn plan(
+        options: &BuildOptions,
+        project: &Project,
+        output_dir: &Path,
         target_archs: Vec<Self::Arch>,
-        _params: Self::Params,
-    ) -> anyhow::Result<PathBuf> {
+        fresh_archs: &[Self::Arch],
+        params: &Self::Params,
+    ) -> Result<BuildPlan, BindingsError> {
         if !cfg!(feature = "uniffi") {
-            panic!("uniffi feature is not enabled, please enable it in your Cargo.toml");
+            return Err(BindingsError::MissingFeature { feature: "uniffi" });
         }
 
-        let uniffi_style_identifier = project_name_from_toml(project_dir)
-            .expect("Failed to get project name from Cargo.toml");
+        project.require_crate_types(&["cdylib"])?;
+        let project_dir = &project.manifest_dir;
+        let uniffi_style_identifier = &project.lib_name;
 
         // Names for the files that will be outputted (can be changed)
-        let binding_dir_name = ANDROID_BINDINGS_DIR;
-        let out_android_package_name = ANDROID_PACKAGE_NAME;
-        let out_android_kt_file_name = ANDROID_KT_FILE;
+        let binding_dir_name = &params.bindings_dir;
 
         // Names for the generated files by uniffi
         let lib_name = format!("lib{}.so", &uniffi_style_identifier);
-        let gen_android_module_name = &uniffi_style_identifier;
-        let gen_android_kt_file_name = format!("{}.kt", &uniffi_style_identifier);
 
         // Paths for the generated files
-        let build_dir = Path::new(&project_dir).join("build");
-        let work_dir = mktemp_local(&build_dir);
+        let work_dir = work_dir_path(&project_dir.join("build"));
         let bindings_out = work_dir.join(binding_dir_name);
-        let bindings_dest = Path::new(&project_dir).join(binding_dir_name);
-
-        install_ndk();
-        let mut latest_out_lib_path = PathBuf::new();
-        for arch in target_archs {
-            latest_out_lib_path =
-                build_for_arch(arch, &lib_name, &build_dir, &bindings_out, mode).context(
-                    format!("Failed to build for architecture: {}", arch.as_str()),
-                )?;
+        let bindings_dest = output_dir.join(binding_dir_name);
+
+        let mut plan = BuildPlan::new(Self::name(), options, project, output_dir, &bindings_dest);
+        plan.work_dir = Some(work_dir.clone());
+        if !options.offline {
+            plan.steps.push(install_ndk());
         }
 
-        generate_android_bindings(&latest_out_lib_path, &bindings_out)
-            .expect("Failed to generate bindings");
+        let mode = &options.mode;
+        let parallel = options.jobs > 1 && target_archs.len() > 1;
+        let out_lib_path = |arch: AndroidArch| {
+            arch_target_dir(&project.target_dir, arch.as_str(), parallel).join(format!(
+                "{}/{}/{}",
+                arch.as_str(),
+                mode.as_str(),
+                lib_name
+            ))
+        };
+        let slice_dir =
+            |dir: &Path, arch: AndroidArch| dir.join(ANDROID_JNILIBS_DIR).join(abi_folder(arch));
 
+        // Reuse the slices of the previous build if they are up to date
+        let (fresh_archs, stale_archs): (Vec<AndroidArch>, Vec<AndroidArch>) =
+            target_archs.iter().partition(|&&arch| {
+                fresh_archs.contains(&arch)
+                    && slice_dir(&bindings_dest, arch).is_dir()
+                    && out_lib_path(arch).exists()
+            });
+        for &arch in &fresh_archs {
+            plan.steps.push(BuildStep::Copy {
+                from: slice_dir(&bindings_dest, arch),
+                to: slice_dir(&bindings_out, arch),
+            });
+        }
+        if !options.offline {
+            for &arch in &stale_archs {
+                plan.steps.push(install_arch(arch.as_str()));
+            }
+        }
+        for &arch in &stale_archs {
+            plan.steps.push(build_for_arch(
+                arch,
+                project_dir,
+                &arch_target_dir(&project.target_dir, arch.as_str(), parallel),
+                &bindings_out,
+                options,
+            ));
+        }
+        for &arch in &target_archs {
+            plan.steps.push(BuildStep::Copy {
+                from: out_lib_path(arch),
+                to: slice_dir(&bindings_out, arch).join(&lib_name),
+            });
+        }
+        let latest_out_lib_path = target_archs
+            .last()
+            .map(|&arch| out_lib_path(arch))
+            .unwrap_or_default();
+
+        plan.steps.push(BuildStep::WriteFile {
+            path: work_dir.join("uniffi_config.toml"),
+            contents: "[bindings.kotlin]\nandroid = true".to_string(),
+        });
+        plan.steps.push(BuildStep::GenerateBindings {
+            language: BindingsLanguage::Kotlin,
+            library: latest_out_lib_path,
+            out_dir: bindings_out.clone(),
+        });
+        plan.steps
+            .push(BuildStep::patch(KOTLIN_PACKAGE_PATCH, &bindings_out));
+        plan.steps.push(BuildStep::Install {
+            from: bindings_out,
+            to: bindings_dest.clone(),
+        });
+        plan.steps.push(BuildStep::WriteManifest {
+            dir: bindings_dest,
+            uniffi_version: Some(UNIFFI_VERSION.to_string()),
+            arch_paths: target_archs
+                .iter()
+                .map(|arch| {
+                    ArchPath::new(
+                        Path::new(ANDROID_JNILIBS_DIR).join(abi_folder(*arch)),
+                        &[arch.as_str()],
+                    )
+                })
+                .collect(),
+        });
+
+        Ok(plan)
+    }
+
+    fn apply_patch(
+        plan: &BuildPlan,
+        name: &str,
+        path: &Path,
+        params: &Self::Params,
+    ) -> Result<(), BindingsError> {
+        if name != KOTLIN_PACKAGE_PATCH {
+            return Err(BindingsError::unknown_patch(Self::identifier(), name));
+        }
+        let gen_android_module_name = &plan.project.lib_name;
         reformat_kotlin_package(
             gen_android_module_name,
-            &gen_android_kt_file_name,
-            out_android_package_name,
-            &out_android_kt_file_name,
-            &bindings_out,
+            &format!("{gen_android_module_name}.kt"),
+            &params.package_name,
+            &ANDROID_KT_FILE,
+            path,
         )
-        .expect("Failed to reformat generated Kotlin package");
+    }
 
-        move_bindings(&bindings_out, &bindings_dest);
-        cleanup_tmp_local(&build_dir);
+    fn toolchain_checks(target_archs: &[Self::Arch]) -> Vec<ToolCheck> {
+        let targets: Vec<&str> = target_archs.iter().map(|arch| arch.as_str()).collect();
+        let mut checks = check_rustup_targets(None, &targets);
+        checks.push(check_command(
+            "cargo-ndk",
+            "cargo",
+            &["ndk", "--version"],
+            "cargo install cargo-ndk",
+        ));
+        checks.push(check_android_ndk());
+        checks
+    }
+}
 
-        Ok(bindings_out)
+fn abi_folder(arch: AndroidArch) -> &'static str {
+    match arch {
+        AndroidArch::X8664Linux => ARCH_X86_64,
+        AndroidArch::I686Linux => ARCH_I686,
+        AndroidArch::Armv7LinuxAbi => ARCH_ARM_V7_ABI,
+        AndroidArch::Aarch64Linux => ARCH_ARM_64_V8,
     }
 }
 
+/// Command building the library for `arch`. `cargo ndk` copies the C++ runtime next to it
+/// into the `jniLibs` of the bindings.
 fn build_for_arch(
     arch: AndroidArch,
-    lib_name: &str,
-    build_dir: &Path,
+    project_dir: &Path,
+    target_dir: &Path,
     bindings_out: &Path,
-    mode: Mode,
-) -> anyhow::Result<PathBuf> {
+    options: &BuildOptions,
+) -> BuildStep {
     let arch_str = arch.as_str();
-    install_arch(arch_str.to_string());
-    let cpp_lib_dest = bindings_out.join("jniLibs");
-
     let mut build_cmd = Command::new("cargo");
     build_cmd
         .arg("ndk")
@@ -106,79 +242,21 @@ fn build_for_arch(
         .arg(arch_str)
         .arg("build")
         .arg("--link-libcxx-shared")
-        .arg("--lib");
-    if mode == Mode::Release {
-        build_cmd.arg("--release");
+        .arg("--lib")
+        .args(options.mode.cargo_args())
+        .args(options.cargo.feature_args());
+    if let Some(rustflags) = options.cargo.rustflags(arch_str) {
+        build_cmd.env("RUSTFLAGS", rustflags);
     }
     build_cmd
-        .env("CARGO_BUILD_TARGET_DIR", build_dir)
+        .current_dir(project_dir)
+        .env("CARGO_BUILD_TARGET_DIR", target_dir)
         .env("CARGO_BUILD_TARGET", arch_str)
-        .env("CARGO_NDK_OUTPUT_PATH", cpp_lib_dest)
-        .spawn()
-        .expect("Failed to spawn cargo build")
-        .wait()
-        .expect("cargo build errored");
-
-    let folder = match arch {
-        AndroidArch::X8664Linux => ARCH_X86_64,
-        AndroidArch::I686Linux => ARCH_I686,
-        AndroidArch::Armv7LinuxAbi => ARCH_ARM_V7_ABI,
-        AndroidArch::Aarch64Linux => ARCH_ARM_64_V8,
-    };
-
-    let out_lib_path = build_dir.join(format!(
-        "{}/{}/{}/{}",
-        build_dir.display(),
-        arch_str,
-        mode.as_str(),
-        lib_name
-    ));
-    let out_lib_dest = bindings_out.join(format!("jniLibs/{folder}/{lib_name}"));
-
-    let parent_dir = out_lib_dest.parent().context(format!(
-        "Failed to get parent directory for {}",
-        out_lib_dest.display()
-    ))?;
-
-    fs::create_dir_all(parent_dir).context("Failed to create jniLibs directory")?;
-    fs::copy(&out_lib_path, &out_lib_dest).context("Failed to copy file")?;
-
-    Ok(out_lib_path)
-}
-
-fn move_bindings(bindings_out: &Path, bindings_dest: &Path) {
-    if let Ok(info) = fs::metadata(bindings_dest) {
-        if !info.is_dir() {
-            panic!("bindings directory exists and is not a directory");
-        }
-        fs::remove_dir_all(bindings_dest).expect("Failed to remove bindings directory");
-    }
-    fs::rename(bindings_out, bindings_dest).expect("Failed to move bindings into place");
-}
-
-fn generate_android_bindings(dylib_path: &Path, binding_dir: &Path) -> anyhow::Result<()> {
-    let content = "[bindings.kotlin]\nandroid = true";
-    let parent_dir = binding_dir
-        .parent()
-        .context("Failed to get parent directory")?;
-    let config_path = parent_dir.join("uniffi_config.toml");
-    fs::write(&config_path, content).expect("Failed to write uniffi_config.toml");
-
-    generate_bindings_library_mode(
-        Utf8Path::from_path(dylib_path)
-            .ok_or(Error::new(ErrorKind::InvalidInput, "Invalid dylib path"))?,
-        None,
-        &KotlinBindingGenerator,
-        &CargoMetadataConfigSupplier::default(),
-        None,
-        Utf8Path::from_path(binding_dir).ok_or(Error::new(
-            ErrorKind::InvalidInput,
-            "Invalid kotlin files directory",
-        ))?,
-        true,
-    )
-    .map_err(|e| Error::other(e.to_string()))?;
-    Ok(())
+        .env(
+            "CARGO_NDK_OUTPUT_PATH",
+            bindings_out.join(ANDROID_JNILIBS_DIR),
+        );
+    BuildStep::arch_command(arch_str, &build_cmd)
 }
 
 fn reformat_kotlin_package(
@@ -187,7 +265,7 @@ fn reformat_kotlin_package(
     out_android_module_name: &str,
     out_android_kt_file_name: &&str,
     bindings_out: &Path,
-) -> anyhow::Result<()> {
+) -> Result<(), BindingsError> {
     let generated_kt_file = bindings_out
         .join("uniffi")
         .join(gen_android_module_name)
@@ -198,18 +276,196 @@ fn reformat_kotlin_package(
         .join(out_android_kt_file_name);
 
     fs::create_dir(bindings_out.join("uniffi").join(out_android_module_name))
-        .context("Failed to create new package directory")?;
-    fs::rename(generated_kt_file, &out_android_kt_file).context("Failed to move kotlin file")?;
-    fs::remove_dir(bindings_out.join("uniffi").join(gen_android_module_name))
-        .context("Failed to remove gen android kotlin package directory")?;
+        .map_err(BindingsError::io("Failed to create new package directory"))?;
+    fs::rename(generated_kt_file, &out_android_kt_file)
+        .map_err(BindingsError::io("Failed to move kotlin file"))?;
+    fs::remove_dir(bindings_out.join("uniffi").join(gen_android_module_name)).map_err(
+        BindingsError::io("Failed to remove gen android kotlin package directory"),
+    )?;
 
     // Remove `package uniffi.<gen_android_module_name>` from the generated Kotlin file
-    let content =
-        fs::read_to_string(&out_android_kt_file).context("Failed to read generated Kotlin file")?;
+    let content = fs::read_to_string(&out_android_kt_file)
+        .map_err(BindingsError::io("Failed to read generated Kotlin file"))?;
     let modified_content = content.replace(
         &format!("package uniffi.{gen_android_module_name}"),
         &format!("package uniffi.{out_android_module_name}"),
     );
     fs::write(&out_android_kt_file, modified_content)
-        .context("Failed to write modified Kotlin file")
+        .map_err(BindingsError::io("Failed to write modified Kotlin file"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bindings::constants::{BuildContext, Mode};
+    use crate::bindings::observer::NoopObserver;
+    use crate::bindings::project::test_project;
+    use crate::bindings::runner::RecordingRunner;
+
+    #[test]
+    fn test_build_commands() {
+        let project = test_project("my-circuits", &["lib", "cdylib", "staticlib"]);
+        let project_dir = project.manifest_dir.clone();
+
+        let runner = RecordingRunner::new();
+        let bindings_dir = AndroidPlatform::build(
+            &BuildOptions::new(Mode::Debug),
+            &project,
+            &project_dir,
+            vec![AndroidArch::Aarch64Linux, AndroidArch::X8664Linux],
+            &[],
+            AndroidBindingsParams::default(),
+            BuildContext::new(&runner, &NoopObserver),
+        )
+        .unwrap();
+        assert_eq!(bindings_dir, project_dir.join(ANDROID_BINDINGS_DIR));
+
+        let commands = runner.commands();
+        let lines: Vec<String> = commands.iter().map(|cmd| cmd.command()).collect();
+        assert_eq!(
+            lines,
+            [
+                "cargo install cargo-ndk",
+                "rustup target add aarch64-linux-android",
+                "rustup target add x86_64-linux-android",
+                "cargo ndk -t aarch64-linux-android build --link-libcxx-shared --lib",
+                "cargo ndk -t x86_64-linux-android build --link-libcxx-shared --lib",
+            ]
+        );
+        let ndk_output = commands[3]
+            .envs
+            .iter()
+            .find(|(key, _)| key == "CARGO_NDK_OUTPUT_PATH")
+            .and_then(|(_, value)| value.clone())
+            .unwrap();
+        assert!(ndk_output.ends_with("MoproAndroidBindings/jniLibs"));
+    }
+
+    #[test]
+    fn test_parallel_target_dirs() {
+        let project = test_project("my-circuits", &["lib", "cdylib", "staticlib"]);
+        let project_dir = project.manifest_dir.clone();
+
+        let options = BuildOptions {
+            jobs: 2,
+            ..BuildOptions::new(Mode::Release)
+        };
+        let plan = AndroidPlatform::plan(
+            &options,
+            &project,
+            &project_dir,
+            vec![AndroidArch::Aarch64Linux, AndroidArch::X8664Linux],
+            &[],
+            &AndroidBindingsParams::default(),
+        )
+        .unwrap();
+        let target_dirs: Vec<(String, String)> = plan
+            .steps
+            .iter()
+            .filter_map(|step| match step {
+                BuildStep::Command {
+                    arch: Some(arch),
+                    command,
+                } => command
+                    .envs
+                    .iter()
+                    .find(|(key, _)| key == "CARGO_BUILD_TARGET_DIR")
+                    .and_then(|(_, dir)| Some((arch.clone(), dir.clone()?))),
+                _ => None,
+            })
+            .collect();
+        let build_dir = project.target_dir.join("parallel");
+        assert_eq!(
+            target_dirs,
+            [
+                (
+                    "aarch64-linux-android".to_string(),
+                    build_dir
+                        .join("aarch64-linux-android")
+                        .display()
+                        .to_string()
+                ),
+                (
+                    "x86_64-linux-android".to_string(),
+                    build_dir.join("x86_64-linux-android").display().to_string()
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_offline_plan() {
+        let project = test_project("my-circuits", &["lib", "cdylib", "staticlib"]);
+        let project_dir = project.manifest_dir.clone();
+
+        let options = BuildOptions {
+            offline: true,
+            ..BuildOptions::new(Mode::Debug)
+        };
+        let plan = AndroidPlatform::plan(
+            &options,
+            &project,
+            &project_dir,
+            vec![AndroidArch::Aarch64Linux],
+            &[],
+            &AndroidBindingsParams::default(),
+        )
+        .unwrap();
+        assert!(plan.offline);
+        let programs: Vec<String> = plan
+            .steps
+            .iter()
+            .filter_map(|step| match step {
+                BuildStep::Command { command, .. } => Some(command.command()),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(
+            programs,
+            ["cargo ndk -t aarch64-linux-android build --link-libcxx-shared --lib"]
+        );
+    }
+
+    #[test]
+    fn test_profile_and_cargo_flags() {
+        let project = test_project("my-circuits", &["lib", "cdylib", "staticlib"]);
+        let project_dir = project.manifest_dir.clone();
+
+        let mut options = BuildOptions {
+            offline: true,
+            ..BuildOptions::new(Mode::Profile("mobile".to_string()))
+        };
+        options.cargo.features = vec!["noir".to_string()];
+        options.cargo.no_default_features = true;
+        options.cargo.target_rustflags.insert(
+            "aarch64-linux-android".to_string(),
+            vec!["-C target-cpu=cortex-a76".to_string()],
+        );
+        let plan = AndroidPlatform::plan(
+            &options,
+            &project,
+            &project_dir,
+            vec![AndroidArch::Aarch64Linux],
+            &[],
+            &AndroidBindingsParams::default(),
+        )
+        .unwrap();
+        assert_eq!(plan.mode, "mobile");
+        let BuildStep::Command { command, .. } = &plan.steps[0] else {
+            panic!("expected the cargo build, got {}", plan.steps[0]);
+        };
+        assert_eq!(
+            command.command(),
+            "cargo ndk -t aarch64-linux-android build --link-libcxx-shared --lib --profile mobile \
+             --no-default-features --features noir"
+        );
+        assert!(command.envs.contains(&(
+            "RUSTFLAGS".to_string(),
+            Some("-C target-cpu=cortex-a76".to_string())
+        )));
+        assert!(plan.steps.iter().any(|step| matches!(
+            step,
+            BuildStep::Copy { from, .. } if from.ends_with("aarch64-linux-android/mobile/libmy_circuits.so")
+        )));
+    }
 }
@@ -0,0 +1,423 @@
+//! Project configuration read from `mopro.toml` in the project root.
+//!
+//! ```toml
+//! [build]
+//! platforms = ["ios", "android"]
+//! package = "my-circuits"
+//! mode = "release"
+//! output_dir = "bindings"
+//! jobs = 4
+//! offline = true
+//! features = ["noir"]
+//! no_default_features = true
+//! rustflags = "-C opt-level=z"
+//!
+//! [build.target.aarch64-apple-ios]
+//! rustflags = "-C target-cpu=apple-a14"
+//!
+//! [ios]
+//! archs = ["aarch64-apple-ios", "aarch64-apple-ios-sim"]
+//! bindings_dir = "MoproiOSBindings"
+//! using_noir = true
+//!
+//! [android]
+//! archs = ["aarch64-linux-android", "x86_64-linux-android"]
+//! package_name = "mopro"
+//! ```
+//!
+//! Every setting is optional. Settings are resolved with the precedence
+//! CLI flag > environment variable > `mopro.toml` > default.
+
+use anyhow::Context;
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use toml::{Table, Value};
+
+use super::constants::{
+    AndroidPlatform, CargoFlags, DesktopPlatform, FlutterPlatform, IosPlatform, JvmPlatform, Mode,
+    NodePlatform, Platform, PythonPlatform, ReactNativePlatform, WebPlatform,
+};
+
+pub const CONFIG_FILE: &str = "mopro.toml";
+
+const BUILD_SECTION: &str = "build";
+
+/// Sections of the platforms, named after [`Platform::name`], and their default bindings
+/// directories.
+fn platform_sections() -> [(&'static str, &'static str); 9] {
+    [
+        (IosPlatform::name(), IosPlatform::default_bindings_dir()),
+        (
+            AndroidPlatform::name(),
+            AndroidPlatform::default_bindings_dir(),
+        ),
+        (WebPlatform::name(), WebPlatform::default_bindings_dir()),
+        (
+            FlutterPlatform::name(),
+            FlutterPlatform::default_bindings_dir(),
+        ),
+        (
+            DesktopPlatform::name(),
+            DesktopPlatform::default_bindings_dir(),
+        ),
+        (
+            PythonPlatform::name(),
+            PythonPlatform::default_bindings_dir(),
+        ),
+        (JvmPlatform::name(), JvmPlatform::default_bindings_dir()),
+        (NodePlatform::name(), NodePlatform::default_bindings_dir()),
+        (
+            ReactNativePlatform::name(),
+            ReactNativePlatform::default_bindings_dir(),
+        ),
+    ]
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct MoproConfig {
+    /// Platforms built when no platform is given explicitly
+    pub platforms: Vec<String>,
+    /// Package of the workspace to build the bindings for
+    pub package: Option<String>,
+    pub mode: Option<Mode>,
+    /// Output directory, relative to the project directory
+    pub output_dir: Option<PathBuf>,
+    /// Number of architectures compiled in parallel
+    pub jobs: Option<usize>,
+    /// Never install missing rust targets and tools
+    pub offline: Option<bool>,
+    /// Features and RUSTFLAGS of the cargo builds
+    pub cargo: CargoFlags,
+    platform_configs: HashMap<String, PlatformConfig>,
+}
+
+/// Settings of a single platform section, e.g. `[ios]`.
+#[derive(Debug, Clone, Default)]
+pub struct PlatformConfig {
+    section_name: String,
+    pub archs: Option<Vec<String>>,
+    pub bindings_dir: Option<String>,
+    /// Platform-specific parameters, read by [`FromPlatformConfig`]
+    pub params: Table,
+}
+
+/// Builder parameters that can be read from the platform's section of `mopro.toml`.
+pub trait FromPlatformConfig: Default {
+    fn from_platform_config(config: &PlatformConfig) -> anyhow::Result<Self>;
+}
+
+impl MoproConfig {
+    /// Loads `mopro.toml` from the project directory.
+    /// Returns the default configuration if the file does not exist.
+    pub fn load(project_dir: &Path) -> anyhow::Result<Self> {
+        let config_path = project_dir.join(CONFIG_FILE);
+        if !config_path.exists() {
+            return Ok(Self::default());
+        }
+
+        let content = fs::read_to_string(&config_path)
+            .context(format!("Failed to read {}", config_path.display()))?;
+        Self::parse(&content).context(format!("Failed to parse {}", config_path.display()))
+    }
+
+    pub fn parse(content: &str) -> anyhow::Result<Self> {
+        let config: Table = content.parse::<Table>()?;
+        let mut mopro_config = Self::default();
+
+        for (section_name, section) in config {
+            let section = match section {
+                Value::Table(table) => table,
+                _ => return Err(anyhow::anyhow!("[{section_name}] must be a table")),
+            };
+
+            if section_name == BUILD_SECTION {
+                mopro_config.parse_build_section(section)?;
+            } else if !platform_sections()
+                .iter()
+                .any(|(name, _)| *name == section_name)
+            {
+                let names: Vec<&str> = platform_sections().iter().map(|(name, _)| *name).collect();
+                return Err(anyhow::anyhow!(
+                    "Unknown section [{section_name}], expected [{BUILD_SECTION}] or one of the platforms: {}",
+                    names.join(", ")
+                ));
+            } else {
+                let platform_config = PlatformConfig::parse(&section_name, section)?;
+                mopro_config
+                    .platform_configs
+                    .insert(section_name, platform_config);
+            }
+        }
+
+        Ok(mopro_config)
+    }
+
+    fn parse_build_section(&mut self, section: Table) -> anyhow::Result<()> {
+        for (key, value) in section {
+            match key.as_str() {
+                "platforms" => self.platforms = string_array(BUILD_SECTION, &key, &value)?,
+                "package" => self.package = Some(string(BUILD_SECTION, &key, &value)?),
+                "mode" => {
+                    let mode = string(BUILD_SECTION, &key, &value)?;
+                    self.mode = Some(Mode::parse(&mode).ok_or(anyhow::anyhow!(
+                        "Unsupported mode '{mode}' in [build], expected {} or a cargo profile name",
+                        Mode::all_strings().join(", ")
+                    ))?);
+                }
+                "output_dir" => {
+                    self.output_dir = Some(PathBuf::from(string(BUILD_SECTION, &key, &value)?))
+                }
+                "jobs" => {
+                    self.jobs = Some(
+                        value
+                            .as_integer()
+                            .filter(|jobs| *jobs > 0)
+                            .map(|jobs| jobs as usize)
+                            .ok_or(anyhow::anyhow!(
+                                "'jobs' in [build] must be a positive integer"
+                            ))?,
+                    )
+                }
+                "offline" => {
+                    self.offline = Some(
+                        value
+                            .as_bool()
+                            .ok_or(anyhow::anyhow!("'offline' in [build] must be a boolean"))?,
+                    )
+                }
+                "features" => self.cargo.features = string_array(BUILD_SECTION, &key, &value)?,
+                "no_default_features" => {
+                    self.cargo.no_default_features = bool(BUILD_SECTION, &key, &value)?
+                }
+                "all_features" => self.cargo.all_features = bool(BUILD_SECTION, &key, &value)?,
+                "rustflags" => self.cargo.rustflags = rustflags(BUILD_SECTION, &value)?,
+                "target" => {
+                    let targets = value
+                        .as_table()
+                        .ok_or(anyhow::anyhow!("[build.target] must be a table"))?;
+                    for (target, target_section) in targets {
+                        let section_name = format!("build.target.{target}");
+                        let flags = target_section
+                            .get("rustflags")
+                            .map(|value| rustflags(&section_name, value))
+                            .transpose()?
+                            .ok_or(anyhow::anyhow!("[{section_name}] must set 'rustflags'"))?;
+                        self.cargo.target_rustflags.insert(target.clone(), flags);
+                    }
+                }
+                _ => return Err(anyhow::anyhow!("Unknown key '{key}' in [build]")),
+            }
+        }
+        Ok(())
+    }
+
+    /// Returns the section of the given platform, or an empty one if it is not configured.
+    pub fn platform(&self, name: &str) -> PlatformConfig {
+        self.platform_configs
+            .get(name)
+            .cloned()
+            .unwrap_or_else(|| PlatformConfig {
+                section_name: name.to_string(),
+                ..Default::default()
+            })
+    }
+
+    /// Bindings directory of every platform, its `bindings_dir` or the default one. They are
+    /// written to the output directory, which may be the project, and are never build inputs.
+    pub fn bindings_dirs(&self) -> Vec<String> {
+        platform_sections()
+            .iter()
+            .map(|(name, default_dir)| {
+                self.platform_configs
+                    .get(*name)
+                    .and_then(|config| config.bindings_dir.clone())
+                    .unwrap_or_else(|| default_dir.to_string())
+            })
+            .collect()
+    }
+}
+
+impl PlatformConfig {
+    fn parse(section_name: &str, section: Table) -> anyhow::Result<Self> {
+        let mut platform_config = Self {
+            section_name: section_name.to_string(),
+            ..Default::default()
+        };
+        for (key, value) in section {
+            match key.as_str() {
+                "archs" => {
+                    platform_config.archs = Some(string_array(section_name, &key, &value)?);
+                }
+                "bindings_dir" => {
+                    platform_config.bindings_dir = Some(string(section_name, &key, &value)?);
+                }
+                _ => {
+                    platform_config.params.insert(key, value);
+                }
+            }
+        }
+        Ok(platform_config)
+    }
+
+    /// Fails if the section contains parameters other than `known_keys`.
+    pub fn check_params(&self, known_keys: &[&str]) -> anyhow::Result<()> {
+        match self
+            .params
+            .keys()
+            .find(|key| !known_keys.contains(&key.as_str()))
+        {
+            Some(key) => Err(anyhow::anyhow!(
+                "Unknown key '{key}' in [{}], expected one of: archs, bindings_dir{}",
+                self.section_name,
+                known_keys
+                    .iter()
+                    .map(|k| format!(", {k}"))
+                    .collect::<String>()
+            )),
+            None => Ok(()),
+        }
+    }
+
+    pub fn bool_param(&self, key: &str) -> anyhow::Result<Option<bool>> {
+        self.params
+            .get(key)
+            .map(|value| {
+                value.as_bool().ok_or(anyhow::anyhow!(
+                    "'{key}' in [{}] must be a boolean",
+                    self.section_name
+                ))
+            })
+            .transpose()
+    }
+
+    pub fn string_param(&self, key: &str) -> anyhow::Result<Option<String>> {
+        self.params
+            .get(key)
+            .map(|value| string(&self.section_name, key, value))
+            .transpose()
+    }
+}
+
+fn string(section_name: &str, key: &str, value: &Value) -> anyhow::Result<String> {
+    value.as_str().map(|s| s.to_string()).ok_or(anyhow::anyhow!(
+        "'{key}' in [{section_name}] must be a string"
+    ))
+}
+
+fn bool(section_name: &str, key: &str, value: &Value) -> anyhow::Result<bool> {
+    value.as_bool().ok_or(anyhow::anyhow!(
+        "'{key}' in [{section_name}] must be a boolean"
+    ))
+}
+
+/// RUSTFLAGS given as a string, like the environment variable, or as an array of flags.
+fn rustflags(section_name: &str, value: &Value) -> anyhow::Result<Vec<String>> {
+    match value {
+        Value::String(flags) => Ok(flags.split_whitespace().map(str::to_string).collect()),
+        _ => string_array(section_name, "rustflags", value),
+    }
+}
+
+fn string_array(section_name: &str, key: &str, value: &Value) -> anyhow::Result<Vec<String>> {
+    value
+        .as_array()
+        .and_then(|values| {
+            values
+                .iter()
+                .map(|v| v.as_str().map(|s| s.to_string()))
+                .collect::<Option<Vec<String>>>()
+        })
+        .ok_or(anyhow::anyhow!(
+            "'{key}' in [{section_name}] must be an array of strings"
+        ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bindings::constants::ANDROID_BINDINGS_DIR;
+
+    #[test]
+    fn test_parse_config() {
+        let config = MoproConfig::parse(
+            r#"
+            [build]
+            platforms = ["ios", "android"]
+            package = "my-circuits"
+            mode = "release"
+            output_dir = "bindings"
+            jobs = 4
+            offline = true
+            features = ["noir"]
+            no_default_features = true
+            rustflags = "-C opt-level=z"
+
+            [build.target.aarch64-apple-ios]
+            rustflags = ["-C", "target-cpu=apple-a14"]
+
+            [ios]
+            archs = ["aarch64-apple-ios"]
+            bindings_dir = "Bindings"
+            using_noir = true
+            "#,
+        )
+        .unwrap();
+
+        assert_eq!(config.platforms, ["ios", "android"]);
+        assert_eq!(config.package.as_deref(), Some("my-circuits"));
+        assert_eq!(config.mode, Some(Mode::Release));
+        assert_eq!(config.output_dir, Some(PathBuf::from("bindings")));
+        assert_eq!(config.jobs, Some(4));
+        assert_eq!(config.offline, Some(true));
+        assert_eq!(
+            config.cargo.feature_args(),
+            ["--no-default-features", "--features", "noir"]
+        );
+        assert_eq!(
+            config.cargo.rustflags("aarch64-apple-ios").as_deref(),
+            Some("-C opt-level=z -C target-cpu=apple-a14")
+        );
+        assert_eq!(
+            config.cargo.rustflags("x86_64-apple-ios").as_deref(),
+            Some("-C opt-level=z")
+        );
+
+        let ios = config.platform("ios");
+        assert_eq!(ios.archs, Some(vec!["aarch64-apple-ios".to_string()]));
+        assert_eq!(ios.bool_param("using_noir").unwrap(), Some(true));
+        assert!(ios.check_params(&["using_noir"]).is_ok());
+        assert!(ios.check_params(&[]).is_err());
+
+        let android = config.platform("android");
+        assert!(android.archs.is_none());
+
+        // Platforms without a `bindings_dir` keep their default one
+        let bindings_dirs = config.bindings_dirs();
+        assert_eq!(bindings_dirs.len(), 9);
+        assert_eq!(bindings_dirs[0], "Bindings");
+        assert!(bindings_dirs.contains(&ANDROID_BINDINGS_DIR.to_string()));
+    }
+
+    #[test]
+    fn test_parse_custom_profile() {
+        let config = MoproConfig::parse("[build]\nmode = \"mobile\"").unwrap();
+        assert_eq!(config.mode, Some(Mode::Profile("mobile".to_string())));
+        assert_eq!(config.mode.unwrap().cargo_args(), ["--profile", "mobile"]);
+        assert_eq!(Mode::parse("dev"), Some(Mode::Debug));
+        assert_eq!(Mode::parse("Release"), Some(Mode::Release));
+    }
+
+    #[test]
+    fn test_parse_config_rejects_invalid_values() {
+        assert!(MoproConfig::parse("[build]\nmode = \"fast build\"").is_err());
+        assert!(MoproConfig::parse("[build]\nunknown = 1").is_err());
+        assert!(MoproConfig::parse("[build]\njobs = 0").is_err());
+        assert!(MoproConfig::parse("[build]\noffline = \"yes\"").is_err());
+        assert!(MoproConfig::parse("[build]\nfeatures = \"noir\"").is_err());
+        assert!(MoproConfig::parse("[build.target.x86_64-apple-ios]\ncpu = 1").is_err());
+        assert!(MoproConfig::parse("[ios]\narchs = \"aarch64-apple-ios\"").is_err());
+        assert!(MoproConfig::parse("[iso]\narchs = [\"aarch64-apple-ios\"]").is_err());
+        assert!(MoproConfig::parse("[react-native]\npackage_name = \"mopro\"").is_ok());
+    }
+}
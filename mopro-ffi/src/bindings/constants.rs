@@ -1,9 +1,27 @@
-use color_eyre::eyre::ContextCompat;
+use std::collections::BTreeMap;
+
+use super::config::FromPlatformConfig;
+use super::doctor::ToolCheck;
+use super::error::BindingsError;
+use super::observer::BuildObserver;
+use super::plan::BuildPlan;
+use super::project::Project;
+use super::runner::CommandRunner;
 
 pub const BUILD_MODE_ENV: &str = "CONFIGURATION";
+pub const BUILD_JOBS_ENV: &str = "MOPRO_JOBS";
+pub const OFFLINE_ENV: &str = "MOPRO_OFFLINE";
+/// Set by CI services, turns on offline builds unless configured otherwise
+pub const CI_ENV: &str = "CI";
 pub const IOS_ARCHS_ENV: &str = "IOS_ARCHS";
 pub const ANDROID_ARCHS_ENV: &str = "ANDROID_ARCHS";
 pub const FLUTTER_ARCHS_ENV: &str = "FLUTTER_ARCHS";
+pub const WEB_ARCHS_ENV: &str = "WEB_ARCHS";
+pub const DESKTOP_ARCHS_ENV: &str = "DESKTOP_ARCHS";
+pub const PYTHON_ARCHS_ENV: &str = "PYTHON_ARCHS";
+pub const REACT_NATIVE_ARCHS_ENV: &str = "REACT_NATIVE_ARCHS";
+pub const NODE_ARCHS_ENV: &str = "NODE_ARCHS";
+pub const JVM_ARCHS_ENV: &str = "JVM_ARCHS";
 
 pub const IOS_BINDINGS_DIR: &str = "MoproiOSBindings";
 pub const IOS_SWIFT_FILE: &str = "mopro.swift";
@@ -16,6 +34,30 @@ pub const ANDROID_PACKAGE_NAME: &str = "mopro";
 pub const ANDROID_KT_FILE: &str = "mopro.kt";
 
 pub const WEB_BINDINGS_DIR: &str = "MoproWasmBindings";
+pub const WEB_NIGHTLY_TOOLCHAIN: &str = "nightly-2024-07-18";
+
+pub const DESKTOP_BINDINGS_DIR: &str = "MoproDesktopBindings";
+pub const DESKTOP_INCLUDE_DIR: &str = "include";
+pub const DESKTOP_HEADER_FILE: &str = "mopro.h";
+/// Name of the pkg-config package, `pkg-config --libs mopro`
+pub const DESKTOP_PKG_CONFIG_NAME: &str = "mopro";
+
+pub const PYTHON_BINDINGS_DIR: &str = "MoproPythonBindings";
+pub const PYTHON_PACKAGE_NAME: &str = "mopro";
+pub const PYTHON_MODULE_FILE: &str = "mopro.py";
+
+pub const REACT_NATIVE_BINDINGS_DIR: &str = "MoproReactNativeBindings";
+pub const REACT_NATIVE_PACKAGE_NAME: &str = "mopro-react-native";
+/// Version of uniffi-bindgen-react-native generating bindings of the uniffi version in use
+pub const UNIFFI_BINDGEN_REACT_NATIVE_VERSION: &str = "0.29.0-0";
+
+pub const NODE_BINDINGS_DIR: &str = "MoproNodeBindings";
+pub const NODE_PACKAGE_NAME: &str = "mopro-node";
+
+pub const JVM_BINDINGS_DIR: &str = "MoproJvmBindings";
+/// Versions of the Kotlin Gradle plugin and JNA in the Gradle build of the JVM bindings
+pub const JVM_KOTLIN_VERSION: &str = "1.9.24";
+pub const JVM_JNA_VERSION: &str = "5.14.0";
 
 pub const ARCH_X86_64: &str = "x86_64";
 pub const ARCH_ARM_64: &str = "aarch64";
@@ -24,11 +66,15 @@ pub const ARCH_ARM_V7_ABI: &str = "armeabi-v7a";
 pub const ARCH_ARM_64_V8: &str = "arm64-v8a";
 
 pub const FLUTTER_BINDINGS_DIR: &str = "mopro_flutter_bindings";
+pub const FLUTTER_RUST_BRIDGE_CODEGEN_VERSION: &str = "2.11.1";
 
-#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+/// Cargo profile a build compiles with.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub enum Mode {
     Debug,
     Release,
+    /// A custom profile of the project, e.g. `[profile.mobile]`
+    Profile(String),
 }
 
 struct ModeInfo {
@@ -48,36 +94,125 @@ const MODES: [ModeInfo; 2] = [
 ];
 
 impl Mode {
-    pub fn as_str(&self) -> &'static str {
-        MODES
-            .iter()
-            .find(|info| info.mode == *self)
-            .map(|info| info.str)
-            .expect("Unsupported Mode, only support 'release' and 'debug'")
+    /// Name of the mode, which is also the directory cargo writes it to under `target/<arch>/`.
+    pub fn as_str(&self) -> &str {
+        match self {
+            Mode::Profile(name) => name,
+            _ => MODES
+                .iter()
+                .find(|info| info.mode == *self)
+                .map(|info| info.str)
+                .expect("Every built-in mode is in MODES"),
+        }
     }
 
-    pub fn parse_from_str(s: &str) -> Self {
-        MODES
-            .iter()
-            .find(|info| info.str.to_lowercase() == s.to_lowercase())
-            .map(|info| info.mode)
-            .expect("Unsupported Mode String, only support 'release' and 'debug'")
+    /// Parses `debug`, `release` or the name of a custom cargo profile. `dev` is the profile of
+    /// debug builds.
+    pub fn parse(s: &str) -> Option<Self> {
+        if s.eq_ignore_ascii_case("dev") {
+            return Some(Mode::Debug);
+        }
+        if let Some(info) = MODES.iter().find(|info| info.str.eq_ignore_ascii_case(s)) {
+            return Some(info.mode.clone());
+        }
+        let valid = !s.is_empty()
+            && s.chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+        valid.then(|| Mode::Profile(s.to_string()))
     }
 
-    pub fn from_idx(idx: usize) -> Self {
-        MODES[idx].mode
+    /// Arguments selecting the profile of a `cargo build`.
+    pub fn cargo_args(&self) -> Vec<&str> {
+        match self {
+            Mode::Debug => vec![],
+            Mode::Release => vec!["--release"],
+            Mode::Profile(name) => vec!["--profile", name],
+        }
     }
 
-    pub fn idx(s: &str) -> Option<usize> {
-        MODES
+    pub fn all_strings() -> Vec<&'static str> {
+        MODES.iter().map(|info| info.str).collect()
+    }
+}
+
+/// Cargo features and compiler flags forwarded to every cargo invocation of a build.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
+pub struct CargoFlags {
+    pub features: Vec<String>,
+    pub no_default_features: bool,
+    pub all_features: bool,
+    /// RUSTFLAGS of every target
+    pub rustflags: Vec<String>,
+    /// RUSTFLAGS added for single targets, keyed by target triple
+    pub target_rustflags: BTreeMap<String, Vec<String>>,
+}
+
+impl CargoFlags {
+    /// Arguments selecting the features, e.g. `--no-default-features --features noir`.
+    pub fn feature_args(&self) -> Vec<String> {
+        let mut args = Vec::new();
+        if self.no_default_features {
+            args.push("--no-default-features".to_string());
+        }
+        if self.all_features {
+            args.push("--all-features".to_string());
+        }
+        if !self.features.is_empty() {
+            args.push("--features".to_string());
+            args.push(self.features.join(","));
+        }
+        args
+    }
+
+    /// RUSTFLAGS of a build for `target`, `None` if there are none.
+    pub fn rustflags(&self, target: &str) -> Option<String> {
+        let flags: Vec<&str> = self
+            .rustflags
             .iter()
-            .enumerate()
-            .find(|(_, m)| m.str == s)
-            .map(|(i, _)| i)
+            .chain(self.target_rustflags.get(target).into_iter().flatten())
+            .map(String::as_str)
+            .collect();
+        (!flags.is_empty()).then(|| flags.join(" "))
     }
+}
 
-    pub fn all_strings() -> Vec<&'static str> {
-        MODES.iter().map(|info| info.str).collect()
+/// Settings of a bindings build that apply to every platform.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BuildOptions {
+    pub mode: Mode,
+    /// Number of architectures compiled in parallel
+    pub jobs: usize,
+    /// Fail on missing rust targets and tools instead of installing them
+    pub offline: bool,
+    pub cargo: CargoFlags,
+    /// Package of the workspace to build the bindings for, like cargo's `--package`
+    pub package: Option<String>,
+}
+
+impl BuildOptions {
+    /// Options of a build in `mode` that compiles one architecture at a time, installs missing
+    /// targets and tools and uses the default features.
+    pub fn new(mode: Mode) -> Self {
+        Self {
+            mode,
+            jobs: 1,
+            offline: false,
+            cargo: CargoFlags::default(),
+            package: None,
+        }
+    }
+}
+
+/// Runs the external tools of a build and receives its progress.
+#[derive(Clone, Copy)]
+pub struct BuildContext<'a> {
+    pub runner: &'a dyn CommandRunner,
+    pub observer: &'a dyn BuildObserver,
+}
+
+impl<'a> BuildContext<'a> {
+    pub fn new(runner: &'a dyn CommandRunner, observer: &'a dyn BuildObserver) -> Self {
+        Self { runner, observer }
     }
 }
 
@@ -88,10 +223,26 @@ impl Mode {
 pub trait Arch {
     fn platform() -> Box<dyn Platform>;
     fn as_str(&self) -> &'static str;
-    fn parse_from_str<S: AsRef<str>>(s: S) -> Self;
+    /// Parses one of [`Arch::all_strings`], ignoring the case.
+    fn parse_from_str<S: AsRef<str>>(s: S) -> Result<Self, BindingsError>
+    where
+        Self: Sized;
     fn all_strings() -> Vec<&'static str>;
     fn all_display_strings() -> Vec<(String, String)>;
     fn env_var_name() -> &'static str;
+
+    /// Architectures built when none are configured
+    fn default_strings() -> Vec<&'static str> {
+        Self::all_strings()
+    }
+}
+
+/// Error for an architecture that is not one of `A`'s.
+fn invalid_arch<A: Arch>(arch: &str) -> BindingsError {
+    BindingsError::InvalidArch {
+        arch: arch.to_string(),
+        supported: A::all_strings().iter().map(|s| s.to_string()).collect(),
+    }
 }
 
 // https://developer.apple.com/documentation/xcode/build-settings-reference#Architectures
@@ -140,13 +291,12 @@ impl Arch for IosArch {
             .expect("Unsupported iOS Arch")
     }
 
-    fn parse_from_str<S: AsRef<str>>(s: S) -> Self {
+    fn parse_from_str<S: AsRef<str>>(s: S) -> Result<Self, BindingsError> {
         IOS_ARCHS
             .iter()
             .find(|info| info.str.to_lowercase() == s.as_ref().to_lowercase())
             .map(|info| info.arch)
-            .context(format!("Unsupported iOS Arch '{}'", s.as_ref()))
-            .unwrap()
+            .ok_or_else(|| invalid_arch::<Self>(s.as_ref()))
     }
 
     fn all_strings() -> Vec<&'static str> {
@@ -215,13 +365,12 @@ impl Arch for AndroidArch {
             .expect("Unsupported Android Arch")
     }
 
-    fn parse_from_str<S: AsRef<str>>(s: S) -> Self {
+    fn parse_from_str<S: AsRef<str>>(s: S) -> Result<Self, BindingsError> {
         ANDROID_ARCHS
             .iter()
             .find(|info| info.str.to_lowercase() == s.as_ref().to_lowercase())
             .map(|info| info.arch)
-            .context(format!("Unsupported Android Arch '{}'", s.as_ref()))
-            .unwrap()
+            .ok_or_else(|| invalid_arch::<Self>(s.as_ref()))
     }
 
     fn all_strings() -> Vec<&'static str> {
@@ -240,6 +389,362 @@ impl Arch for AndroidArch {
     }
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DesktopArch {
+    X8664LinuxGnu,
+    Aarch64LinuxGnu,
+}
+
+struct DesktopArchInfo {
+    arch: DesktopArch,
+    str: &'static str,
+    description: &'static str,
+    /// `std::env::consts::ARCH` of a host building the target natively
+    host_arch: &'static str,
+    /// Linker cross-compiling for the target from another host
+    linker: &'static str,
+    /// System libraries a program linking the static library needs, see
+    /// `rustc --print native-static-libs`
+    native_static_libs: &'static str,
+}
+
+const DESKTOP_ARCHS: [DesktopArchInfo; 2] = [
+    DesktopArchInfo {
+        arch: DesktopArch::X8664LinuxGnu,
+        str: "x86_64-unknown-linux-gnu",
+        description: "64-bit Linux desktops and servers (x86_64 architecture)",
+        host_arch: ARCH_X86_64,
+        linker: "x86_64-linux-gnu-gcc",
+        native_static_libs: "-lgcc_s -lutil -lrt -lpthread -lm -ldl -lc",
+    },
+    DesktopArchInfo {
+        arch: DesktopArch::Aarch64LinuxGnu,
+        str: "aarch64-unknown-linux-gnu",
+        description: "64-bit ARM Linux desktops, servers and boards",
+        host_arch: ARCH_ARM_64,
+        linker: "aarch64-linux-gnu-gcc",
+        native_static_libs: "-lgcc_s -lutil -lrt -lpthread -lm -ldl -lc",
+    },
+];
+
+impl DesktopArch {
+    fn info(&self) -> &'static DesktopArchInfo {
+        let archs: &'static [DesktopArchInfo] = &DESKTOP_ARCHS;
+        archs
+            .iter()
+            .find(|info| info.arch == *self)
+            .expect("Unsupported Desktop Arch")
+    }
+
+    /// Whether the machine running the build can build the target without a cross linker.
+    pub fn is_host(&self) -> bool {
+        std::env::consts::OS == "linux" && std::env::consts::ARCH == self.info().host_arch
+    }
+
+    pub fn linker(&self) -> &'static str {
+        self.info().linker
+    }
+
+    pub fn native_static_libs(&self) -> &'static str {
+        self.info().native_static_libs
+    }
+}
+
+impl Arch for DesktopArch {
+    fn platform() -> Box<dyn Platform> {
+        Box::new(DesktopPlatform)
+    }
+
+    fn as_str(&self) -> &'static str {
+        self.info().str
+    }
+
+    fn parse_from_str<S: AsRef<str>>(s: S) -> Result<Self, BindingsError> {
+        DESKTOP_ARCHS
+            .iter()
+            .find(|info| info.str.to_lowercase() == s.as_ref().to_lowercase())
+            .map(|info| info.arch)
+            .ok_or_else(|| invalid_arch::<Self>(s.as_ref()))
+    }
+
+    fn all_strings() -> Vec<&'static str> {
+        DESKTOP_ARCHS.iter().map(|info| info.str).collect()
+    }
+
+    fn all_display_strings() -> Vec<(String, String)> {
+        DESKTOP_ARCHS
+            .iter()
+            .map(|info| (info.str.to_string(), info.description.to_string()))
+            .collect()
+    }
+
+    fn env_var_name() -> &'static str {
+        DESKTOP_ARCHS_ENV
+    }
+
+    /// Only the host's architecture, the others need a cross linker
+    fn default_strings() -> Vec<&'static str> {
+        let host: Vec<&'static str> = DESKTOP_ARCHS
+            .iter()
+            .filter(|info| info.arch.is_host())
+            .map(|info| info.str)
+            .collect();
+        if host.is_empty() {
+            Self::all_strings()
+        } else {
+            host
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum JvmArch {
+    X8664LinuxGnu,
+    Aarch64LinuxGnu,
+}
+
+struct JvmArchInfo {
+    arch: JvmArch,
+    str: &'static str,
+    description: &'static str,
+    /// Resource directory JNA loads the library of the platform from
+    jna_prefix: &'static str,
+    /// The same target built for the desktop bindings
+    desktop: DesktopArch,
+}
+
+const JVM_ARCHS: [JvmArchInfo; 2] = [
+    JvmArchInfo {
+        arch: JvmArch::X8664LinuxGnu,
+        str: "x86_64-unknown-linux-gnu",
+        description: "JVMs on 64-bit Linux (x86_64 architecture)",
+        jna_prefix: "linux-x86-64",
+        desktop: DesktopArch::X8664LinuxGnu,
+    },
+    JvmArchInfo {
+        arch: JvmArch::Aarch64LinuxGnu,
+        str: "aarch64-unknown-linux-gnu",
+        description: "JVMs on 64-bit ARM Linux",
+        jna_prefix: "linux-aarch64",
+        desktop: DesktopArch::Aarch64LinuxGnu,
+    },
+];
+
+impl JvmArch {
+    fn info(&self) -> &'static JvmArchInfo {
+        let archs: &'static [JvmArchInfo] = &JVM_ARCHS;
+        archs
+            .iter()
+            .find(|info| info.arch == *self)
+            .expect("Unsupported JVM Arch")
+    }
+
+    /// Resource directory of the library in the jar, e.g. `linux-x86-64`
+    pub fn jna_prefix(&self) -> &'static str {
+        self.info().jna_prefix
+    }
+
+    pub fn desktop_arch(&self) -> DesktopArch {
+        self.info().desktop
+    }
+}
+
+impl Arch for JvmArch {
+    fn platform() -> Box<dyn Platform> {
+        Box::new(JvmPlatform)
+    }
+
+    fn as_str(&self) -> &'static str {
+        self.info().str
+    }
+
+    fn parse_from_str<S: AsRef<str>>(s: S) -> Result<Self, BindingsError> {
+        JVM_ARCHS
+            .iter()
+            .find(|info| info.str.to_lowercase() == s.as_ref().to_lowercase())
+            .map(|info| info.arch)
+            .ok_or_else(|| invalid_arch::<Self>(s.as_ref()))
+    }
+
+    fn all_strings() -> Vec<&'static str> {
+        JVM_ARCHS.iter().map(|info| info.str).collect()
+    }
+
+    fn all_display_strings() -> Vec<(String, String)> {
+        JVM_ARCHS
+            .iter()
+            .map(|info| (info.str.to_string(), info.description.to_string()))
+            .collect()
+    }
+
+    fn env_var_name() -> &'static str {
+        JVM_ARCHS_ENV
+    }
+
+    /// Only the host's architecture, the others need a cross linker
+    fn default_strings() -> Vec<&'static str> {
+        let host: Vec<&'static str> = JVM_ARCHS
+            .iter()
+            .filter(|info| info.desktop.is_host())
+            .map(|info| info.str)
+            .collect();
+        if host.is_empty() {
+            Self::all_strings()
+        } else {
+            host
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NodeArch {
+    X8664LinuxGnu,
+    Aarch64LinuxGnu,
+    X8664Darwin,
+    Aarch64Darwin,
+    X8664WindowsMsvc,
+}
+
+struct NodeArchInfo {
+    arch: NodeArch,
+    str: &'static str,
+    description: &'static str,
+    /// `std::env::consts::OS` and `std::env::consts::ARCH` of a host building the target natively
+    host: (&'static str, &'static str),
+    /// `process.platform` and `process.arch` of the Node.js processes loading the addon
+    node_host: (&'static str, &'static str),
+    /// Platform suffix of the addon file, following the napi-rs naming, e.g. `linux-x64-gnu`
+    node_triple: &'static str,
+    /// Prefix and suffix of the dynamic library cargo builds
+    dylib: (&'static str, &'static str),
+}
+
+const NODE_ARCHS: [NodeArchInfo; 5] = [
+    NodeArchInfo {
+        arch: NodeArch::X8664LinuxGnu,
+        str: "x86_64-unknown-linux-gnu",
+        description: "64-bit Linux (x86_64 architecture)",
+        host: ("linux", ARCH_X86_64),
+        node_host: ("linux", "x64"),
+        node_triple: "linux-x64-gnu",
+        dylib: ("lib", ".so"),
+    },
+    NodeArchInfo {
+        arch: NodeArch::Aarch64LinuxGnu,
+        str: "aarch64-unknown-linux-gnu",
+        description: "64-bit ARM Linux",
+        host: ("linux", ARCH_ARM_64),
+        node_host: ("linux", "arm64"),
+        node_triple: "linux-arm64-gnu",
+        dylib: ("lib", ".so"),
+    },
+    NodeArchInfo {
+        arch: NodeArch::X8664Darwin,
+        str: "x86_64-apple-darwin",
+        description: "macOS on Intel",
+        host: ("macos", ARCH_X86_64),
+        node_host: ("darwin", "x64"),
+        node_triple: "darwin-x64",
+        dylib: ("lib", ".dylib"),
+    },
+    NodeArchInfo {
+        arch: NodeArch::Aarch64Darwin,
+        str: "aarch64-apple-darwin",
+        description: "macOS on Apple silicon",
+        host: ("macos", ARCH_ARM_64),
+        node_host: ("darwin", "arm64"),
+        node_triple: "darwin-arm64",
+        dylib: ("lib", ".dylib"),
+    },
+    NodeArchInfo {
+        arch: NodeArch::X8664WindowsMsvc,
+        str: "x86_64-pc-windows-msvc",
+        description: "64-bit Windows",
+        host: ("windows", ARCH_X86_64),
+        node_host: ("win32", "x64"),
+        node_triple: "win32-x64-msvc",
+        dylib: ("", ".dll"),
+    },
+];
+
+impl NodeArch {
+    fn info(&self) -> &'static NodeArchInfo {
+        let archs: &'static [NodeArchInfo] = &NODE_ARCHS;
+        archs
+            .iter()
+            .find(|info| info.arch == *self)
+            .expect("Unsupported Node Arch")
+    }
+
+    /// Whether the machine running the build can build the target natively.
+    pub fn is_host(&self) -> bool {
+        (std::env::consts::OS, std::env::consts::ARCH) == self.info().host
+    }
+
+    /// `process.platform` and `process.arch` of the Node.js processes the addon is loaded by.
+    pub fn node_host(&self) -> (&'static str, &'static str) {
+        self.info().node_host
+    }
+
+    /// Name of the addon of the library `lib_name`, e.g. `my_circuits.linux-x64-gnu.node`.
+    pub fn addon_file_name(&self, lib_name: &str) -> String {
+        format!("{lib_name}.{}.node", self.info().node_triple)
+    }
+
+    /// Name of the dynamic library cargo builds for `lib_name`, e.g. `libmy_circuits.so`.
+    pub fn dylib_file_name(&self, lib_name: &str) -> String {
+        let (prefix, suffix) = self.info().dylib;
+        format!("{prefix}{lib_name}{suffix}")
+    }
+}
+
+impl Arch for NodeArch {
+    fn platform() -> Box<dyn Platform> {
+        Box::new(NodePlatform)
+    }
+
+    fn as_str(&self) -> &'static str {
+        self.info().str
+    }
+
+    fn parse_from_str<S: AsRef<str>>(s: S) -> Result<Self, BindingsError> {
+        NODE_ARCHS
+            .iter()
+            .find(|info| info.str.to_lowercase() == s.as_ref().to_lowercase())
+            .map(|info| info.arch)
+            .ok_or_else(|| invalid_arch::<Self>(s.as_ref()))
+    }
+
+    fn all_strings() -> Vec<&'static str> {
+        NODE_ARCHS.iter().map(|info| info.str).collect()
+    }
+
+    fn all_display_strings() -> Vec<(String, String)> {
+        NODE_ARCHS
+            .iter()
+            .map(|info| (info.str.to_string(), info.description.to_string()))
+            .collect()
+    }
+
+    fn env_var_name() -> &'static str {
+        NODE_ARCHS_ENV
+    }
+
+    /// Only the host's architecture, the others need a cross toolchain
+    fn default_strings() -> Vec<&'static str> {
+        let host: Vec<&'static str> = NODE_ARCHS
+            .iter()
+            .filter(|info| info.arch.is_host())
+            .map(|info| info.str)
+            .collect();
+        if host.is_empty() {
+            Self::all_strings()
+        } else {
+            host
+        }
+    }
+}
+
 pub struct WebArch;
 
 impl Arch for WebArch {
@@ -251,8 +756,12 @@ impl Arch for WebArch {
         "wasm32-unknown-unknown"
     }
 
-    fn parse_from_str<S: AsRef<str>>(_s: S) -> Self {
-        WebArch
+    fn parse_from_str<S: AsRef<str>>(s: S) -> Result<Self, BindingsError> {
+        if s.as_ref().eq_ignore_ascii_case("wasm32-unknown-unknown") {
+            Ok(WebArch)
+        } else {
+            Err(invalid_arch::<Self>(s.as_ref()))
+        }
     }
 
     fn all_strings() -> Vec<&'static str> {
@@ -267,7 +776,104 @@ impl Arch for WebArch {
     }
 
     fn env_var_name() -> &'static str {
-        "WEB_ARCHS"
+        WEB_ARCHS_ENV
+    }
+}
+
+/// The machine running the build, which Python bindings are built for
+pub struct PythonArch;
+
+impl Arch for PythonArch {
+    fn platform() -> Box<dyn Platform> {
+        Box::new(PythonPlatform)
+    }
+
+    fn as_str(&self) -> &'static str {
+        "host"
+    }
+
+    fn parse_from_str<S: AsRef<str>>(s: S) -> Result<Self, BindingsError> {
+        if s.as_ref().eq_ignore_ascii_case("host") {
+            Ok(PythonArch)
+        } else {
+            Err(invalid_arch::<Self>(s.as_ref()))
+        }
+    }
+
+    fn all_strings() -> Vec<&'static str> {
+        vec!["host"]
+    }
+
+    fn all_display_strings() -> Vec<(String, String)> {
+        vec![(
+            "host".to_string(),
+            "The machine running the build".to_string(),
+        )]
+    }
+
+    fn env_var_name() -> &'static str {
+        PYTHON_ARCHS_ENV
+    }
+}
+
+/// Architectures of a React Native package, built by the iOS and Android builders
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReactNativeArch {
+    Ios(IosArch),
+    Android(AndroidArch),
+}
+
+impl Arch for ReactNativeArch {
+    fn platform() -> Box<dyn Platform> {
+        Box::new(ReactNativePlatform)
+    }
+
+    fn as_str(&self) -> &'static str {
+        match self {
+            ReactNativeArch::Ios(arch) => arch.as_str(),
+            ReactNativeArch::Android(arch) => arch.as_str(),
+        }
+    }
+
+    fn parse_from_str<S: AsRef<str>>(s: S) -> Result<Self, BindingsError> {
+        let s = s.as_ref();
+        let is_ios = IosArch::all_strings()
+            .iter()
+            .any(|arch| arch.eq_ignore_ascii_case(s));
+        if is_ios {
+            IosArch::parse_from_str(s).map(ReactNativeArch::Ios)
+        } else {
+            AndroidArch::parse_from_str(s)
+                .map(ReactNativeArch::Android)
+                .map_err(|_| invalid_arch::<Self>(s))
+        }
+    }
+
+    fn all_strings() -> Vec<&'static str> {
+        IosArch::all_strings()
+            .into_iter()
+            .chain(AndroidArch::all_strings())
+            .collect()
+    }
+
+    fn all_display_strings() -> Vec<(String, String)> {
+        IosArch::all_display_strings()
+            .into_iter()
+            .chain(AndroidArch::all_display_strings())
+            .collect()
+    }
+
+    fn env_var_name() -> &'static str {
+        REACT_NATIVE_ARCHS_ENV
+    }
+
+    /// The iOS architectures need Xcode, so they are only built by default on macOS
+    fn default_strings() -> Vec<&'static str> {
+        if cfg!(target_os = "macos") {
+            Self::all_strings()
+        } else {
+            AndroidArch::all_strings()
+        }
     }
 }
 
@@ -340,13 +946,12 @@ impl Arch for FlutterArch {
             .expect("Unsupported iOS Arch")
     }
 
-    fn parse_from_str<S: AsRef<str>>(s: S) -> Self {
+    fn parse_from_str<S: AsRef<str>>(s: S) -> Result<Self, BindingsError> {
         FLUTTER_ARCHS
             .iter()
             .find(|info| info.str.to_lowercase() == s.as_ref().to_lowercase())
             .map(|info| info.arch)
-            .context(format!("Unsupported iOS Arch '{}'", s.as_ref()))
-            .unwrap()
+            .ok_or_else(|| invalid_arch::<Self>(s.as_ref()))
     }
 
     fn all_strings() -> Vec<&'static str> {
@@ -373,18 +978,80 @@ pub trait Platform {
     fn identifier() -> &'static str
     where
         Self: Sized;
+
+    /// Short name of the platform, also used as its section name in `mopro.toml`
+    fn name() -> &'static str
+    where
+        Self: Sized;
+
+    /// Cargo feature of `mopro-ffi` the project needs to enable for the platform
+    fn cargo_feature() -> &'static str
+    where
+        Self: Sized;
+
+    /// Directory the bindings are written to inside the output directory, unless configured
+    fn default_bindings_dir() -> &'static str
+    where
+        Self: Sized;
 }
 
-pub trait PlatformBuilder: Platform {
+pub trait PlatformBuilder: Platform + Sized {
     type Arch: Arch;
-    type Params: Default;
+    type Params: FromPlatformConfig + std::hash::Hash;
+
+    /// Computes the steps of building the bindings of `project` for `target_arch` into
+    /// `output_dir`, without running anything or touching the file system.
+    ///
+    /// `fresh_archs` are the architectures whose slices from the previous build are still
+    /// up to date; builders may reuse them instead of compiling the library again.
+    fn plan(
+        options: &BuildOptions,
+        project: &Project,
+        output_dir: &std::path::Path,
+        target_arch: Vec<Self::Arch>,
+        fresh_archs: &[Self::Arch],
+        params: &Self::Params,
+    ) -> Result<BuildPlan, BindingsError>;
+
+    /// Applies the [`BuildStep::Patch`](super::plan::BuildStep::Patch) named `name` of a
+    /// plan of this builder to the generated files at `path`.
+    fn apply_patch(
+        plan: &BuildPlan,
+        name: &str,
+        path: &std::path::Path,
+        params: &Self::Params,
+    ) -> Result<(), BindingsError> {
+        let _ = (plan, path, params);
+        Err(BindingsError::unknown_patch(Self::identifier(), name))
+    }
 
+    /// Builds the bindings for `target_arch` into `output_dir` by executing its [`plan`](Self::plan).
+    /// Every external tool is invoked through the runner of `context`, and the progress is
+    /// reported to its observer.
     fn build(
-        mode: Mode,
-        project_dir: &std::path::Path,
+        options: &BuildOptions,
+        project: &Project,
+        output_dir: &std::path::Path,
         target_arch: Vec<Self::Arch>,
+        fresh_archs: &[Self::Arch],
         params: Self::Params,
-    ) -> anyhow::Result<std::path::PathBuf>;
+        context: BuildContext<'_>,
+    ) -> Result<std::path::PathBuf, BindingsError> {
+        let plan = Self::plan(
+            options,
+            project,
+            output_dir,
+            target_arch,
+            fresh_archs,
+            &params,
+        )?;
+        plan.execute(context, |name, path| {
+            Self::apply_patch(&plan, name, path, &params)
+        })
+    }
+
+    /// Checks the external tools `build` needs for the given architectures.
+    fn toolchain_checks(target_archs: &[Self::Arch]) -> Vec<ToolCheck>;
 }
 
 pub struct IosPlatform;
@@ -393,6 +1060,18 @@ impl Platform for IosPlatform {
     fn identifier() -> &'static str {
         "iOS Bindings Builder"
     }
+
+    fn name() -> &'static str {
+        "ios"
+    }
+
+    fn cargo_feature() -> &'static str {
+        "uniffi"
+    }
+
+    fn default_bindings_dir() -> &'static str {
+        IOS_BINDINGS_DIR
+    }
 }
 
 pub struct AndroidPlatform;
@@ -401,6 +1080,18 @@ impl Platform for AndroidPlatform {
     fn identifier() -> &'static str {
         "Android Bindings Builder"
     }
+
+    fn name() -> &'static str {
+        "android"
+    }
+
+    fn cargo_feature() -> &'static str {
+        "uniffi"
+    }
+
+    fn default_bindings_dir() -> &'static str {
+        ANDROID_BINDINGS_DIR
+    }
 }
 
 pub struct WebPlatform;
@@ -409,6 +1100,18 @@ impl Platform for WebPlatform {
     fn identifier() -> &'static str {
         "Web Bindings Builder"
     }
+
+    fn name() -> &'static str {
+        "web"
+    }
+
+    fn cargo_feature() -> &'static str {
+        "wasm"
+    }
+
+    fn default_bindings_dir() -> &'static str {
+        WEB_BINDINGS_DIR
+    }
 }
 
 pub struct FlutterPlatform;
@@ -417,4 +1120,143 @@ impl Platform for FlutterPlatform {
     fn identifier() -> &'static str {
         "Flutter Bindings Builder"
     }
+
+    fn name() -> &'static str {
+        "flutter"
+    }
+
+    fn cargo_feature() -> &'static str {
+        "flutter"
+    }
+
+    fn default_bindings_dir() -> &'static str {
+        FLUTTER_BINDINGS_DIR
+    }
+}
+
+pub struct DesktopPlatform;
+
+impl Platform for DesktopPlatform {
+    fn identifier() -> &'static str {
+        "Desktop Bindings Builder"
+    }
+
+    fn name() -> &'static str {
+        "desktop"
+    }
+
+    fn cargo_feature() -> &'static str {
+        "uniffi"
+    }
+
+    fn default_bindings_dir() -> &'static str {
+        DESKTOP_BINDINGS_DIR
+    }
+}
+
+pub struct PythonPlatform;
+
+impl Platform for PythonPlatform {
+    fn identifier() -> &'static str {
+        "Python Bindings Builder"
+    }
+
+    fn name() -> &'static str {
+        "python"
+    }
+
+    fn cargo_feature() -> &'static str {
+        "uniffi"
+    }
+
+    fn default_bindings_dir() -> &'static str {
+        PYTHON_BINDINGS_DIR
+    }
+}
+
+pub struct JvmPlatform;
+
+impl Platform for JvmPlatform {
+    fn identifier() -> &'static str {
+        "Kotlin/JVM Bindings Builder"
+    }
+
+    fn name() -> &'static str {
+        "jvm"
+    }
+
+    fn cargo_feature() -> &'static str {
+        "uniffi"
+    }
+
+    fn default_bindings_dir() -> &'static str {
+        JVM_BINDINGS_DIR
+    }
+}
+
+pub struct NodePlatform;
+
+impl Platform for NodePlatform {
+    fn identifier() -> &'static str {
+        "Node.js Bindings Builder"
+    }
+
+    fn name() -> &'static str {
+        "node"
+    }
+
+    /// The crate exports the addon with napi-rs, so the builder needs no binding generator
+    fn cargo_feature() -> &'static str {
+        "build"
+    }
+
+    fn default_bindings_dir() -> &'static str {
+        NODE_BINDINGS_DIR
+    }
+}
+
+pub struct ReactNativePlatform;
+
+impl Platform for ReactNativePlatform {
+    fn identifier() -> &'static str {
+        "React Native Bindings Builder"
+    }
+
+    fn name() -> &'static str {
+        "react-native"
+    }
+
+    fn cargo_feature() -> &'static str {
+        "uniffi"
+    }
+
+    fn default_bindings_dir() -> &'static str {
+        REACT_NATIVE_BINDINGS_DIR
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_arch() {
+        assert_eq!(
+            IosArch::parse_from_str("AARCH64-apple-ios").unwrap(),
+            IosArch::Aarch64Apple
+        );
+        assert_eq!(
+            ReactNativeArch::parse_from_str("x86_64-linux-android").unwrap(),
+            ReactNativeArch::Android(AndroidArch::X8664Linux)
+        );
+        assert!(matches!(
+            AndroidArch::parse_from_str("aarch64-apple-ios"),
+            Err(BindingsError::InvalidArch { supported, .. }) if supported.len() == 4
+        ));
+        assert!(matches!(
+            ReactNativeArch::parse_from_str("wasm32-unknown-unknown"),
+            Err(BindingsError::InvalidArch { supported, .. }) if supported.len() == 7
+        ));
+        assert!(WebArch::parse_from_str("host").is_err());
+    }
 }
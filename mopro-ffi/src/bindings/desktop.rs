@@ -0,0 +1,491 @@
+use std::fs;
+use std::path::Path;
+use std::process::Command;
+
+use super::PlatformBuilder;
+
+use super::config::{FromPlatformConfig, PlatformConfig};
+use super::constants::{
+    Arch, BuildOptions, DesktopArch, DesktopPlatform, Platform, DESKTOP_BINDINGS_DIR,
+    DESKTOP_HEADER_FILE, DESKTOP_INCLUDE_DIR, DESKTOP_PKG_CONFIG_NAME,
+};
+use super::doctor::{check_command, check_rustup_targets, ToolCheck};
+use super::error::BindingsError;
+use super::install_arch;
+use super::manifest::{ArchPath, UNIFFI_VERSION};
+use super::plan::{BindingsLanguage, BuildPlan, BuildStep};
+use super::project::Project;
+use super::{arch_target_dir, work_dir_path};
+
+/// Patch making the header uniffi generates for Swift compile with any C compiler
+const C_HEADER_PATCH: &str = "c-header";
+
+#[inline]
+pub fn build() {
+    super::build_from_env::<DesktopPlatform>().unwrap_or_else(|e| {
+        panic!(
+            "Failed to build {} bindings: {e}",
+            DesktopPlatform::identifier()
+        )
+    })
+}
+
+#[derive(Hash)]
+pub struct DesktopBindingsParams {
+    /// Name of the generated bindings directory
+    pub bindings_dir: String,
+}
+
+impl Default for DesktopBindingsParams {
+    fn default() -> Self {
+        Self {
+            bindings_dir: DESKTOP_BINDINGS_DIR.to_string(),
+        }
+    }
+}
+
+impl FromPlatformConfig for DesktopBindingsParams {
+    fn from_platform_config(config: &PlatformConfig) -> anyhow::Result<Self> {
+        config.check_params(&[])?;
+        Ok(Self {
+            bindings_dir: config
+                .bindings_dir
+                .clone()
+                .unwrap_or_else(|| DESKTOP_BINDINGS_DIR.to_string()),
+        })
+    }
+}
+
+impl PlatformBuilder for DesktopPlatform {
+    type Arch = DesktopArch;
+    type Params = DesktopBindingsParams;
+
+    /// Lays the bindings out as
+    ///
+    /// ```text
+    /// MoproDesktopBindings/
+    /// ├── include/mopro.h
+    /// └── <target>/lib/
+    ///     ├── lib<name>.so
+    ///     ├── lib<name>.a            (if the crate is a staticlib)
+    ///     └── pkgconfig/mopro.pc
+    /// ```
+    fn plan(
+        options: &BuildOptions,
+        project: &Project,
+        output_dir: &Path,
+        target_archs: Vec<Self::Arch>,
+        fresh_archs: &[Self::Arch],
+        params: &Self::Params,
+    ) -> Result<BuildPlan, BindingsError> {
+        if !cfg!(feature = "uniffi") {
+            return Err(BindingsError::MissingFeature { feature: "uniffi" });
+        }
+
+        // uniffi reads the bindings from the dynamic library, the static one is optional
+        project.require_crate_types(&["cdylib"])?;
+        let with_static_lib = project.crate_types.iter().any(|c| c == "staticlib");
+        let project_dir = &project.manifest_dir;
+        let uniffi_style_identifier = &project.lib_name;
+
+        let bindings_dir_name = &params.bindings_dir;
+
+        // Names for the generated files by uniffi
+        let gen_header_file_name = format!("{uniffi_style_identifier}FFI.h");
+        let dylib_name = format!("lib{uniffi_style_identifier}.so");
+        let staticlib_name = format!("lib{uniffi_style_identifier}.a");
+
+        // Paths for the generated files
+        let work_dir = work_dir_path(&project_dir.join("build"));
+        // The C header is the one uniffi generates for the Swift module map
+        let swift_bindings_dir = work_dir.join("SwiftBindings");
+        let bindings_out = work_dir.join(bindings_dir_name);
+        let bindings_dest = output_dir.join(bindings_dir_name);
+
+        let mut plan = BuildPlan::new(Self::name(), options, project, output_dir, &bindings_dest);
+        plan.work_dir = Some(work_dir.clone());
+        plan.steps.push(BuildStep::CreateDir {
+            path: bindings_out.clone(),
+        });
+
+        let mode = &options.mode;
+        let parallel = options.jobs > 1 && target_archs.len() > 1;
+        let out_lib_dir = |arch: &DesktopArch| {
+            arch_target_dir(&project.target_dir, arch.as_str(), parallel)
+                .join(arch.as_str())
+                .join(mode.as_str())
+        };
+        let lib_dir = |arch: &DesktopArch| bindings_out.join(arch.as_str()).join("lib");
+
+        // Targets built with the same inputs before keep their libraries, if still there
+        let stale_archs: Vec<DesktopArch> = target_archs
+            .iter()
+            .copied()
+            .filter(|arch| {
+                !(fresh_archs.contains(arch) && out_lib_dir(arch).join(&dylib_name).exists())
+            })
+            .collect();
+        if !options.offline {
+            for arch in &stale_archs {
+                plan.steps.push(install_arch(arch.as_str()));
+            }
+        }
+        for arch in &stale_archs {
+            plan.steps.push(build_for_arch(
+                *arch,
+                project_dir,
+                &arch_target_dir(&project.target_dir, arch.as_str(), parallel),
+                options,
+            ));
+        }
+
+        for arch in &target_archs {
+            let mut lib_names = vec![&dylib_name];
+            if with_static_lib {
+                lib_names.push(&staticlib_name);
+            }
+            for lib_name in lib_names {
+                plan.steps.push(BuildStep::Copy {
+                    from: out_lib_dir(arch).join(lib_name),
+                    to: lib_dir(arch).join(lib_name),
+                });
+            }
+            let pkg_config_dir = lib_dir(arch).join("pkgconfig");
+            plan.steps.push(BuildStep::CreateDir {
+                path: pkg_config_dir.clone(),
+            });
+            plan.steps.push(BuildStep::WriteFile {
+                path: pkg_config_dir.join(format!("{DESKTOP_PKG_CONFIG_NAME}.pc")),
+                contents: pkg_config_file(project, *arch, with_static_lib),
+            });
+        }
+
+        let out_dylib_path = out_lib_dir(&target_archs[0]).join(&dylib_name);
+        plan.steps.push(BuildStep::GenerateBindings {
+            language: BindingsLanguage::Swift,
+            library: out_dylib_path,
+            out_dir: swift_bindings_dir.clone(),
+        });
+        let header_path = bindings_out
+            .join(DESKTOP_INCLUDE_DIR)
+            .join(DESKTOP_HEADER_FILE);
+        plan.steps.push(BuildStep::Copy {
+            from: swift_bindings_dir.join(gen_header_file_name),
+            to: header_path.clone(),
+        });
+        plan.steps
+            .push(BuildStep::patch(C_HEADER_PATCH, header_path));
+        plan.steps.push(BuildStep::Install {
+            from: bindings_out,
+            to: bindings_dest.clone(),
+        });
+        plan.steps.push(BuildStep::WriteManifest {
+            dir: bindings_dest,
+            uniffi_version: Some(UNIFFI_VERSION.to_string()),
+            arch_paths: target_archs
+                .iter()
+                .map(|arch| ArchPath::new(arch.as_str(), &[arch.as_str()]))
+                .collect(),
+        });
+
+        Ok(plan)
+    }
+
+    fn apply_patch(
+        _plan: &BuildPlan,
+        name: &str,
+        path: &Path,
+        _params: &Self::Params,
+    ) -> Result<(), BindingsError> {
+        if name != C_HEADER_PATCH {
+            return Err(BindingsError::unknown_patch(Self::identifier(), name));
+        }
+        let header = fs::read_to_string(path).map_err(BindingsError::io(format!(
+            "Failed to read {}",
+            path.display()
+        )))?;
+        // The nullability qualifiers of the Swift header are clang extensions
+        let header =
+            format!("#ifndef __clang__\n#define _Nullable\n#define _Nonnull\n#endif\n\n{header}");
+        fs::write(path, header).map_err(BindingsError::io(format!(
+            "Failed to write {}",
+            path.display()
+        )))
+    }
+
+    fn toolchain_checks(target_archs: &[Self::Arch]) -> Vec<ToolCheck> {
+        let targets: Vec<&str> = target_archs.iter().map(|arch| arch.as_str()).collect();
+        let mut checks = check_rustup_targets(None, &targets);
+        checks.push(check_command(
+            "cc",
+            "cc",
+            &["--version"],
+            "Install a C toolchain, e.g. sudo apt install build-essential",
+        ));
+        for arch in target_archs.iter().filter(|arch| !arch.is_host()) {
+            let linker = arch.linker();
+            checks.push(check_command(
+                linker,
+                linker,
+                &["--version"],
+                &format!(
+                    "Install the cross linker, e.g. sudo apt install gcc-{}",
+                    linker.trim_end_matches("-gcc")
+                ),
+            ));
+        }
+        checks
+    }
+}
+
+/// Command building the libraries for `arch`. Targets other than the host's are linked with
+/// the arch's cross linker, unless cargo is configured with one through the environment.
+pub(crate) fn build_for_arch(
+    arch: DesktopArch,
+    project_dir: &Path,
+    target_dir: &Path,
+    options: &BuildOptions,
+) -> BuildStep {
+    let arch_str = arch.as_str();
+    let mut build_cmd = Command::new("cargo");
+    build_cmd
+        .arg("build")
+        .arg("--lib")
+        .args(options.mode.cargo_args())
+        .args(options.cargo.feature_args());
+    if let Some(rustflags) = options.cargo.rustflags(arch_str) {
+        build_cmd.env("RUSTFLAGS", rustflags);
+    }
+    let linker_env = format!(
+        "CARGO_TARGET_{}_LINKER",
+        arch_str.to_uppercase().replace('-', "_")
+    );
+    if !arch.is_host() && std::env::var_os(&linker_env).is_none() {
+        build_cmd.env(linker_env, arch.linker());
+    }
+    build_cmd
+        .current_dir(project_dir)
+        .env("CARGO_BUILD_TARGET_DIR", target_dir)
+        .env("CARGO_BUILD_TARGET", arch_str);
+    BuildStep::arch_command(arch_str, &build_cmd)
+}
+
+/// pkg-config file of the libraries of `arch`, written to `<target>/lib/pkgconfig` so that
+/// the paths relative to it point into the bindings wherever they are installed.
+fn pkg_config_file(project: &Project, arch: DesktopArch, with_static_lib: bool) -> String {
+    let mut contents = format!(
+        "prefix=${{pcfiledir}}/../../..\n\
+         libdir=${{pcfiledir}}/..\n\
+         includedir=${{prefix}}/{DESKTOP_INCLUDE_DIR}\n\
+         \n\
+         Name: {DESKTOP_PKG_CONFIG_NAME}\n\
+         Description: Mopro bindings of {} for {}\n\
+         Version: {}\n\
+         Libs: -L${{libdir}} -l{}\n",
+        project.package,
+        arch.as_str(),
+        project.version,
+        project.lib_name
+    );
+    if with_static_lib {
+        contents.push_str(&format!("Libs.private: {}\n", arch.native_static_libs()));
+    }
+    contents.push_str("Cflags: -I${includedir}\n");
+    contents
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bindings::constants::{BuildContext, Mode};
+    use crate::bindings::observer::NoopObserver;
+    use crate::bindings::project::test_project;
+    use crate::bindings::runner::RecordingRunner;
+
+    #[test]
+    fn test_build_commands() {
+        let project = test_project("my-circuits", &["lib", "cdylib", "staticlib"]);
+        let project_dir = project.manifest_dir.clone();
+
+        let runner = RecordingRunner::new();
+        let bindings_dir = DesktopPlatform::build(
+            &BuildOptions::new(Mode::Release),
+            &project,
+            &project_dir,
+            vec![DesktopArch::X8664LinuxGnu, DesktopArch::Aarch64LinuxGnu],
+            &[],
+            DesktopBindingsParams::default(),
+            BuildContext::new(&runner, &NoopObserver),
+        )
+        .unwrap();
+        assert_eq!(bindings_dir, project_dir.join(DESKTOP_BINDINGS_DIR));
+
+        let commands = runner.commands();
+        let lines: Vec<String> = commands.iter().map(|cmd| cmd.command()).collect();
+        assert_eq!(
+            lines,
+            [
+                "rustup target add x86_64-unknown-linux-gnu",
+                "rustup target add aarch64-unknown-linux-gnu",
+                "cargo build --lib --release",
+                "cargo build --lib --release",
+            ]
+        );
+        // Only the targets of other hosts are cross linked
+        for (command, arch) in commands[2..]
+            .iter()
+            .zip([DesktopArch::X8664LinuxGnu, DesktopArch::Aarch64LinuxGnu])
+        {
+            let linker = command
+                .envs
+                .iter()
+                .find(|(key, _)| key.ends_with("_LINKER"))
+                .and_then(|(_, value)| value.clone());
+            assert_eq!(linker.is_none(), arch.is_host());
+        }
+    }
+
+    #[test]
+    fn test_bindings_layout() {
+        let project = test_project("my-circuits", &["lib", "cdylib", "staticlib"]);
+        let project_dir = project.manifest_dir.clone();
+
+        let plan = DesktopPlatform::plan(
+            &BuildOptions::new(Mode::Debug),
+            &project,
+            &project_dir,
+            vec![DesktopArch::Aarch64LinuxGnu],
+            &[],
+            &DesktopBindingsParams::default(),
+        )
+        .unwrap();
+        let work_dir = plan.work_dir.clone().unwrap();
+        let bindings_out = work_dir.join(DESKTOP_BINDINGS_DIR);
+        let lib_dir = bindings_out.join("aarch64-unknown-linux-gnu/lib");
+        let out_dir = project.target_dir.join("aarch64-unknown-linux-gnu/debug");
+
+        assert_eq!(
+            plan.copies(),
+            [
+                (
+                    out_dir.join("libmy_circuits.so").as_path(),
+                    lib_dir.join("libmy_circuits.so").as_path()
+                ),
+                (
+                    out_dir.join("libmy_circuits.a").as_path(),
+                    lib_dir.join("libmy_circuits.a").as_path()
+                ),
+                (
+                    work_dir.join("SwiftBindings/my_circuitsFFI.h").as_path(),
+                    bindings_out.join("include/mopro.h").as_path()
+                ),
+            ]
+        );
+
+        let pkg_config = plan.written_file("pkgconfig/mopro.pc").unwrap();
+        assert!(pkg_config.contains("Version: 0.1.0\n"));
+        assert!(pkg_config.contains("Libs: -L${libdir} -lmy_circuits\n"));
+        assert!(pkg_config.contains("Libs.private: -lgcc_s"));
+        assert!(pkg_config.contains("Cflags: -I${includedir}\n"));
+    }
+
+    /// Builds the bindings of a uniffi crate and calls them from a C program linked through
+    /// pkg-config, if a C compiler and pkg-config are installed.
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn test_link_c_program() {
+        use crate::bindings::doctor::command_output;
+        use crate::bindings::project::uniffi_test_project;
+        use crate::bindings::runner::SystemRunner;
+
+        if command_output("cc", &["--version"]).is_err()
+            || command_output("pkg-config", &["--version"]).is_err()
+        {
+            eprintln!("cc or pkg-config is not installed, skipping");
+            return;
+        }
+        let Some(host) = DesktopArch::all_strings()
+            .into_iter()
+            .map(|arch| DesktopArch::parse_from_str(arch).unwrap())
+            .find(|arch| arch.is_host())
+        else {
+            eprintln!("The host is not a desktop target, skipping");
+            return;
+        };
+        let project = uniffi_test_project("desktop-smoke");
+        let project_dir = project.manifest_dir.clone();
+
+        let runner = SystemRunner::new();
+        let bindings_dir = DesktopPlatform::build(
+            &BuildOptions {
+                offline: true,
+                ..BuildOptions::new(Mode::Debug)
+            },
+            &project,
+            &project_dir,
+            vec![host],
+            &[],
+            DesktopBindingsParams::default(),
+            BuildContext::new(&runner, &NoopObserver),
+        )
+        .unwrap();
+        let lib_dir = bindings_dir.join(host.as_str()).join("lib");
+
+        // Strings are passed as RustBuffers holding their UTF-8 bytes
+        let main_c = project_dir.join("main.c");
+        fs::write(
+            &main_c,
+            r#"#include <stdio.h>
+#include <string.h>
+#include "mopro.h"
+
+int main(void) {
+    const char *name = "C";
+    RustCallStatus status = {0};
+    ForeignBytes bytes = {(int32_t)strlen(name), (const uint8_t *)name};
+    RustBuffer arg = ffi_desktop_smoke_rustbuffer_from_bytes(bytes, &status);
+    if (status.code != 0) return 1;
+    RustBuffer greeting = uniffi_desktop_smoke_fn_func_greet(arg, &status);
+    if (status.code != 0) return 1;
+    printf("%.*s\n", (int)greeting.len, (const char *)greeting.data);
+    ffi_desktop_smoke_rustbuffer_free(greeting, &status);
+    return status.code;
+}
+"#,
+        )
+        .unwrap();
+        let flags = Command::new("pkg-config")
+            .args(["--cflags", "--libs", DESKTOP_PKG_CONFIG_NAME])
+            .env("PKG_CONFIG_PATH", lib_dir.join("pkgconfig"))
+            .output()
+            .unwrap();
+        assert!(
+            flags.status.success(),
+            "{}",
+            String::from_utf8_lossy(&flags.stderr)
+        );
+        let program = project_dir.join("main");
+        let output = Command::new("cc")
+            .arg(&main_c)
+            .args(String::from_utf8_lossy(&flags.stdout).split_whitespace())
+            .arg("-o")
+            .arg(&program)
+            .output()
+            .unwrap();
+        assert!(
+            output.status.success(),
+            "{}",
+            String::from_utf8_lossy(&output.stderr)
+        );
+
+        let output = Command::new(&program)
+            .env("LD_LIBRARY_PATH", &lib_dir)
+            .output()
+            .unwrap();
+        assert!(output.status.success());
+        assert_eq!(
+            String::from_utf8_lossy(&output.stdout).trim(),
+            "Hi Mopro, C!"
+        );
+    }
+}
@@ -0,0 +1,229 @@
+//! Toolchain checks used by `mopro doctor`.
+//!
+//! Every [`PlatformBuilder`](super::constants::PlatformBuilder) reports the external tools it
+//! spawns as a list of [`ToolCheck`]s, so missing tools can be found before a build fails
+//! halfway through.
+
+use std::path::{Path, PathBuf};
+use std::process::{Command, Output};
+
+/// Result of checking a single external tool.
+#[derive(Debug, Clone)]
+pub struct ToolCheck {
+    pub name: String,
+    /// The version or location that was found, or what is wrong
+    pub result: Result<String, String>,
+    /// Command or instruction that fixes a failed check
+    pub fix: String,
+}
+
+impl ToolCheck {
+    pub fn new(
+        name: impl Into<String>,
+        result: Result<String, String>,
+        fix: impl Into<String>,
+    ) -> Self {
+        Self {
+            name: name.into(),
+            result,
+            fix: fix.into(),
+        }
+    }
+
+    pub fn passed(&self) -> bool {
+        self.result.is_ok()
+    }
+}
+
+/// Runs `program args` and returns the first line of its output.
+pub fn command_output(program: &str, args: &[&str]) -> Result<String, String> {
+    let output = run(program, args)?;
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    Ok(stdout
+        .lines()
+        .chain(stderr.lines())
+        .map(str::trim)
+        .find(|line| !line.is_empty())
+        .unwrap_or_default()
+        .to_string())
+}
+
+/// Checks that a command can be run, reporting its version output.
+pub fn check_command(name: &str, program: &str, args: &[&str], fix: &str) -> ToolCheck {
+    ToolCheck::new(name, command_output(program, args), fix)
+}
+
+/// Checks that the rust targets are installed, for the given toolchain or the default one.
+pub fn check_rustup_targets(toolchain: Option<&str>, targets: &[&str]) -> Vec<ToolCheck> {
+    let mut args = vec!["target", "list", "--installed"];
+    if let Some(toolchain) = toolchain {
+        args.extend(["--toolchain", toolchain]);
+    }
+    let toolchain_arg = toolchain
+        .map(|t| format!(" --toolchain {t}"))
+        .unwrap_or_default();
+
+    let installed = match installed_lines("rustup", &args) {
+        Ok(installed) => installed,
+        Err(e) => {
+            return vec![ToolCheck::new(
+                "rustup",
+                Err(e),
+                "Install rustup from https://rustup.rs",
+            )]
+        }
+    };
+
+    targets
+        .iter()
+        .map(|target| {
+            let result = if installed.iter().any(|t| t == target) {
+                Ok("installed".to_string())
+            } else {
+                Err("not installed".to_string())
+            };
+            ToolCheck::new(
+                format!("target {target}"),
+                result,
+                format!("rustup target add {target}{toolchain_arg}"),
+            )
+        })
+        .collect()
+}
+
+/// Checks that a rustup toolchain is installed together with the given components.
+pub fn check_rustup_toolchain(toolchain: &str, components: &[&str]) -> Vec<ToolCheck> {
+    let installed = match installed_lines("rustup", &["toolchain", "list"]) {
+        Ok(installed) => installed,
+        Err(e) => {
+            return vec![ToolCheck::new(
+                "rustup",
+                Err(e),
+                "Install rustup from https://rustup.rs",
+            )]
+        }
+    };
+    if !installed.iter().any(|t| t.starts_with(toolchain)) {
+        return vec![ToolCheck::new(
+            format!("toolchain {toolchain}"),
+            Err("not installed".to_string()),
+            format!("rustup toolchain install {toolchain}"),
+        )];
+    }
+
+    let mut checks = vec![ToolCheck::new(
+        format!("toolchain {toolchain}"),
+        Ok("installed".to_string()),
+        "",
+    )];
+    let installed_components = installed_lines(
+        "rustup",
+        &["component", "list", "--installed", "--toolchain", toolchain],
+    )
+    .unwrap_or_default();
+    checks.extend(components.iter().map(|component| {
+        let result = if installed_components
+            .iter()
+            .any(|c| c.starts_with(component))
+        {
+            Ok("installed".to_string())
+        } else {
+            Err("not installed".to_string())
+        };
+        ToolCheck::new(
+            format!("component {component} ({toolchain})"),
+            result,
+            format!("rustup component add {component} --toolchain {toolchain}"),
+        )
+    }));
+    checks
+}
+
+/// Checks that the Android NDK can be found the same way `cargo ndk` looks for it.
+pub fn check_android_ndk() -> ToolCheck {
+    ToolCheck::new(
+        "Android NDK",
+        android_ndk_dir()
+            .map(|dir| dir.display().to_string())
+            .ok_or(
+                "ANDROID_NDK_HOME is not set and no NDK was found in the Android SDK".to_string(),
+            ),
+        "Install the NDK with Android Studio's SDK Manager and set ANDROID_NDK_HOME",
+    )
+}
+
+fn android_ndk_dir() -> Option<PathBuf> {
+    for var in ["ANDROID_NDK_HOME", "ANDROID_NDK_ROOT", "NDK_HOME"] {
+        if let Some(dir) = std::env::var_os(var).map(PathBuf::from) {
+            if dir.is_dir() {
+                return Some(dir);
+            }
+        }
+    }
+
+    // Fall back to the newest NDK installed in the Android SDK
+    ["ANDROID_HOME", "ANDROID_SDK_ROOT"]
+        .iter()
+        .filter_map(|var| std::env::var_os(var).map(PathBuf::from))
+        .filter_map(|sdk| std::fs::read_dir(sdk.join("ndk")).ok())
+        .flat_map(|entries| entries.filter_map(|e| e.ok().map(|e| e.path())))
+        .filter(|dir| dir.is_dir())
+        .max_by_key(|dir| ndk_version(dir))
+}
+
+/// Version of the NDK in `dir`, named after it, e.g. `[26, 1, 10909125]` for `ndk/26.1.10909125`.
+/// Compared as numbers so that `27.0.0` is newer than `9.0.0`.
+fn ndk_version(dir: &Path) -> Vec<u64> {
+    dir.file_name()
+        .map(|name| name.to_string_lossy())
+        .unwrap_or_default()
+        .split('.')
+        .map(|part| part.parse().unwrap_or(0))
+        .collect()
+}
+
+fn installed_lines(program: &str, args: &[&str]) -> Result<Vec<String>, String> {
+    let output = run(program, args)?;
+    Ok(String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .map(|line| line.trim().to_string())
+        .collect())
+}
+
+fn run(program: &str, args: &[&str]) -> Result<Output, String> {
+    let output = Command::new(program)
+        .args(args)
+        .output()
+        .map_err(|e| match e.kind() {
+            std::io::ErrorKind::NotFound => format!("`{program}` not found in PATH"),
+            _ => format!("Failed to run `{program}`: {e}"),
+        })?;
+    if !output.status.success() {
+        return Err(format!(
+            "`{program} {}` exited with {}",
+            args.join(" "),
+            output.status
+        ));
+    }
+    Ok(output)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_ndk_version_ordering() {
+        let dirs = [
+            PathBuf::from("/sdk/ndk/9.0.0"),
+            PathBuf::from("/sdk/ndk/27.0.12077973"),
+            PathBuf::from("/sdk/ndk/26.1.10909125"),
+        ];
+        assert_eq!(ndk_version(&dirs[2]), [26, 1, 10909125]);
+        assert_eq!(
+            dirs.iter().max_by_key(|dir| ndk_version(dir)),
+            Some(&dirs[1])
+        );
+    }
+}
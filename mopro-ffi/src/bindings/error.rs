@@ -0,0 +1,183 @@
+use std::fmt::Display;
+use std::io;
+use std::path::PathBuf;
+use std::process::ExitStatus;
+
+use super::doctor::ToolCheck;
+
+/// Errors returned by the bindings builders.
+#[derive(Debug, thiserror::Error)]
+pub enum BindingsError {
+    /// The cargo feature the platform needs is not enabled on `mopro-ffi`
+    #[error("\"{feature}\" feature is not enabled, please enable it in your Cargo.toml")]
+    MissingFeature { feature: &'static str },
+
+    /// An external tool could not be started
+    #[error("`{tool}` not found, is it installed?")]
+    ToolNotFound {
+        tool: String,
+        #[source]
+        source: io::Error,
+    },
+
+    /// An external tool exited unsuccessfully
+    #[error("`{tool}` failed ({status})")]
+    ToolFailed {
+        tool: String,
+        status: ExitStatus,
+        /// Last lines the tool wrote to stderr
+        stderr: String,
+    },
+
+    /// A parallel job was stopped because another job of the build failed
+    #[error("`{tool}` was stopped after another job failed")]
+    Cancelled { tool: String },
+
+    /// An architecture that the platform or host does not support
+    #[error("Unsupported architecture '{arch}', expected one of: {}", supported.join(", "))]
+    InvalidArch {
+        arch: String,
+        supported: Vec<String>,
+    },
+
+    #[error("No target architectures specified for {platform} bindings")]
+    NoTargetArchs { platform: &'static str },
+
+    #[error("Custom cargo profile '{profile}' is not supported for {platform} bindings, use debug or release")]
+    UnsupportedProfile {
+        platform: &'static str,
+        profile: String,
+    },
+
+    /// Rust targets or tools an offline build would have had to install
+    #[error(
+        "Missing targets or tools, which offline builds do not install:\n{}",
+        missing_list(missing)
+    )]
+    MissingTools { missing: Vec<ToolCheck> },
+
+    /// `--package` names a package that is not in the workspace
+    #[error("Package '{package}' is not in the workspace, expected one of: {}", available.join(", "))]
+    PackageNotFound {
+        package: String,
+        available: Vec<String>,
+    },
+
+    /// A workspace root has no package of its own and not exactly one library to bind
+    #[error(
+        "Select the package to build bindings for with --package or `package` in mopro.toml{}",
+        one_of(candidates)
+    )]
+    PackageRequired { candidates: Vec<String> },
+
+    #[error("Package '{package}' has no library target to build bindings for")]
+    MissingLibTarget { package: String },
+
+    /// The library is not built as a crate type the platform links against
+    #[error(
+        "The library of '{package}' is not built as a {crate_type} (crate-type = [{}]), add \"{crate_type}\" to `crate-type` under [lib] in its Cargo.toml",
+        quoted_list(crate_types)
+    )]
+    MissingCrateType {
+        package: String,
+        crate_type: &'static str,
+        crate_types: Vec<String>,
+    },
+
+    /// Settings from `mopro.toml` or the environment that cannot be used
+    #[error("Invalid configuration: {message}")]
+    Config { message: String },
+
+    /// A `Cargo.toml` that is missing or cannot be understood
+    #[error("Invalid manifest {}: {message}", path.display())]
+    ProjectManifest { path: PathBuf, message: String },
+
+    /// A plan names a patch the builder executing it does not have
+    #[error("{platform} has no patch named '{patch}'")]
+    UnknownPatch {
+        platform: &'static str,
+        patch: String,
+    },
+
+    /// A Node.js addon without the N-API entry point, so `require()` cannot load it
+    #[error(
+        "{} does not export `napi_register_module_v1`, so Node.js cannot load it. Export the functions of '{package}' with napi-rs (`#[napi]`), the Node.js bindings are not generated from uniffi",
+        addon.display()
+    )]
+    MissingNapiEntry { package: String, addon: PathBuf },
+
+    /// Generating the foreign-language bindings failed
+    #[error("Failed to generate bindings: {message}")]
+    Bindgen { message: String },
+
+    #[error("{context}")]
+    Io {
+        context: String,
+        #[source]
+        source: io::Error,
+    },
+}
+
+impl BindingsError {
+    /// Wraps an I/O error with a description of what failed, for use with `map_err`.
+    pub fn io(context: impl Display) -> impl FnOnce(io::Error) -> Self {
+        move |source| Self::Io {
+            context: context.to_string(),
+            source,
+        }
+    }
+
+    pub fn manifest(path: impl Into<PathBuf>, message: impl Display) -> Self {
+        Self::ProjectManifest {
+            path: path.into(),
+            message: message.to_string(),
+        }
+    }
+
+    /// Wraps an error of the configuration, keeping its context, for use with `map_err`.
+    pub fn config(error: anyhow::Error) -> Self {
+        Self::Config {
+            message: format!("{error:#}"),
+        }
+    }
+
+    pub fn unknown_patch(platform: &'static str, patch: &str) -> Self {
+        Self::UnknownPatch {
+            platform,
+            patch: patch.to_string(),
+        }
+    }
+
+    pub fn bindgen(message: impl Display) -> Self {
+        Self::Bindgen {
+            message: message.to_string(),
+        }
+    }
+}
+
+fn missing_list(missing: &[ToolCheck]) -> String {
+    missing
+        .iter()
+        .map(|check| {
+            let problem = check.result.as_ref().err().map_or("", String::as_str);
+            format!("  - {}: {problem} (fix: {})", check.name, check.fix)
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+fn one_of(candidates: &[String]) -> String {
+    if candidates.is_empty() {
+        String::new()
+    } else {
+        format!(", one of: {}", candidates.join(", "))
+    }
+}
+
+fn quoted_list(values: &[String]) -> String {
+    values
+        .iter()
+        .map(|value| format!("\"{value}\""))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
@@ -0,0 +1,398 @@
+//! Fingerprints of the inputs of a bindings build, used to skip builds whose inputs
+//! did not change since the last build.
+//!
+//! The fingerprint of a platform is stored in `<output_dir>/.mopro/<platform>.fingerprint.toml`
+//! next to the bindings:
+//!
+//! ```toml
+//! bindings_dir = "/path/to/project/MoproiOSBindings"
+//! inputs = "3f1c..."
+//!
+//! [archs]
+//! aarch64-apple-ios = "9b2e..."
+//! ```
+//!
+//! `inputs` covers the sources of the crate and of its local path dependencies, the
+//! `.cargo/config.toml` files cargo reads for the build, `Cargo.lock`, the build mode, features
+//! and the platform parameters. Every architecture slice gets its own hash derived from the
+//! inputs, so adding an architecture only builds the new slice.
+
+use sha2::{Digest, Sha256};
+use std::collections::BTreeMap;
+use std::fs;
+use std::hash::{Hash, Hasher};
+use std::path::{Path, PathBuf};
+use toml::{Table, Value};
+
+use super::constants::{CargoFlags, Mode};
+use super::error::BindingsError;
+use super::manifest::MANIFEST_FILE;
+use super::project::Project;
+
+/// Directory in the output directory holding the fingerprints of every platform.
+pub const FINGERPRINT_DIR: &str = ".mopro";
+
+/// Directories of the project that never contain build inputs.
+const IGNORED_DIRS: [&str; 3] = ["target", "build", "node_modules"];
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Fingerprint {
+    /// Bindings directory produced by the build
+    pub bindings_dir: PathBuf,
+    /// Hash of the sources, cargo configuration, `Cargo.lock`, build mode, features and
+    /// parameters
+    pub inputs: String,
+    /// Hash of every architecture slice, keyed by the architecture
+    pub archs: BTreeMap<String, String>,
+}
+
+impl Fingerprint {
+    /// Computes the fingerprint of a build of `project` for the given architectures.
+    /// `bindings_dir` is filled in once the build produced the bindings.
+    /// `params_hash` is the [`hash_params`] of the platform parameters.
+    /// `bindings_dirs` are the bindings directories of every platform, which are not sources
+    /// when they are written into a package, also not the leftovers of a failed build.
+    pub fn compute(
+        project: &Project,
+        mode: &Mode,
+        cargo: &CargoFlags,
+        archs: &[&str],
+        params_hash: &str,
+        bindings_dirs: &[PathBuf],
+    ) -> Result<Self, BindingsError> {
+        let skipped_dirs: Vec<PathBuf> = bindings_dirs
+            .iter()
+            .chain([&project.target_dir])
+            .map(|dir| fs::canonicalize(dir).unwrap_or_else(|_| dir.clone()))
+            .collect();
+        let mut hasher = Sha256::new();
+        for source_dir in &project.source_dirs {
+            hasher.update(b"package\0");
+            hash_sources(&mut hasher, source_dir, &skipped_dirs)?;
+        }
+        let lock_file = find_lock_file(&project.manifest_dir);
+        for path in cargo_config_files(&project.manifest_dir)
+            .iter()
+            .chain(lock_file.iter())
+        {
+            hasher.update(path.to_string_lossy().as_bytes());
+            hasher.update([0]);
+            hasher.update(fs::read(path).map_err(BindingsError::io(format!(
+                "Failed to read {}",
+                path.display()
+            )))?);
+        }
+        hasher.update(mode.as_str().as_bytes());
+        for arg in cargo.feature_args() {
+            hasher.update(b"\0");
+            hasher.update(arg.as_bytes());
+        }
+        hasher.update(params_hash.as_bytes());
+        let inputs = hex(&hasher.finalize());
+
+        let archs = archs
+            .iter()
+            .map(|arch| {
+                let mut hasher = Sha256::new();
+                hasher.update(inputs.as_bytes());
+                hasher.update(arch.as_bytes());
+                // Flags of a single target only make its slice stale
+                if let Some(rustflags) = cargo.rustflags(arch) {
+                    hasher.update(b"\0");
+                    hasher.update(rustflags.as_bytes());
+                }
+                (arch.to_string(), hex(&hasher.finalize()))
+            })
+            .collect();
+
+        Ok(Self {
+            bindings_dir: PathBuf::new(),
+            inputs,
+            archs,
+        })
+    }
+
+    /// Loads the fingerprint of the platform's last build, if there is a readable one.
+    pub fn load(output_dir: &Path, platform: &str) -> Option<Self> {
+        let content = fs::read_to_string(fingerprint_path(output_dir, platform)).ok()?;
+        let table: Table = content.parse().ok()?;
+        Some(Self {
+            bindings_dir: PathBuf::from(table.get("bindings_dir")?.as_str()?),
+            inputs: table.get("inputs")?.as_str()?.to_string(),
+            archs: table
+                .get("archs")?
+                .as_table()?
+                .iter()
+                .map(|(arch, hash)| Some((arch.clone(), hash.as_str()?.to_string())))
+                .collect::<Option<_>>()?,
+        })
+    }
+
+    pub fn save(&self, output_dir: &Path, platform: &str) -> Result<(), BindingsError> {
+        let path = fingerprint_path(output_dir, platform);
+        fs::create_dir_all(output_dir.join(FINGERPRINT_DIR))
+            .map_err(BindingsError::io("Failed to create fingerprint directory"))?;
+
+        let mut table = Table::new();
+        table.insert(
+            "bindings_dir".to_string(),
+            Value::String(self.bindings_dir.to_string_lossy().into_owned()),
+        );
+        table.insert("inputs".to_string(), Value::String(self.inputs.clone()));
+        table.insert(
+            "archs".to_string(),
+            Value::Table(
+                self.archs
+                    .iter()
+                    .map(|(arch, hash)| (arch.clone(), Value::String(hash.clone())))
+                    .collect(),
+            ),
+        );
+        fs::write(&path, table.to_string()).map_err(BindingsError::io(format!(
+            "Failed to write {}",
+            path.display()
+        )))
+    }
+
+    /// Architectures whose slice from the `previous` build is still up to date.
+    pub fn fresh_archs(&self, previous: &Fingerprint) -> Vec<String> {
+        self.archs
+            .iter()
+            .filter(|(arch, hash)| previous.archs.get(*arch) == Some(hash))
+            .map(|(arch, _)| arch.clone())
+            .collect()
+    }
+
+    /// Whether the `previous` build produced exactly the bindings of this one.
+    pub fn is_up_to_date(&self, previous: &Fingerprint) -> bool {
+        self.inputs == previous.inputs && self.archs == previous.archs
+    }
+}
+
+fn fingerprint_path(output_dir: &Path, platform: &str) -> PathBuf {
+    output_dir
+        .join(FINGERPRINT_DIR)
+        .join(format!("{platform}.fingerprint.toml"))
+}
+
+/// Hashes the relative path and content of every file of a package, in a stable order.
+/// Hidden entries, build directories, `skipped_dirs` and generated bindings, which hold a
+/// manifest, are skipped.
+fn hash_sources(
+    hasher: &mut Sha256,
+    project_dir: &Path,
+    skipped_dirs: &[PathBuf],
+) -> Result<(), BindingsError> {
+    // Canonical, like `skipped_dirs`, so that the entries compare equal to them
+    let project_dir = fs::canonicalize(project_dir).map_err(BindingsError::io(format!(
+        "Failed to read {}",
+        project_dir.display()
+    )))?;
+    let mut dirs = vec![project_dir.clone()];
+    while let Some(dir) = dirs.pop() {
+        let mut entries: Vec<PathBuf> = fs::read_dir(&dir)
+            .map_err(BindingsError::io(format!(
+                "Failed to read {}",
+                dir.display()
+            )))?
+            .map(|entry| entry.map(|entry| entry.path()))
+            .collect::<Result<_, _>>()
+            .map_err(BindingsError::io(format!(
+                "Failed to read {}",
+                dir.display()
+            )))?;
+        entries.sort();
+
+        for path in entries {
+            let name = path.file_name().unwrap_or_default().to_string_lossy();
+            if name.starts_with('.') || skipped_dirs.contains(&path) {
+                continue;
+            }
+            if path.is_dir() {
+                if !IGNORED_DIRS.contains(&name.as_ref()) && !path.join(MANIFEST_FILE).is_file() {
+                    dirs.push(path);
+                }
+                continue;
+            }
+
+            let relative = path.strip_prefix(&project_dir).unwrap_or(&path);
+            hasher.update(relative.to_string_lossy().as_bytes());
+            hasher.update([0]);
+            hasher.update(fs::read(&path).map_err(BindingsError::io(format!(
+                "Failed to read {}",
+                path.display()
+            )))?);
+        }
+    }
+    Ok(())
+}
+
+/// `Cargo.lock` of the project, or of the workspace it belongs to.
+fn find_lock_file(project_dir: &Path) -> Option<PathBuf> {
+    project_dir
+        .ancestors()
+        .map(|dir| dir.join("Cargo.lock"))
+        .find(|path| path.is_file())
+}
+
+/// Cargo configuration files read by a build in `project_dir`, from the outermost one.
+fn cargo_config_files(project_dir: &Path) -> Vec<PathBuf> {
+    let mut files: Vec<PathBuf> = project_dir
+        .ancestors()
+        .flat_map(|dir| ["config.toml", "config"].map(|name| dir.join(".cargo").join(name)))
+        .filter(|path| path.is_file())
+        .collect();
+    files.reverse();
+    files
+}
+
+/// Stable hash of the platform parameters.
+pub fn hash_params<P: Hash>(params: &P) -> String {
+    let mut hasher = Sha256::new();
+    params.hash(&mut DigestHasher(&mut hasher));
+    hex(&hasher.finalize())
+}
+
+pub(crate) fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{byte:02x}")).collect()
+}
+
+/// Feeds `Hash` implementations into a SHA-256 digest, which, unlike `DefaultHasher`,
+/// is stable across Rust versions.
+struct DigestHasher<'a>(&'a mut Sha256);
+
+impl Hasher for DigestHasher<'_> {
+    fn finish(&self) -> u64 {
+        0
+    }
+
+    fn write(&mut self, bytes: &[u8]) {
+        self.0.update(bytes);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bindings::config::MoproConfig;
+    use crate::bindings::constants::FLUTTER_BINDINGS_DIR;
+    use crate::bindings::project::{test_project, TestProject};
+
+    fn compute(project: &Project, archs: &[&str]) -> Fingerprint {
+        compute_with(project, archs, &CargoFlags::default())
+    }
+
+    fn compute_with(project: &Project, archs: &[&str], cargo: &CargoFlags) -> Fingerprint {
+        let params_hash = hash_params(&"params");
+        let bindings_dirs: Vec<PathBuf> = MoproConfig::default()
+            .bindings_dirs()
+            .iter()
+            .map(|dir| project.manifest_dir.join(dir))
+            .collect();
+        Fingerprint::compute(
+            project,
+            &Mode::Debug,
+            cargo,
+            archs,
+            &params_hash,
+            &bindings_dirs,
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn test_fingerprint_tracks_sources_and_archs() {
+        let project = test_project("my-circuits", &["cdylib"]);
+        let project_dir = project.manifest_dir.clone();
+        fs::write(project_dir.join("src/lib.rs"), "pub fn a() {}").unwrap();
+        fs::create_dir(project_dir.join("target")).unwrap();
+
+        let mut previous = compute(&project, &["aarch64-apple-ios"]);
+        previous.bindings_dir = project_dir.join("MoproiOSBindings");
+        previous.save(&project_dir, "ios").unwrap();
+        assert_eq!(
+            Fingerprint::load(&project_dir, "ios"),
+            Some(previous.clone())
+        );
+
+        // Build outputs, bindings and the fingerprint itself are not inputs
+        fs::write(project_dir.join("target/lib.a"), "artifact").unwrap();
+        fs::create_dir(project_dir.join("MoproiOSBindings")).unwrap();
+        fs::write(project_dir.join("MoproiOSBindings/mopro.swift"), "").unwrap();
+        fs::write(
+            project_dir.join("MoproiOSBindings").join(MANIFEST_FILE),
+            "{}",
+        )
+        .unwrap();
+        // Neither are the bindings a failed build left without a manifest
+        let flutter_rust_dir = project_dir.join(FLUTTER_BINDINGS_DIR).join("rust/src");
+        fs::create_dir_all(&flutter_rust_dir).unwrap();
+        fs::write(flutter_rust_dir.join("lib.rs"), "pub fn c() {}").unwrap();
+        assert!(compute(&project, &["aarch64-apple-ios"]).is_up_to_date(&previous));
+
+        // A new arch only makes its own slice stale
+        let current = compute(&project, &["aarch64-apple-ios", "x86_64-apple-ios"]);
+        assert!(!current.is_up_to_date(&previous));
+        assert_eq!(current.fresh_archs(&previous), vec!["aarch64-apple-ios"]);
+
+        // A source change makes every slice stale
+        fs::write(project_dir.join("src/lib.rs"), "pub fn b() {}").unwrap();
+        let current = compute(&project, &["aarch64-apple-ios"]);
+        assert!(current.fresh_archs(&previous).is_empty());
+    }
+
+    #[test]
+    fn test_fingerprint_tracks_path_dependencies_and_cargo_config() {
+        let project = TestProject::with_crate_dir(
+            &[
+                (
+                    "app/Cargo.toml",
+                    "[package]\nname = \"app\"\nversion = \"0.1.0\"\nedition = \"2021\"\n\n\
+                     [dependencies]\nmopro-ffi = { path = \"../mopro-ffi\" }\n",
+                ),
+                ("app/src/lib.rs", ""),
+                (
+                    "mopro-ffi/Cargo.toml",
+                    "[package]\nname = \"mopro-ffi\"\nversion = \"0.1.0\"\nedition = \"2021\"\n",
+                ),
+                ("mopro-ffi/src/lib.rs", "pub fn a() {}"),
+            ],
+            "app",
+        );
+        let dir = project.dir();
+        let previous = compute(&project, &["aarch64-apple-ios"]);
+
+        fs::write(dir.join("mopro-ffi/src/lib.rs"), "pub fn b() {}").unwrap();
+        let current = compute(&project, &["aarch64-apple-ios"]);
+        assert!(!current.is_up_to_date(&previous));
+
+        // The configuration of a parent directory applies to the build too
+        fs::create_dir(dir.join(".cargo")).unwrap();
+        fs::write(dir.join(".cargo/config.toml"), "[build]\njobs = 1\n").unwrap();
+        assert!(!compute(&project, &["aarch64-apple-ios"]).is_up_to_date(&current));
+    }
+
+    #[test]
+    fn test_fingerprint_tracks_cargo_flags() {
+        let project = test_project("my-circuits", &["cdylib"]);
+        let archs = ["aarch64-apple-ios", "x86_64-apple-ios"];
+        let previous = compute(&project, &archs);
+
+        // Features change every slice
+        let features = CargoFlags {
+            features: vec!["noir".to_string()],
+            ..CargoFlags::default()
+        };
+        let current = compute_with(&project, &archs, &features);
+        assert!(current.fresh_archs(&previous).is_empty());
+
+        // RUSTFLAGS of one target only change its slice
+        let mut rustflags = CargoFlags::default();
+        rustflags.target_rustflags.insert(
+            "aarch64-apple-ios".to_string(),
+            vec!["-C target-cpu=apple-a14".to_string()],
+        );
+        let current = compute_with(&project, &archs, &rustflags);
+        assert_eq!(current.fresh_archs(&previous), vec!["x86_64-apple-ios"]);
+    }
+}
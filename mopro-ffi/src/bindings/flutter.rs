@@ -1,179 +1,258 @@
-use anyhow::Context;
 use std::fs;
-use std::path::{Path, PathBuf};
+use std::path::Path;
 use std::process::Command;
 use toml::Value;
 
-use crate::bindings::constants::{FlutterArch, FlutterPlatform, Mode, FLUTTER_BINDINGS_DIR};
-
-use super::raw_project_name_from_toml;
+use crate::bindings::constants::{
+    Arch, BuildOptions, FlutterArch, FlutterPlatform, Platform, FLUTTER_BINDINGS_DIR,
+    FLUTTER_RUST_BRIDGE_CODEGEN_VERSION,
+};
+
+use super::config::{FromPlatformConfig, PlatformConfig};
+use super::doctor::{check_command, check_rustup_targets, command_output, ToolCheck};
+use super::error::BindingsError;
+use super::plan::{BuildPlan, BuildStep};
+use super::project::Project;
+use super::runner::CommandLine;
 use super::PlatformBuilder;
 
+// Patches of the generated Flutter plugin
+const WORKSPACE_TOML_PATCH: &str = "workspace-toml";
+const ABSOLUTE_CRATE_PATH_PATCH: &str = "absolute-crate-path";
+const CARGOKIT_BUILD_SCRIPT_PATCH: &str = "cargokit-build-script";
+const PODSPEC_CPP_FLAG_PATCH: &str = "podspec-cpp-flag";
+const ANDROID_ARCHS_PATCH: &str = "android-archs";
+const LIBCXX_SHARED_PATCH: &str = "libcxx-shared";
+
 // Maintained for backwards compatibility
 #[inline]
 pub fn build() {
     if cfg!(feature = "uniffi") {
         panic!("\"uniffi\" and \"flutter\" features cannot be enabled at the same time, please disable one of them in your Cargo.toml");
     }
-    super::build_from_env::<FlutterPlatform>()
+    super::build_from_env::<FlutterPlatform>().unwrap_or_else(|e| {
+        panic!(
+            "Failed to build {} bindings: {e}",
+            FlutterPlatform::identifier()
+        )
+    })
 }
 
-#[derive(Default)]
+#[derive(Hash)]
 pub struct FlutterBindingsParams {
     pub using_noir: bool,
+    /// Name of the generated Flutter plugin, which is also its directory name
+    pub bindings_dir: String,
+}
+
+impl Default for FlutterBindingsParams {
+    fn default() -> Self {
+        Self {
+            using_noir: false,
+            bindings_dir: FLUTTER_BINDINGS_DIR.to_string(),
+        }
+    }
+}
+
+impl FromPlatformConfig for FlutterBindingsParams {
+    fn from_platform_config(config: &PlatformConfig) -> anyhow::Result<Self> {
+        config.check_params(&["using_noir"])?;
+        let default = Self::default();
+        Ok(Self {
+            using_noir: config
+                .bool_param("using_noir")?
+                .unwrap_or(default.using_noir),
+            bindings_dir: config.bindings_dir.clone().unwrap_or(default.bindings_dir),
+        })
+    }
 }
 
 impl PlatformBuilder for FlutterPlatform {
     type Arch = FlutterArch;
     type Params = FlutterBindingsParams;
 
-    fn build(
-        _mode: Mode,
-        project_dir: &Path,
+    fn plan(
+        options: &BuildOptions,
+        project: &Project,
+        output_dir: &Path,
         _target_archs: Vec<Self::Arch>,
-        _params: Self::Params,
-    ) -> anyhow::Result<PathBuf> {
+        _fresh_archs: &[Self::Arch],
+        params: &Self::Params,
+    ) -> Result<BuildPlan, BindingsError> {
+        let bindings_dir = output_dir.join(&params.bindings_dir);
+        let cargo_toml_path = bindings_dir.join("rust/Cargo.toml");
+        let mut plan = BuildPlan::new(Self::name(), options, project, output_dir, &bindings_dir);
+        let project_dir = &project.manifest_dir;
+
         // Init flutter bindings template
-        init_flutter_bindings(project_dir)?;
+        if !options.offline {
+            plan.steps.push(install_flutter_rust_bridge_codegen());
+        }
+        if !bindings_dir.exists() {
+            plan.steps.push(BuildStep::command(
+                Command::new("flutter_rust_bridge_codegen")
+                    .args(["create", &params.bindings_dir, "--template", "plugin"])
+                    .current_dir(output_dir),
+            ));
+        }
 
         // Init workspace for bindings template
-        let cargo_toml_path = project_dir
-            .join(FLUTTER_BINDINGS_DIR)
-            .join("rust/Cargo.toml");
-        ensure_workspace_toml(&cargo_toml_path.to_string_lossy().to_string());
+        plan.steps
+            .push(BuildStep::patch(WORKSPACE_TOML_PATCH, &cargo_toml_path));
 
         // Import user defined crates
-        let third_party_crate_name = raw_project_name_from_toml(project_dir)?;
-        let cargo_add_status = Command::new("cargo")
-            .args([
-                "add",
-                &third_party_crate_name,
-                "--path",
-                &project_dir.to_string_lossy().to_string(),
-            ])
-            .current_dir(project_dir.join(FLUTTER_BINDINGS_DIR).join("rust"))
-            .status()
-            .expect("failed to run cargo add");
-        if !cargo_add_status.success() {
-            return Err(anyhow::anyhow!("Failed to add third party crate"));
+        let third_party_crate_name = project.package.as_str();
+        // cargokit compiles the crate when the app is built, so only its features are chosen
+        // here. `cargo add` has no `--all-features`.
+        let mut add_cmd = Command::new("cargo");
+        add_cmd
+            .args(["add", third_party_crate_name, "--path"])
+            .arg(project_dir)
+            .current_dir(bindings_dir.join("rust"));
+        if options.cargo.no_default_features {
+            add_cmd.arg("--no-default-features");
+        }
+        if !options.cargo.features.is_empty() {
+            add_cmd
+                .arg("--features")
+                .arg(options.cargo.features.join(","));
         }
+        plan.steps.push(BuildStep::command(&add_cmd));
 
         // Replace relative path with absolute path
-        replace_relative_path_with_absolute(
+        plan.steps.push(BuildStep::patch(
+            ABSOLUTE_CRATE_PATH_PATCH,
             &cargo_toml_path,
-            &third_party_crate_name,
-            &project_dir,
-        )?;
+        ));
 
         // Patch cargokit build script
         // See: https://github.com/fzyzcjy/flutter_rust_bridge/issues/2839
         // TODO: remove this once the issue is fixed
-        patch_cargokit_build_script(project_dir)?;
+        plan.steps
+            .push(BuildStep::patch(CARGOKIT_BUILD_SCRIPT_PATCH, &bindings_dir));
 
         // add C++ flag
-        add_cpp_flag_to_ios_podspec(project_dir)?;
+        plan.steps
+            .push(BuildStep::patch(PODSPEC_CPP_FLAG_PATCH, &bindings_dir));
 
         // Disable android architecture support
-        disable_android_architecture_support(project_dir)?;
+        plan.steps
+            .push(BuildStep::patch(ANDROID_ARCHS_PATCH, &bindings_dir));
 
         // Copy libc++_shared.so to jniLibs
-        copy_libcxx_shared_so_to_jni_libs(project_dir)?;
+        plan.steps
+            .push(BuildStep::patch(LIBCXX_SHARED_PATCH, &bindings_dir));
 
         // Generate flutter bindings
-        let rust_root = project_dir.join(FLUTTER_BINDINGS_DIR).join("rust");
-        let dart_output = project_dir.join(FLUTTER_BINDINGS_DIR).join("lib/src/rust");
-        let generate_status = Command::new("flutter_rust_bridge_codegen")
-            .args(["generate"])
-            .args([
-                "--rust-root",
-                &rust_root.to_string_lossy(),
-                "--rust-input",
-                &third_party_crate_name,
-                "--dart-output",
-                &dart_output.to_string_lossy(),
-            ])
-            .current_dir(project_dir)
-            .status()
-            .expect("failed to run flutter_rust_bridge_codegen");
-        if !generate_status.success() {
-            return Err(anyhow::anyhow!("Failed to generate simple.rs"));
-        }
-
-        Ok(PathBuf::from(FLUTTER_BINDINGS_DIR))
+        let rust_root = bindings_dir.join("rust");
+        let dart_output = bindings_dir.join("lib/src/rust");
+        plan.steps.push(BuildStep::command(
+            Command::new("flutter_rust_bridge_codegen")
+                .args(["generate"])
+                .args([
+                    "--rust-root",
+                    &rust_root.to_string_lossy(),
+                    "--rust-input",
+                    third_party_crate_name,
+                    "--dart-output",
+                    &dart_output.to_string_lossy(),
+                ])
+                .current_dir(project_dir),
+        ));
+        plan.steps.push(BuildStep::WriteManifest {
+            dir: bindings_dir,
+            uniffi_version: None,
+            arch_paths: Vec::new(),
+        });
+
+        Ok(plan)
     }
-}
 
-fn install_flutter_rust_bridge_codegen() -> anyhow::Result<()> {
-    let output = Command::new("flutter_rust_bridge_codegen").output();
-    match output {
-        Ok(_) => {
-            // Command exists, no need to install
-            println!("flutter_rust_bridge_codegen already installed.");
-            return Ok(());
-        }
-        Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
-            // Command not found, proceed with installation
-            println!("flutter_rust_bridge_codegen not found, installing...");
-            let status = Command::new("cargo")
-                .args(["install", "flutter_rust_bridge_codegen@=2.11.1"])
-                .status()
-                .expect("failed to run flutter_rust_bridge_codegen");
-            if !status.success() {
-                return Err(anyhow::anyhow!(
-                    "Failed to install flutter_rust_bridge_codegen"
-                ));
-            }
-        }
-        Err(e) => {
-            // Other error, propagate it
-            return Err(anyhow::anyhow!(
-                "Failed to check for flutter_rust_bridge_codegen: {}",
-                e
-            ));
+    fn apply_patch(
+        plan: &BuildPlan,
+        name: &str,
+        path: &Path,
+        params: &Self::Params,
+    ) -> Result<(), BindingsError> {
+        match name {
+            WORKSPACE_TOML_PATCH => ensure_workspace_toml(path),
+            ABSOLUTE_CRATE_PATH_PATCH => replace_relative_path_with_absolute(
+                path,
+                &plan.project.package,
+                &plan.project.manifest_dir,
+            ),
+            CARGOKIT_BUILD_SCRIPT_PATCH => patch_cargokit_build_script(path),
+            PODSPEC_CPP_FLAG_PATCH => add_cpp_flag_to_ios_podspec(path, &params.bindings_dir),
+            ANDROID_ARCHS_PATCH => disable_android_architecture_support(path),
+            LIBCXX_SHARED_PATCH => copy_libcxx_shared_so_to_jni_libs(path),
+            _ => Err(BindingsError::unknown_patch(Self::identifier(), name)),
         }
     }
 
-    Ok(())
+    fn toolchain_checks(target_archs: &[Self::Arch]) -> Vec<ToolCheck> {
+        let targets: Vec<&str> = target_archs.iter().map(|arch| arch.as_str()).collect();
+        let mut checks = check_rustup_targets(None, &targets);
+        checks.push(ToolCheck::new(
+            "flutter_rust_bridge_codegen",
+            command_output("flutter_rust_bridge_codegen", &["--version"]).and_then(|version| {
+                if version.ends_with(FLUTTER_RUST_BRIDGE_CODEGEN_VERSION) {
+                    Ok(version)
+                } else {
+                    Err(format!(
+                        "found {version}, expected {FLUTTER_RUST_BRIDGE_CODEGEN_VERSION}"
+                    ))
+                }
+            }),
+            format!(
+                "cargo install flutter_rust_bridge_codegen@={FLUTTER_RUST_BRIDGE_CODEGEN_VERSION}"
+            ),
+        ));
+        checks.push(check_command(
+            "flutter",
+            "flutter",
+            &["--version"],
+            "Install the Flutter SDK from https://docs.flutter.dev/get-started/install",
+        ));
+        checks
+    }
 }
 
-fn init_flutter_bindings(project_dir: &Path) -> anyhow::Result<()> {
-    let flutter_bindings_dir = project_dir.join(FLUTTER_BINDINGS_DIR);
-
-    install_flutter_rust_bridge_codegen()?;
-
-    if !flutter_bindings_dir.exists() {
-        let status = Command::new("flutter_rust_bridge_codegen")
-            .args(["create", FLUTTER_BINDINGS_DIR, "--template", "plugin"])
-            .status()
-            .expect("failed to run flutter_rust_bridge_codegen");
-
-        if !status.success() {
-            return Err(anyhow::anyhow!("flutter_rust_bridge_codegen failed"));
-        }
+fn install_flutter_rust_bridge_codegen() -> BuildStep {
+    BuildStep::InstallTool {
+        tool: "flutter_rust_bridge_codegen".to_string(),
+        check: CommandLine::from_command(
+            Command::new("flutter_rust_bridge_codegen").arg("--version"),
+        ),
+        install: CommandLine::from_command(Command::new("cargo").args([
+            "install",
+            &format!("flutter_rust_bridge_codegen@={FLUTTER_RUST_BRIDGE_CODEGEN_VERSION}"),
+        ])),
     }
-
-    Ok(())
 }
 
-fn ensure_workspace_toml(cargo_toml_path: &str) {
-    let content = fs::read_to_string(cargo_toml_path).expect("Failed to read Cargo.toml");
+fn ensure_workspace_toml(cargo_toml_path: &Path) -> Result<(), BindingsError> {
+    let content = fs::read_to_string(cargo_toml_path)
+        .map_err(|e| BindingsError::manifest(cargo_toml_path, e))?;
 
     if !content.contains("[workspace]") {
         let new_content = format!("{content}\n\n[workspace]\n");
-        fs::write(cargo_toml_path, new_content).expect("Failed to write updated Cargo.toml");
+        fs::write(cargo_toml_path, new_content)
+            .map_err(BindingsError::io("Failed to write updated Cargo.toml"))?;
     }
+
+    Ok(())
 }
 
 fn replace_relative_path_with_absolute(
     cargo_toml_path: &Path,
     crate_name: &str,
     abs_path: &Path,
-) -> anyhow::Result<()> {
-    let cargo_toml_content =
-        fs::read_to_string(cargo_toml_path).context("Failed to read Cargo.toml")?;
+) -> Result<(), BindingsError> {
+    let cargo_toml_content = fs::read_to_string(cargo_toml_path)
+        .map_err(|e| BindingsError::manifest(cargo_toml_path, e))?;
     let mut cargo_toml: Value = cargo_toml_content
         .parse::<Value>()
-        .context("Failed to parse Cargo.toml")?;
+        .map_err(|e| BindingsError::manifest(cargo_toml_path, e))?;
 
     // If the `name` under [lib] section is set, using the `name` as library name.
     // Otherwise, using the package name.
@@ -189,89 +268,89 @@ fn replace_relative_path_with_absolute(
         );
     }
 
-    let updated_cargo_toml_content =
-        toml::to_string_pretty(&cargo_toml).context("Failed to serialize updated Cargo.toml")?;
+    let updated_cargo_toml_content = toml::to_string_pretty(&cargo_toml)
+        .map_err(|e| BindingsError::manifest(cargo_toml_path, e))?;
 
     fs::write(cargo_toml_path, updated_cargo_toml_content)
-        .context("Failed to write updated Cargo.toml")?;
+        .map_err(BindingsError::io("Failed to write updated Cargo.toml"))?;
 
     Ok(())
 }
 
-fn patch_cargokit_build_script(project_dir: &Path) -> anyhow::Result<()> {
-    let cargo_kit_build_script_path = project_dir
-        .join(FLUTTER_BINDINGS_DIR)
+fn patch_cargokit_build_script(bindings_dir: &Path) -> Result<(), BindingsError> {
+    let cargo_kit_build_script_path = bindings_dir
         .join("cargokit")
         .join("gradle")
         .join("plugin.gradle");
     let cargo_kit_build_script_content = fs::read_to_string(cargo_kit_build_script_path.clone())
-        .context("Failed to read plugin.gradle")?;
+        .map_err(BindingsError::io("Failed to read plugin.gradle"))?;
     if !cargo_kit_build_script_content.contains("if (plugin.class.name == \"com.flutter.gradle.FlutterPlugin\" || plugin.class.name == \"FlutterPlugin\")") {
         let updated_content = cargo_kit_build_script_content.replace(
         "if (plugin.class.name == \"com.flutter.gradle.FlutterPlugin\")",
         "if (plugin.class.name == \"com.flutter.gradle.FlutterPlugin\" || plugin.class.name == \"FlutterPlugin\")"
         );
-    
+
         let updated_content = updated_content.replace(
             "        def platforms = com.flutter.gradle.FlutterPluginUtils.getTargetPlatforms(project).collect()",
         "        def List<String> platforms\n            try {\n                platforms = com.flutter.gradle.FlutterPluginUtils.getTargetPlatforms(project).collect()\n            } catch (Exception ignored) {\n                platforms = plugin.getTargetPlatforms().collect()\n            }"
         );
 
         fs::write(&cargo_kit_build_script_path, updated_content)
-        .context("Failed to write updated plugin.gradle")?;
+        .map_err(BindingsError::io("Failed to write updated plugin.gradle"))?;
     }
 
     Ok(())
 }
 
-fn add_cpp_flag_to_ios_podspec(project_dir: &Path) -> anyhow::Result<()> {
-    let ios_podspec_path = project_dir
-        .join(FLUTTER_BINDINGS_DIR)
+fn add_cpp_flag_to_ios_podspec(
+    bindings_dir: &Path,
+    bindings_name: &str,
+) -> Result<(), BindingsError> {
+    let ios_podspec_path = bindings_dir
         .join("ios")
-        .join(format!("{FLUTTER_BINDINGS_DIR}.podspec"));
-    let ios_podspec_content = fs::read_to_string(ios_podspec_path.clone()).context(format!(
-        "Failed to read {}",
-        ios_podspec_path.to_string_lossy()
-    ))?;
+        .join(format!("{bindings_name}.podspec"));
+    let ios_podspec_content =
+        fs::read_to_string(ios_podspec_path.clone()).map_err(BindingsError::io(format!(
+            "Failed to read {}",
+            ios_podspec_path.to_string_lossy()
+        )))?;
     if !ios_podspec_content.contains("-lc++") {
         let updated_content = ios_podspec_content.replace(
-        "'OTHER_LDFLAGS' => '-force_load ${BUILT_PRODUCTS_DIR}/libmopro_flutter_bindings.a'",
-        "'OTHER_LDFLAGS' => '-force_load ${BUILT_PRODUCTS_DIR}/libmopro_flutter_bindings.a -lc++'",
+            &format!("'OTHER_LDFLAGS' => '-force_load ${{BUILT_PRODUCTS_DIR}}/lib{bindings_name}.a'"),
+            &format!("'OTHER_LDFLAGS' => '-force_load ${{BUILT_PRODUCTS_DIR}}/lib{bindings_name}.a -lc++'"),
         );
-        fs::write(&ios_podspec_path, updated_content).context(format!(
-            "Failed to write updated {FLUTTER_BINDINGS_DIR}.podspec"
-        ))?;
+        fs::write(&ios_podspec_path, updated_content).map_err(BindingsError::io(format!(
+            "Failed to write updated {bindings_name}.podspec"
+        )))?;
     }
 
     Ok(())
 }
 
-fn disable_android_architecture_support(project_dir: &Path) -> anyhow::Result<()> {
-    let android_gradle_path = project_dir
-        .join(FLUTTER_BINDINGS_DIR)
+fn disable_android_architecture_support(bindings_dir: &Path) -> Result<(), BindingsError> {
+    let android_gradle_path = bindings_dir
         .join("cargokit")
         .join("gradle")
         .join("plugin.gradle");
-    let cargo_kit_build_script_content =
-        fs::read_to_string(android_gradle_path.clone()).context("Failed to read plugin.gradle")?;
+    let cargo_kit_build_script_content = fs::read_to_string(android_gradle_path.clone())
+        .map_err(BindingsError::io("Failed to read plugin.gradle"))?;
     let updated_content =
         cargo_kit_build_script_content.replace("        platforms.add(\"android-x86\")", "");
     let updated_content = updated_content.replace("        platforms.add(\"android-x64\")", "");
-    fs::write(&android_gradle_path, updated_content).context(format!(
+    fs::write(&android_gradle_path, updated_content).map_err(BindingsError::io(format!(
         "Failed to write updated {}",
         android_gradle_path.to_string_lossy()
-    ))?;
+    )))?;
     Ok(())
 }
 
-fn copy_libcxx_shared_so_to_jni_libs(project_dir: &Path) -> anyhow::Result<()> {
-    let android_gradle_path = project_dir
-        .join(FLUTTER_BINDINGS_DIR)
+fn copy_libcxx_shared_so_to_jni_libs(bindings_dir: &Path) -> Result<(), BindingsError> {
+    let android_gradle_path = bindings_dir
         .join("cargokit")
         .join("gradle")
         .join("plugin.gradle");
-    let cargo_kit_build_script_content =
-        fs::read_to_string(android_gradle_path.clone()).context("Failed to read plugin.gradle")?;
+    let cargo_kit_build_script_content = fs::read_to_string(android_gradle_path.clone())
+        .map_err(BindingsError::io("Failed to read plugin.gradle"))?;
 
     if !cargo_kit_build_script_content.contains("// After cargo build in CargoKitBuildTask.build()")
     {
@@ -304,10 +383,10 @@ fn copy_libcxx_shared_so_to_jni_libs(project_dir: &Path) -> anyhow::Result<()> {
                     }
                 }"
         );
-        fs::write(&android_gradle_path, updated_content).context(format!(
+        fs::write(&android_gradle_path, updated_content).map_err(BindingsError::io(format!(
             "Failed to write updated {}",
             android_gradle_path.to_string_lossy()
-        ))?;
+        )))?;
     }
 
     Ok(())
@@ -0,0 +1,152 @@
+//! Guards of the directories a build owns while it runs: its scratch [`WorkDir`], removed when
+//! the build ends however it ends, and a [`DestinationLock`] so that builds writing to the same
+//! bindings directory, e.g. started by two CI jobs in one checkout, take turns.
+
+use std::fs::{self, File, TryLockError};
+use std::path::{Path, PathBuf};
+
+use super::error::BindingsError;
+use super::observer::{BuildEvent, BuildObserver};
+
+/// Scratch directory of a build, removed with everything in it when the guard is dropped.
+#[derive(Debug)]
+pub struct WorkDir {
+    path: PathBuf,
+}
+
+impl WorkDir {
+    /// Creates `path`, which must be unique to the build, e.g. a
+    /// [`work_dir_path`](super::work_dir_path).
+    pub fn create(path: &Path) -> Result<Self, BindingsError> {
+        fs::create_dir_all(path).map_err(BindingsError::io(format!(
+            "Failed to create {}",
+            path.display()
+        )))?;
+        Ok(Self {
+            path: path.to_path_buf(),
+        })
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+}
+
+impl Drop for WorkDir {
+    fn drop(&mut self) {
+        // Only the guard's own directory, the parent is shared with the work dirs of concurrent
+        // builds that may be about to create theirs in it
+        let _ = fs::remove_dir_all(&self.path);
+    }
+}
+
+/// Exclusive lock of a bindings directory, released when the guard is dropped or the process
+/// exits. The lock file, `.<name>.lock` next to the directory, is left in place.
+#[derive(Debug)]
+pub struct DestinationLock {
+    _file: File,
+}
+
+impl DestinationLock {
+    /// Locks `bindings_dir`, waiting for another build holding the lock to finish.
+    pub fn acquire(
+        bindings_dir: &Path,
+        observer: &dyn BuildObserver,
+    ) -> Result<Self, BindingsError> {
+        let path = lock_path(bindings_dir);
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent).map_err(BindingsError::io(format!(
+                "Failed to create {}",
+                parent.display()
+            )))?;
+        }
+        let file = File::create(&path).map_err(BindingsError::io(format!(
+            "Failed to create {}",
+            path.display()
+        )))?;
+        let locked = match file.try_lock() {
+            Ok(()) => Ok(()),
+            Err(TryLockError::WouldBlock) => {
+                let message = format!(
+                    "Waiting for another build writing to {}",
+                    bindings_dir.display()
+                );
+                observer.on_event(&BuildEvent::Message { message: &message });
+                file.lock()
+            }
+            Err(TryLockError::Error(e)) => Err(e),
+        };
+        locked.map_err(BindingsError::io(format!(
+            "Failed to lock {}",
+            path.display()
+        )))?;
+        Ok(Self { _file: file })
+    }
+}
+
+fn lock_path(bindings_dir: &Path) -> PathBuf {
+    let name = bindings_dir
+        .file_name()
+        .unwrap_or_default()
+        .to_string_lossy();
+    bindings_dir.with_file_name(format!(".{name}.lock"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bindings::observer::JsonLinesObserver;
+    use std::sync::mpsc;
+    use std::thread;
+    use std::time::Duration;
+
+    #[test]
+    fn test_work_dir_is_removed_on_drop() {
+        let dir = crate::bindings::mktemp().unwrap();
+        let tmp = dir.join("build/tmp");
+        let first = WorkDir::create(&tmp.join("first")).unwrap();
+        let second = WorkDir::create(&tmp.join("second")).unwrap();
+        fs::write(first.path().join("lib.a"), "").unwrap();
+
+        // Another build's work dir is left alone
+        drop(first);
+        assert!(!tmp.join("first").exists());
+        assert!(second.path().is_dir());
+        drop(second);
+        assert!(!tmp.join("second").exists());
+        // The shared parent stays, a concurrent build may be creating its work dir in it
+        assert!(tmp.is_dir());
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_destination_lock_is_exclusive() {
+        let dir = crate::bindings::mktemp().unwrap();
+        let bindings_dir = dir.join("Bindings");
+        let lock =
+            DestinationLock::acquire(&bindings_dir, &JsonLinesObserver::new(Vec::new())).unwrap();
+        assert!(dir.join(".Bindings.lock").exists());
+
+        let (sender, receiver) = mpsc::channel();
+        let waiting = thread::spawn({
+            let bindings_dir = bindings_dir.clone();
+            move || {
+                let observer = JsonLinesObserver::new(Vec::new());
+                let _lock = DestinationLock::acquire(&bindings_dir, &observer).unwrap();
+                sender.send(()).unwrap();
+                String::from_utf8(observer.into_inner()).unwrap()
+            }
+        });
+        assert!(receiver.recv_timeout(Duration::from_millis(200)).is_err());
+
+        drop(lock);
+        receiver.recv_timeout(Duration::from_secs(5)).unwrap();
+        assert!(waiting
+            .join()
+            .unwrap()
+            .contains("Waiting for another build writing to"));
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -1,144 +1,189 @@
-use anyhow::Context;
-use camino::Utf8Path;
 use std::fs;
-use std::io::{Error, ErrorKind};
-use std::path::{Path, PathBuf};
+use std::path::Path;
 use std::process::Command;
-use uniffi::generate_bindings_library_mode;
-use uniffi::CargoMetadataConfigSupplier;
-use uniffi::SwiftBindingGenerator;
 
+use super::config::{FromPlatformConfig, PlatformConfig};
 use super::constants::{
-    Arch, IosArch, IosPlatform, Mode, ARCH_ARM_64, ARCH_X86_64, IOS_BINDINGS_DIR, IOS_SWIFT_FILE,
-    IOS_XCFRAMEWORKS_DIR,
+    Arch, BuildOptions, IosArch, IosPlatform, Platform, ARCH_ARM_64, ARCH_X86_64, IOS_BINDINGS_DIR,
+    IOS_SWIFT_FILE, IOS_XCFRAMEWORKS_DIR,
 };
-use super::mktemp_local;
-use super::{cleanup_tmp_local, project_name_from_toml};
-use super::{install_arch, PlatformBuilder};
+use super::doctor::{check_command, check_rustup_targets, ToolCheck};
+use super::error::BindingsError;
+use super::manifest::{ArchPath, UNIFFI_VERSION};
+use super::plan::{ArchGroup, BindingsLanguage, BuildPlan, BuildStep};
+use super::project::Project;
+use super::{arch_target_dir, install_arch, work_dir_path, PlatformBuilder};
+
+/// Patch moving the headers and module map of every XCFramework slice into a subdirectory
+pub(crate) const REGROUP_HEADERS_PATCH: &str = "regroup-headers";
 
 // Maintained for backwards compatibility
 #[inline]
 pub fn build() {
-    super::build_from_env::<IosPlatform>()
+    super::build_from_env::<IosPlatform>().unwrap_or_else(|e| {
+        panic!(
+            "Failed to build {} bindings: {e}",
+            IosPlatform::identifier()
+        )
+    })
 }
 
-#[derive(Default)]
+#[derive(Hash)]
 pub struct IosBindingsParams {
     pub using_noir: bool,
+    /// Name of the generated bindings directory
+    pub bindings_dir: String,
+}
+
+impl Default for IosBindingsParams {
+    fn default() -> Self {
+        Self {
+            using_noir: false,
+            bindings_dir: IOS_BINDINGS_DIR.to_string(),
+        }
+    }
+}
+
+impl FromPlatformConfig for IosBindingsParams {
+    fn from_platform_config(config: &PlatformConfig) -> anyhow::Result<Self> {
+        config.check_params(&["using_noir"])?;
+        let default = Self::default();
+        Ok(Self {
+            using_noir: config
+                .bool_param("using_noir")?
+                .unwrap_or(default.using_noir),
+            bindings_dir: config.bindings_dir.clone().unwrap_or(default.bindings_dir),
+        })
+    }
 }
 
 impl PlatformBuilder for IosPlatform {
     type Arch = IosArch;
     type Params = IosBindingsParams;
 
-    fn build(
-        mode: Mode,
-        project_dir: &Path,
+    fn plan(
+        options: &BuildOptions,
+        project: &Project,
+        output_dir: &Path,
         target_archs: Vec<Self::Arch>,
-        params: Self::Params,
-    ) -> anyhow::Result<PathBuf> {
+        fresh_archs: &[Self::Arch],
+        params: &Self::Params,
+    ) -> Result<BuildPlan, BindingsError> {
         if !cfg!(feature = "uniffi") {
-            panic!("uniffi feature is not enabled, please enable it in your Cargo.toml");
+            return Err(BindingsError::MissingFeature { feature: "uniffi" });
         }
 
-        let uniffi_style_identifier = project_name_from_toml(project_dir)
-            .expect("Failed to get project name from Cargo.toml");
+        // The XCFramework links the static library, uniffi reads the bindings from the dynamic one
+        project.require_crate_types(&["staticlib", "cdylib"])?;
+        let project_dir = &project.manifest_dir;
+        let uniffi_style_identifier = &project.lib_name;
 
         // Names for the files that will be outputted (can be changed)
-        let bindings_dir_name = IOS_BINDINGS_DIR;
+        let bindings_dir_name = &params.bindings_dir;
         let framework_name = IOS_XCFRAMEWORKS_DIR;
         let out_swift_file_name = IOS_SWIFT_FILE;
 
         // Names for the generated files by uniffi
         let gen_swift_file_name = format!("{uniffi_style_identifier}.swift");
         let lib_name = format!("lib{uniffi_style_identifier}.a");
-        let header_name = format!("{uniffi_style_identifier}FFI.h");
-        let modulemap_name = format!("{uniffi_style_identifier}FFI.modulemap");
 
         // Paths for the generated files
-        let build_dir_path = project_dir.join("build");
-        let work_dir = mktemp_local(&build_dir_path);
+        let work_dir = work_dir_path(&project_dir.join("build"));
         let swift_bindings_dir = work_dir.join(Path::new("SwiftBindings"));
         let bindings_out = work_dir.join(bindings_dir_name);
-        fs::create_dir(&bindings_out).expect("Failed to create bindings out directory");
-        let bindings_dest = Path::new(&project_dir).join(bindings_dir_name);
+        let bindings_dest = output_dir.join(bindings_dir_name);
         let framework_out = bindings_out.join(framework_name);
 
-        // Take a list of architectures, build them, and combine them into
-        // a single universal binary/archive
-        let build_combined_archs = |archs: &[IosArch]| -> PathBuf {
-            let out_lib_paths: Vec<PathBuf> = archs
-                .iter()
-                .map(|arch| {
-                    Path::new(&build_dir_path).join(format!(
-                        "{}/{}/{}",
-                        arch.as_str(),
-                        mode.as_str(),
-                        lib_name
-                    ))
-                })
-                .collect();
-            for arch in archs {
-                install_arch(arch.as_str().to_string());
-                let mut build_cmd = Command::new("cargo");
-                build_cmd.arg("build");
-                if mode == Mode::Release {
-                    build_cmd.arg("--release");
-                }
-                // The dependencies of Noir libraries need iOS 15 and above.
-                if params.using_noir {
-                    build_cmd.env("IPHONEOS_DEPLOYMENT_TARGET", "15.0");
-                }
-                build_cmd
-                    .arg("--lib")
-                    .env("CARGO_BUILD_TARGET_DIR", &build_dir_path)
-                    .env("CARGO_BUILD_TARGET", arch.as_str())
-                    .spawn()
-                    .expect("Failed to spawn cargo build")
-                    .wait()
-                    .expect("cargo build errored");
-            }
-            // now lipo the libraries together
-            let mut lipo_cmd = Command::new("lipo");
-            let lib_out = mktemp_local(&build_dir_path).join(lib_name.clone());
-            lipo_cmd
-                .arg("-create")
-                .arg("-output")
-                .arg(lib_out.to_str().unwrap());
-            for p in out_lib_paths {
-                lipo_cmd.arg(p.to_str().unwrap());
-            }
-            lipo_cmd
-                .spawn()
-                .expect("Failed to spawn lipo")
-                .wait()
-                .expect("lipo command failed");
-
-            lib_out
+        let mut plan = BuildPlan::new(Self::name(), options, project, output_dir, &bindings_dest);
+        plan.work_dir = Some(work_dir.clone());
+        plan.steps.push(BuildStep::CreateDir {
+            path: bindings_out.clone(),
+        });
+
+        let mode = &options.mode;
+        let parallel = options.jobs > 1 && target_archs.len() > 1;
+        let out_lib_path = |arch: &IosArch| {
+            arch_target_dir(&project.target_dir, arch.as_str(), parallel).join(format!(
+                "{}/{}/{}",
+                arch.as_str(),
+                mode.as_str(),
+                lib_name
+            ))
         };
 
-        let out_lib_paths: Vec<PathBuf> = group_target_archs(&target_archs)
+        // Reuse the libraries of the previous build if their slices are up to date
+        let stale_archs: Vec<IosArch> = target_archs
             .iter()
-            .map(|v| build_combined_archs(v))
+            .copied()
+            .filter(|arch| !(fresh_archs.contains(arch) && out_lib_path(arch).exists()))
             .collect();
+        if !options.offline {
+            for arch in &stale_archs {
+                plan.steps.push(install_arch(arch.as_str()));
+            }
+        }
+        for arch in &stale_archs {
+            let mut build_cmd = Command::new("cargo");
+            build_cmd
+                .arg("build")
+                .args(mode.cargo_args())
+                .args(options.cargo.feature_args());
+            if let Some(rustflags) = options.cargo.rustflags(arch.as_str()) {
+                build_cmd.env("RUSTFLAGS", rustflags);
+            }
+            // The dependencies of Noir libraries need iOS 15 and above.
+            if params.using_noir {
+                build_cmd.env("IPHONEOS_DEPLOYMENT_TARGET", "15.0");
+            }
+            build_cmd
+                .arg("--lib")
+                .current_dir(project_dir)
+                .env(
+                    "CARGO_BUILD_TARGET_DIR",
+                    arch_target_dir(&project.target_dir, arch.as_str(), parallel),
+                )
+                .env("CARGO_BUILD_TARGET", arch.as_str());
+            plan.steps
+                .push(BuildStep::arch_command(arch.as_str(), &build_cmd));
+        }
 
-        let out_dylib_path = build_dir_path.join(format!(
-            "{}/{}/{}",
-            target_archs[0].as_str(),
-            mode.as_str(),
-            lib_name.replace(".a", ".dylib")
-        ));
-
-        generate_ios_bindings(&out_dylib_path, &swift_bindings_dir)
-            .expect("Failed to generate bindings for iOS");
+        // Combine the libraries of each group of architectures into
+        // a single universal binary/archive
+        let mut arch_paths = Vec::new();
+        for (group_name, archs) in group_target_archs(&target_archs)? {
+            let group_dir = work_dir.join(group_name);
+            let lib_out = group_dir.join(&lib_name);
+            plan.steps.push(BuildStep::CreateDir { path: group_dir });
+            let mut lipo_cmd = Command::new("lipo");
+            lipo_cmd.arg("-create").arg("-output").arg(&lib_out);
+            for arch in &archs {
+                lipo_cmd.arg(out_lib_path(arch));
+            }
+            plan.steps.push(BuildStep::command(&lipo_cmd));
+
+            let arch_strs: Vec<&str> = archs.iter().map(|arch| arch.as_str()).collect();
+            arch_paths.push(ArchPath::new(
+                Path::new(framework_name).join(xcframework_slice(&archs)),
+                &arch_strs,
+            ));
+
+            plan.arch_groups.push(ArchGroup {
+                name: group_name.to_string(),
+                archs: archs.iter().map(|arch| arch.as_str().to_string()).collect(),
+                library: lib_out,
+            });
+        }
 
-        fs::rename(
-            swift_bindings_dir.join(&gen_swift_file_name),
-            bindings_out.join(out_swift_file_name),
-        )
-        .context(format!(
-            "Failed to rename bindings from {gen_swift_file_name}"
-        ))?;
+        let out_dylib_path = out_lib_path(&target_archs[0]).with_extension("dylib");
+        plan.steps.push(BuildStep::GenerateBindings {
+            language: BindingsLanguage::Swift,
+            library: out_dylib_path,
+            out_dir: swift_bindings_dir.clone(),
+        });
+        plan.steps.push(BuildStep::Rename {
+            from: swift_bindings_dir.join(&gen_swift_file_name),
+            to: bindings_out.join(out_swift_file_name),
+        });
 
         let mut xcbuild_cmd = Command::new("xcodebuild");
         // The dependencies of Noir libraries need iOS 15 and above.
@@ -146,49 +191,92 @@ impl PlatformBuilder for IosPlatform {
             xcbuild_cmd.env("IPHONEOS_DEPLOYMENT_TARGET", "15.0");
         }
         xcbuild_cmd.arg("-create-xcframework");
-        for lib_path in out_lib_paths {
+        for group in &plan.arch_groups {
             xcbuild_cmd
                 .arg("-library")
-                .arg(lib_path.to_str().unwrap())
+                .arg(&group.library)
                 .arg("-headers")
-                .arg(swift_bindings_dir.to_str().unwrap());
+                .arg(&swift_bindings_dir);
         }
-        xcbuild_cmd
-            .arg("-output")
-            .arg(framework_out.to_str().unwrap())
-            .spawn()
-            .context("Failed to spawn xcodebuild")?
-            .wait()
-            .context("xcodebuild command failed")?;
+        xcbuild_cmd.arg("-output").arg(&framework_out);
+        plan.steps.push(BuildStep::command(&xcbuild_cmd));
+
+        plan.steps
+            .push(BuildStep::patch(REGROUP_HEADERS_PATCH, &framework_out));
+        plan.steps.push(BuildStep::Install {
+            from: bindings_out,
+            to: bindings_dest.clone(),
+        });
+        plan.steps.push(BuildStep::WriteManifest {
+            dir: bindings_dest,
+            uniffi_version: Some(UNIFFI_VERSION.to_string()),
+            arch_paths,
+        });
+
+        Ok(plan)
+    }
 
+    fn apply_patch(
+        plan: &BuildPlan,
+        name: &str,
+        path: &Path,
+        _params: &Self::Params,
+    ) -> Result<(), BindingsError> {
+        if name != REGROUP_HEADERS_PATCH {
+            return Err(BindingsError::unknown_patch(Self::identifier(), name));
+        }
+        let uniffi_style_identifier = &plan.project.lib_name;
         // Swift requires module maps named "module.modulemap", but uniffi uses "<placeholder>FFI.modulemap".
         // To support multiple libraries in the same project without naming conflicts,
         // we move each header + module map into its own subdirectory and rename accordingly.
         regroup_header_artifacts(
-            &framework_out,
-            &header_name,
-            &modulemap_name,
-            &uniffi_style_identifier,
+            path,
+            &format!("{uniffi_style_identifier}FFI.h"),
+            &format!("{uniffi_style_identifier}FFI.modulemap"),
+            uniffi_style_identifier,
         )
-        .expect("Failed to generate header artifacts");
-
-        if let Ok(info) = fs::metadata(&bindings_dest) {
-            if !info.is_dir() {
-                panic!("framework directory exists and is not a directory");
-            }
-            fs::remove_dir_all(&bindings_dest).expect("Failed to remove framework directory");
-        }
-
-        fs::rename(&bindings_out, &bindings_dest).expect("Failed to move framework into place");
-        // Copy the mopro.swift file to the output directory
-        cleanup_tmp_local(&build_dir_path);
+    }
 
-        Ok(bindings_dest)
+    fn toolchain_checks(target_archs: &[Self::Arch]) -> Vec<ToolCheck> {
+        let targets: Vec<&str> = target_archs.iter().map(|arch| arch.as_str()).collect();
+        let mut checks = check_rustup_targets(None, &targets);
+        checks.push(check_command(
+            "lipo",
+            "xcrun",
+            &["--find", "lipo"],
+            "Install the Xcode command line tools: xcode-select --install",
+        ));
+        checks.push(check_command(
+            "xcodebuild",
+            "xcodebuild",
+            &["-version"],
+            "Install Xcode from the App Store and run: sudo xcode-select -s /Applications/Xcode.app",
+        ));
+        checks
     }
 }
 
 // More general cases
-fn group_target_archs(target_archs: &[IosArch]) -> Vec<Vec<IosArch>> {
+/// Directory of the XCFramework slice `xcodebuild` creates for a library of `archs`,
+/// e.g. `ios-arm64_x86_64-simulator`.
+fn xcframework_slice(archs: &[IosArch]) -> String {
+    let mut names: Vec<&str> = archs
+        .iter()
+        .map(|arch| match arch {
+            IosArch::X8664Apple => "x86_64",
+            IosArch::Aarch64Apple | IosArch::Aarch64AppleSim => "arm64",
+        })
+        .collect();
+    names.sort();
+    names.dedup();
+    let is_simulator = archs.iter().any(|arch| *arch != IosArch::Aarch64Apple);
+    let variant = if is_simulator { "-simulator" } else { "" };
+    format!("ios-{}{variant}", names.join("_"))
+}
+
+fn group_target_archs(
+    target_archs: &[IosArch],
+) -> Result<Vec<(&'static str, Vec<IosArch>)>, BindingsError> {
     // Detect the current architecture
     let current_arch = std::env::consts::ARCH;
 
@@ -196,7 +284,12 @@ fn group_target_archs(target_archs: &[IosArch]) -> Vec<Vec<IosArch>> {
     let device_prefix = match current_arch {
         arch if arch.starts_with(ARCH_X86_64) => ARCH_X86_64,
         arch if arch.starts_with(ARCH_ARM_64) => ARCH_ARM_64,
-        _ => panic!("Unsupported host architecture: {current_arch}"),
+        _ => {
+            return Err(BindingsError::InvalidArch {
+                arch: current_arch.to_string(),
+                supported: vec![ARCH_X86_64.to_string(), ARCH_ARM_64.to_string()],
+            })
+        }
     };
 
     let mut device_archs = Vec::new();
@@ -215,13 +308,13 @@ fn group_target_archs(target_archs: &[IosArch]) -> Vec<Vec<IosArch>> {
 
     let mut grouped_archs = Vec::new();
     if !device_archs.is_empty() {
-        grouped_archs.push(device_archs);
+        grouped_archs.push(("device", device_archs));
     }
     if !simulator_archs.is_empty() {
-        grouped_archs.push(simulator_archs);
+        grouped_archs.push(("simulator", simulator_archs));
     }
 
-    grouped_archs
+    Ok(grouped_archs)
 }
 
 /// Iterate over all architecture entries inside the .xcframework
@@ -233,11 +326,11 @@ pub fn regroup_header_artifacts(
     header_name: &str,
     modulemap_name: &str,
     project_name: &str,
-) -> anyhow::Result<()> {
-    for entry in
-        fs::read_dir(framework_out).with_context(|| format!("reading {framework_out:?}"))?
+) -> Result<(), BindingsError> {
+    for entry in fs::read_dir(framework_out)
+        .map_err(BindingsError::io(format!("reading {framework_out:?}")))?
     {
-        let entry = entry?;
+        let entry = entry.map_err(BindingsError::io(format!("reading {framework_out:?}")))?;
         let arch_path = entry.path();
         if !arch_path.is_dir() {
             // Skip Info.plist or anything that isn't a directory slice
@@ -255,40 +348,135 @@ pub fn regroup_header_artifacts(
 
         // Destination directory: Headers/<identifier>/
         let target_dir = headers_dir.join(project_name);
-        fs::create_dir_all(&target_dir).with_context(|| format!("creating {target_dir:?}"))?;
+        fs::create_dir_all(&target_dir)
+            .map_err(BindingsError::io(format!("creating {target_dir:?}")))?;
 
         // ── move & rename ────────────────────────────────────────
         if modmap_src.exists() {
             fs::rename(&modmap_src, target_dir.join("module.modulemap"))
-                .with_context(|| format!("moving {modmap_src:?}"))?;
+                .map_err(BindingsError::io(format!("moving {modmap_src:?}")))?;
         }
         if header_src.exists() {
             fs::rename(&header_src, target_dir.join(header_name))
-                .with_context(|| format!("moving {header_src:?}"))?;
+                .map_err(BindingsError::io(format!("moving {header_src:?}")))?;
         }
     }
 
     Ok(())
 }
 
-fn generate_ios_bindings(dylib_path: &Path, binding_dir: &Path) -> anyhow::Result<()> {
-    if binding_dir.exists() {
-        fs::remove_dir_all(binding_dir)?;
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bindings::constants::{BuildContext, Mode};
+    use crate::bindings::observer::NoopObserver;
+    use crate::bindings::project::test_project;
+    use crate::bindings::runner::RecordingRunner;
+
+    #[test]
+    fn test_build_commands() {
+        let project = test_project("my-circuits", &["lib", "cdylib", "staticlib"]);
+        let project_dir = project.manifest_dir.clone();
+
+        let runner = RecordingRunner::new();
+        let bindings_dir = IosPlatform::build(
+            &BuildOptions::new(Mode::Release),
+            &project,
+            &project_dir,
+            vec![IosArch::Aarch64AppleSim],
+            &[],
+            IosBindingsParams::default(),
+            BuildContext::new(&runner, &NoopObserver),
+        )
+        .unwrap();
+        assert_eq!(bindings_dir, project_dir.join(IOS_BINDINGS_DIR));
+
+        let commands = runner.commands();
+        let lines: Vec<String> = commands.iter().map(|cmd| cmd.command()).collect();
+        assert_eq!(lines[0], "rustup target add aarch64-apple-ios-sim");
+        assert_eq!(lines[1], "cargo build --release --lib");
+        assert!(commands[1].envs.contains(&(
+            "CARGO_BUILD_TARGET".to_string(),
+            Some("aarch64-apple-ios-sim".to_string())
+        )));
+        assert_eq!(
+            commands[1].current_dir.as_deref(),
+            Some(project_dir.as_path())
+        );
+        assert!(lines[2].starts_with("lipo -create -output "));
+        assert!(lines[2].ends_with("/target/aarch64-apple-ios-sim/release/libmy_circuits.a"));
+        assert!(lines[3].starts_with("xcodebuild -create-xcframework -library "));
+        assert_eq!(lines.len(), 4);
     }
 
-    generate_bindings_library_mode(
-        Utf8Path::from_path(dylib_path)
-            .ok_or(Error::new(ErrorKind::InvalidInput, "Invalid dylib path"))?,
-        None,
-        &SwiftBindingGenerator,
-        &CargoMetadataConfigSupplier::default(),
-        None,
-        Utf8Path::from_path(binding_dir).ok_or(Error::new(
-            ErrorKind::InvalidInput,
-            "Invalid swift files directory",
-        ))?,
-        true,
-    )
-    .map_err(|e| Error::other(e.to_string()))?;
-    Ok(())
+    #[test]
+    fn test_plan() {
+        let project = test_project("my-circuits", &["lib", "cdylib", "staticlib"]);
+        let project_dir = project.manifest_dir.clone();
+
+        let plan = IosPlatform::plan(
+            &BuildOptions::new(Mode::Release),
+            &project,
+            &project_dir,
+            vec![IosArch::Aarch64AppleSim],
+            &[],
+            &IosBindingsParams::default(),
+        )
+        .unwrap();
+        assert_eq!(plan.arch_groups.len(), 1);
+        assert_eq!(plan.arch_groups[0].name, "simulator");
+        assert_eq!(plan.arch_groups[0].archs, ["aarch64-apple-ios-sim"]);
+
+        let library = plan.steps.iter().find_map(|step| match step {
+            BuildStep::GenerateBindings { library, .. } => Some(library),
+            _ => None,
+        });
+        assert_eq!(
+            library,
+            Some(
+                &project
+                    .target_dir
+                    .join("aarch64-apple-ios-sim/release/libmy_circuits.dylib")
+            )
+        );
+        let [.., install, manifest] = plan.steps.as_slice() else {
+            panic!("plan has too few steps");
+        };
+        assert!(matches!(
+            install,
+            BuildStep::Install { to, .. } if *to == project_dir.join(IOS_BINDINGS_DIR)
+        ));
+        match manifest {
+            BuildStep::WriteManifest { arch_paths, .. } => assert_eq!(
+                arch_paths,
+                &[ArchPath::new(
+                    "MoproBindings.xcframework/ios-arm64-simulator",
+                    &["aarch64-apple-ios-sim"]
+                )]
+            ),
+            step => panic!("unexpected last step: {step}"),
+        }
+        // Planning does not touch the file system
+        assert!(!project_dir.join("build").exists());
+
+        // Plans are public, so they may name patches the builder does not have
+        assert!(matches!(
+            IosPlatform::apply_patch(
+                &plan,
+                "missing",
+                &project_dir,
+                &IosBindingsParams::default()
+            ),
+            Err(BindingsError::UnknownPatch { patch, .. }) if patch == "missing"
+        ));
+    }
+
+    #[test]
+    fn test_xcframework_slice() {
+        assert_eq!(xcframework_slice(&[IosArch::Aarch64Apple]), "ios-arm64");
+        assert_eq!(
+            xcframework_slice(&[IosArch::X8664Apple, IosArch::Aarch64AppleSim]),
+            "ios-arm64_x86_64-simulator"
+        );
+    }
 }
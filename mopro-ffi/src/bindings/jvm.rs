@@ -0,0 +1,381 @@
+use std::path::Path;
+
+use super::PlatformBuilder;
+
+use super::android::{AndroidBindingsParams, KOTLIN_PACKAGE_PATCH};
+use super::config::{FromPlatformConfig, PlatformConfig};
+use super::constants::{
+    AndroidPlatform, Arch, BuildOptions, JvmArch, JvmPlatform, Platform, ANDROID_PACKAGE_NAME,
+    JVM_BINDINGS_DIR, JVM_JNA_VERSION, JVM_KOTLIN_VERSION,
+};
+use super::desktop::build_for_arch;
+use super::doctor::{check_command, check_rustup_targets, ToolCheck};
+use super::error::BindingsError;
+use super::manifest::{ArchPath, UNIFFI_VERSION};
+use super::plan::{BindingsLanguage, BuildPlan, BuildStep};
+use super::project::Project;
+use super::{arch_target_dir, install_arch, work_dir_path};
+
+// Source sets of the Gradle project, relative to its root
+const KOTLIN_DIR: &str = "src/main/kotlin";
+const RESOURCES_DIR: &str = "src/main/resources";
+
+#[inline]
+pub fn build() {
+    super::build_from_env::<JvmPlatform>().unwrap_or_else(|e| {
+        panic!(
+            "Failed to build {} bindings: {e}",
+            JvmPlatform::identifier()
+        )
+    })
+}
+
+#[derive(Hash)]
+pub struct JvmBindingsParams {
+    /// Name of the generated bindings directory
+    pub bindings_dir: String,
+    /// Kotlin package the bindings are generated in, as `uniffi.<package_name>`
+    pub package_name: String,
+}
+
+impl Default for JvmBindingsParams {
+    fn default() -> Self {
+        Self {
+            bindings_dir: JVM_BINDINGS_DIR.to_string(),
+            package_name: ANDROID_PACKAGE_NAME.to_string(),
+        }
+    }
+}
+
+impl FromPlatformConfig for JvmBindingsParams {
+    fn from_platform_config(config: &PlatformConfig) -> anyhow::Result<Self> {
+        config.check_params(&["package_name"])?;
+        let default = Self::default();
+        Ok(Self {
+            bindings_dir: config.bindings_dir.clone().unwrap_or(default.bindings_dir),
+            package_name: config
+                .string_param("package_name")?
+                .unwrap_or(default.package_name),
+        })
+    }
+}
+
+impl PlatformBuilder for JvmPlatform {
+    type Arch = JvmArch;
+    type Params = JvmBindingsParams;
+
+    /// Lays the bindings out as a Gradle project whose jar has the Kotlin API of the Android
+    /// bindings and the libraries where JNA loads them from
+    ///
+    /// ```text
+    /// MoproJvmBindings/
+    /// ├── settings.gradle.kts
+    /// ├── build.gradle.kts
+    /// └── src/main/
+    ///     ├── kotlin/uniffi/mopro/mopro.kt
+    ///     └── resources/linux-x86-64/lib<name>.so
+    /// ```
+    fn plan(
+        options: &BuildOptions,
+        project: &Project,
+        output_dir: &Path,
+        target_archs: Vec<Self::Arch>,
+        fresh_archs: &[Self::Arch],
+        params: &Self::Params,
+    ) -> Result<BuildPlan, BindingsError> {
+        if !cfg!(feature = "uniffi") {
+            return Err(BindingsError::MissingFeature { feature: "uniffi" });
+        }
+
+        project.require_crate_types(&["cdylib"])?;
+        let project_dir = &project.manifest_dir;
+        let lib_name = format!("lib{}.so", project.lib_name);
+
+        // Paths for the generated files
+        let work_dir = work_dir_path(&project_dir.join("build"));
+        let bindings_out = work_dir.join(&params.bindings_dir);
+        let bindings_dest = output_dir.join(&params.bindings_dir);
+        let kotlin_out = bindings_out.join(KOTLIN_DIR);
+
+        let mut plan = BuildPlan::new(Self::name(), options, project, output_dir, &bindings_dest);
+        plan.work_dir = Some(work_dir.clone());
+        plan.steps.push(BuildStep::CreateDir {
+            path: kotlin_out.clone(),
+        });
+
+        let mode = &options.mode;
+        let parallel = options.jobs > 1 && target_archs.len() > 1;
+        let out_lib_path = |arch: &JvmArch| {
+            arch_target_dir(&project.target_dir, arch.as_str(), parallel)
+                .join(arch.as_str())
+                .join(mode.as_str())
+                .join(&lib_name)
+        };
+        let resource_dir = |arch: &JvmArch| Path::new(RESOURCES_DIR).join(arch.jna_prefix());
+
+        // Only rebuild the targets whose inputs changed or whose library is missing
+        let stale_archs: Vec<JvmArch> = target_archs
+            .iter()
+            .copied()
+            .filter(|arch| !(fresh_archs.contains(arch) && out_lib_path(arch).exists()))
+            .collect();
+        if !options.offline {
+            for arch in &stale_archs {
+                plan.steps.push(install_arch(arch.as_str()));
+            }
+        }
+        for arch in &stale_archs {
+            plan.steps.push(build_for_arch(
+                arch.desktop_arch(),
+                project_dir,
+                &arch_target_dir(&project.target_dir, arch.as_str(), parallel),
+                options,
+            ));
+        }
+        for arch in &target_archs {
+            plan.steps.push(BuildStep::Copy {
+                from: out_lib_path(arch),
+                to: bindings_out.join(resource_dir(arch)).join(&lib_name),
+            });
+        }
+
+        // The same Kotlin API as on Android, without the Android specific code
+        plan.steps.push(BuildStep::GenerateBindings {
+            language: BindingsLanguage::Kotlin,
+            library: out_lib_path(&target_archs[0]),
+            out_dir: kotlin_out.clone(),
+        });
+        plan.steps
+            .push(BuildStep::patch(KOTLIN_PACKAGE_PATCH, &kotlin_out));
+        plan.steps.push(BuildStep::WriteFile {
+            path: bindings_out.join("settings.gradle.kts"),
+            contents: format!("rootProject.name = \"{}\"\n", project.package),
+        });
+        plan.steps.push(BuildStep::WriteFile {
+            path: bindings_out.join("build.gradle.kts"),
+            contents: build_gradle_kts(project, &params.package_name),
+        });
+        plan.steps.push(BuildStep::Install {
+            from: bindings_out,
+            to: bindings_dest.clone(),
+        });
+        plan.steps.push(BuildStep::WriteManifest {
+            dir: bindings_dest,
+            uniffi_version: Some(UNIFFI_VERSION.to_string()),
+            arch_paths: target_archs
+                .iter()
+                .map(|arch| ArchPath::new(resource_dir(arch), &[arch.as_str()]))
+                .collect(),
+        });
+
+        Ok(plan)
+    }
+
+    fn apply_patch(
+        plan: &BuildPlan,
+        name: &str,
+        path: &Path,
+        params: &Self::Params,
+    ) -> Result<(), BindingsError> {
+        let android_params = AndroidBindingsParams {
+            package_name: params.package_name.clone(),
+            ..AndroidBindingsParams::default()
+        };
+        AndroidPlatform::apply_patch(plan, name, path, &android_params)
+    }
+
+    fn toolchain_checks(target_archs: &[Self::Arch]) -> Vec<ToolCheck> {
+        let targets: Vec<&str> = target_archs.iter().map(|arch| arch.as_str()).collect();
+        let mut checks = check_rustup_targets(None, &targets);
+        for arch in target_archs
+            .iter()
+            .filter(|arch| !arch.desktop_arch().is_host())
+        {
+            let linker = arch.desktop_arch().linker();
+            checks.push(check_command(
+                linker,
+                linker,
+                &["--version"],
+                &format!(
+                    "Install the cross linker, e.g. sudo apt install gcc-{}",
+                    linker.trim_end_matches("-gcc")
+                ),
+            ));
+        }
+        checks.push(check_command(
+            "java",
+            "java",
+            &["-version"],
+            "Install a JDK, e.g. sudo apt install openjdk-17-jdk",
+        ));
+        checks
+    }
+}
+
+/// `build.gradle.kts` building the jar of the bindings, which `gradle publishToMavenLocal`
+/// publishes as `uniffi.<package_name>:<crate>:<version>`.
+fn build_gradle_kts(project: &Project, package_name: &str) -> String {
+    format!(
+        "plugins {{\n    \
+             kotlin(\"jvm\") version \"{JVM_KOTLIN_VERSION}\"\n    \
+             `java-library`\n    \
+             `maven-publish`\n\
+         }}\n\
+         \n\
+         group = \"uniffi.{package_name}\"\n\
+         version = \"{}\"\n\
+         \n\
+         repositories {{\n    \
+             mavenCentral()\n\
+         }}\n\
+         \n\
+         dependencies {{\n    \
+             api(\"net.java.dev.jna:jna:{JVM_JNA_VERSION}\")\n\
+         }}\n\
+         \n\
+         publishing {{\n    \
+             publications {{\n        \
+                 create<MavenPublication>(\"maven\") {{\n            \
+                     from(components[\"java\"])\n        \
+                 }}\n    \
+             }}\n\
+         }}\n",
+        project.version
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bindings::constants::{BuildContext, Mode};
+    use crate::bindings::observer::NoopObserver;
+    use crate::bindings::project::test_project;
+    use crate::bindings::runner::RecordingRunner;
+    use std::fs;
+
+    #[test]
+    fn test_jar_layout() {
+        let project = test_project("my-circuits", &["lib", "cdylib"]);
+        let project_dir = project.manifest_dir.clone();
+
+        let runner = RecordingRunner::new();
+        let bindings_dir = JvmPlatform::build(
+            &BuildOptions::new(Mode::Release),
+            &project,
+            &project_dir,
+            vec![JvmArch::X8664LinuxGnu],
+            &[],
+            JvmBindingsParams::default(),
+            BuildContext::new(&runner, &NoopObserver),
+        )
+        .unwrap();
+        assert_eq!(bindings_dir, project_dir.join(JVM_BINDINGS_DIR));
+        let lines: Vec<String> = runner.commands().iter().map(|cmd| cmd.command()).collect();
+        assert_eq!(
+            lines,
+            [
+                "rustup target add x86_64-unknown-linux-gnu",
+                "cargo build --lib --release",
+            ]
+        );
+
+        let plan = JvmPlatform::plan(
+            &BuildOptions::new(Mode::Release),
+            &project,
+            &project_dir,
+            vec![JvmArch::X8664LinuxGnu, JvmArch::Aarch64LinuxGnu],
+            &[],
+            &JvmBindingsParams::default(),
+        )
+        .unwrap();
+        let bindings_out = plan.work_dir.clone().unwrap().join(JVM_BINDINGS_DIR);
+        let copies: Vec<&Path> = plan.copies().into_iter().map(|(_, to)| to).collect();
+        assert_eq!(
+            copies,
+            [
+                bindings_out.join("src/main/resources/linux-x86-64/libmy_circuits.so"),
+                bindings_out.join("src/main/resources/linux-aarch64/libmy_circuits.so"),
+            ]
+        );
+        // No `[bindings.kotlin] android = true`, so the bindings load the library with JNA
+        assert!(plan.written_file("uniffi_config.toml").is_none());
+        assert!(plan.steps.iter().any(|step| matches!(
+            step,
+            BuildStep::Patch { name, path }
+                if name == KOTLIN_PACKAGE_PATCH && path == &bindings_out.join(KOTLIN_DIR)
+        )));
+        let build_gradle = plan.written_file("build.gradle.kts").unwrap();
+        assert!(build_gradle.contains("group = \"uniffi.mopro\"\nversion = \"0.1.0\"\n"));
+        assert!(build_gradle.contains("api(\"net.java.dev.jna:jna:"));
+    }
+
+    /// Builds the bindings of a uniffi crate and compiles them, if `kotlinc` is installed. JNA
+    /// is taken from `JNA_JAR`, or from the system package at `/usr/share/java/jna.jar`.
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn test_compile_kotlin() {
+        use crate::bindings::constants::ANDROID_KT_FILE;
+        use crate::bindings::doctor::command_output;
+        use crate::bindings::project::uniffi_test_project;
+        use crate::bindings::runner::SystemRunner;
+        use std::path::PathBuf;
+        use std::process::Command;
+
+        if command_output("kotlinc", &["-version"]).is_err() {
+            eprintln!("kotlinc is not installed, skipping");
+            return;
+        }
+        let jna_jar = std::env::var_os("JNA_JAR")
+            .map(PathBuf::from)
+            .unwrap_or(PathBuf::from("/usr/share/java/jna.jar"));
+        if !jna_jar.exists() {
+            eprintln!("{} not found, skipping", jna_jar.display());
+            return;
+        }
+        let Some(host) = JvmArch::all_strings()
+            .into_iter()
+            .map(|arch| JvmArch::parse_from_str(arch).unwrap())
+            .find(|arch| arch.desktop_arch().is_host())
+        else {
+            eprintln!("The host is not a JVM target, skipping");
+            return;
+        };
+        let project = uniffi_test_project("jvm-smoke");
+        let project_dir = project.manifest_dir.clone();
+
+        let runner = SystemRunner::new();
+        let bindings_dir = JvmPlatform::build(
+            &BuildOptions {
+                offline: true,
+                ..BuildOptions::new(Mode::Debug)
+            },
+            &project,
+            &project_dir,
+            vec![host],
+            &[],
+            JvmBindingsParams::default(),
+            BuildContext::new(&runner, &NoopObserver),
+        )
+        .unwrap();
+        let kotlin_file = bindings_dir
+            .join(KOTLIN_DIR)
+            .join("uniffi/mopro")
+            .join(ANDROID_KT_FILE);
+        assert!(fs::read_to_string(&kotlin_file)
+            .unwrap()
+            .contains("package uniffi.mopro"));
+        let output = Command::new("kotlinc")
+            .arg(bindings_dir.join(KOTLIN_DIR))
+            .arg("-classpath")
+            .arg(&jna_jar)
+            .arg("-d")
+            .arg(project_dir.join("classes"))
+            .output()
+            .unwrap();
+        assert!(
+            output.status.success(),
+            "{}",
+            String::from_utf8_lossy(&output.stderr)
+        );
+    }
+}
@@ -0,0 +1,280 @@
+//! Manifest of the files a build produced, written next to the bindings as `mopro-manifest.json`
+//! so the bindings shipped in an app can be checked against the ones CI built:
+//!
+//! ```json
+//! {
+//!   "platform": "android",
+//!   "mode": "release",
+//!   "crate": { "name": "my-circuits", "version": "0.1.0" },
+//!   "mopro_ffi_version": "0.2.0",
+//!   "uniffi_version": "0.29.0",
+//!   "git_commit": "4f1d...",
+//!   "files": [
+//!     { "path": "jniLibs/arm64-v8a/libmy_circuits.so", "sha256": "9b2e...", "archs": ["aarch64-linux-android"] },
+//!     { "path": "uniffi/mopro/mopro.kt", "sha256": "3f1c..." }
+//!   ]
+//! }
+//! ```
+
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use super::error::BindingsError;
+use super::fingerprint::{hex, FINGERPRINT_DIR};
+use super::project::Project;
+
+pub const MANIFEST_FILE: &str = "mopro-manifest.json";
+
+/// Version of uniffi the Swift and Kotlin bindings are generated with, as pinned in `Cargo.toml`.
+pub const UNIFFI_VERSION: &str = "0.29.0";
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Manifest {
+    pub platform: String,
+    pub mode: String,
+    #[serde(rename = "crate")]
+    pub crate_info: CrateInfo,
+    pub mopro_ffi_version: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub uniffi_version: Option<String>,
+    /// Commit of the project's git repository, if it is in one
+    pub git_commit: Option<String>,
+    /// Every file of the bindings, sorted by path
+    pub files: Vec<ManifestFile>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CrateInfo {
+    pub name: String,
+    pub version: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ManifestFile {
+    /// Path relative to the bindings directory, with `/` separators
+    pub path: String,
+    pub sha256: String,
+    /// Architectures the file is built for, empty if it does not depend on the architecture
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub archs: Vec<String>,
+}
+
+/// A file or directory of the bindings built for some architectures, e.g. the `arm64-v8a`
+/// directory of the Android `jniLibs`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ArchPath {
+    /// Path relative to the bindings directory
+    pub path: PathBuf,
+    pub archs: Vec<String>,
+}
+
+impl ArchPath {
+    pub fn new(path: impl Into<PathBuf>, archs: &[&str]) -> Self {
+        Self {
+            path: path.into(),
+            archs: archs.iter().map(|arch| arch.to_string()).collect(),
+        }
+    }
+}
+
+impl Manifest {
+    /// Lists the files of the bindings in `bindings_dir`, built from `project`.
+    /// A file takes the architectures of the innermost of `arch_paths` it is in.
+    pub fn compute(
+        platform: &str,
+        mode: &str,
+        project: &Project,
+        bindings_dir: &Path,
+        uniffi_version: Option<&str>,
+        arch_paths: &[ArchPath],
+    ) -> Result<Self, BindingsError> {
+        let files = list_files(bindings_dir)?
+            .into_iter()
+            .map(|relative| {
+                let path = bindings_dir.join(&relative);
+                let contents = fs::read(&path).map_err(BindingsError::io(format!(
+                    "Failed to read {}",
+                    path.display()
+                )))?;
+                let archs = arch_paths
+                    .iter()
+                    .filter(|arch_path| relative.starts_with(&arch_path.path))
+                    .max_by_key(|arch_path| arch_path.path.components().count())
+                    .map(|arch_path| arch_path.archs.clone())
+                    .unwrap_or_default();
+                Ok(ManifestFile {
+                    path: relative
+                        .components()
+                        .map(|component| component.as_os_str().to_string_lossy())
+                        .collect::<Vec<_>>()
+                        .join("/"),
+                    sha256: hex(&Sha256::digest(contents)),
+                    archs,
+                })
+            })
+            .collect::<Result<_, BindingsError>>()?;
+
+        Ok(Self {
+            platform: platform.to_string(),
+            mode: mode.to_string(),
+            crate_info: CrateInfo {
+                name: project.package.clone(),
+                version: project.version.clone(),
+            },
+            mopro_ffi_version: env!("CARGO_PKG_VERSION").to_string(),
+            uniffi_version: uniffi_version.map(str::to_string),
+            git_commit: git_commit(&project.manifest_dir),
+            files,
+        })
+    }
+
+    /// Writes the manifest into `bindings_dir` and returns its path.
+    pub fn save(&self, bindings_dir: &Path) -> Result<PathBuf, BindingsError> {
+        let path = bindings_dir.join(MANIFEST_FILE);
+        let contents =
+            serde_json::to_string_pretty(self).expect("manifests serialize to JSON") + "\n";
+        fs::write(&path, contents).map_err(BindingsError::io(format!(
+            "Failed to write {}",
+            path.display()
+        )))?;
+        Ok(path)
+    }
+}
+
+/// Paths of the files in `dir` relative to it, sorted, leaving out fingerprints and a previous
+/// manifest.
+fn list_files(dir: &Path) -> Result<Vec<PathBuf>, BindingsError> {
+    let mut files = Vec::new();
+    let mut dirs = vec![dir.to_path_buf()];
+    while let Some(current) = dirs.pop() {
+        let entries: Vec<PathBuf> = fs::read_dir(&current)
+            .and_then(|entries| {
+                entries
+                    .map(|entry| entry.map(|entry| entry.path()))
+                    .collect()
+            })
+            .map_err(BindingsError::io(format!(
+                "Failed to read {}",
+                current.display()
+            )))?;
+        for path in entries {
+            if path.is_dir() {
+                if !path.ends_with(FINGERPRINT_DIR) {
+                    dirs.push(path);
+                }
+            } else if path != dir.join(MANIFEST_FILE) {
+                files.push(path.strip_prefix(dir).unwrap_or(&path).to_path_buf());
+            }
+        }
+    }
+    files.sort();
+    Ok(files)
+}
+
+/// Commit checked out in the repository of `project_dir`.
+fn git_commit(project_dir: &Path) -> Option<String> {
+    let output = Command::new("git")
+        .arg("-C")
+        .arg(project_dir)
+        .args(["rev-parse", "HEAD"])
+        .output()
+        .ok()?;
+    output
+        .status
+        .success()
+        .then(|| String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bindings::project::TestProject;
+
+    #[test]
+    fn test_compute() {
+        let project = TestProject::new(&[
+            (
+                "Cargo.toml",
+                "[package]\nname = \"my-circuits\"\nversion = \"1.2.3\"\n",
+            ),
+            ("src/lib.rs", ""),
+        ]);
+        let project_dir = project.manifest_dir.clone();
+        let bindings_dir = project_dir.join("Bindings");
+        fs::create_dir_all(bindings_dir.join("jniLibs/arm64-v8a")).unwrap();
+        fs::create_dir_all(bindings_dir.join("uniffi")).unwrap();
+        fs::write(bindings_dir.join("jniLibs/arm64-v8a/lib.so"), "lib").unwrap();
+        fs::write(bindings_dir.join("uniffi/mopro.kt"), "").unwrap();
+        // Every file that ships is listed, also hidden ones and build directories
+        fs::create_dir_all(bindings_dir.join("build")).unwrap();
+        fs::write(bindings_dir.join("build/mopro.h"), "").unwrap();
+        fs::write(bindings_dir.join(".gitignore"), "").unwrap();
+        fs::create_dir_all(bindings_dir.join(FINGERPRINT_DIR)).unwrap();
+        fs::write(bindings_dir.join(FINGERPRINT_DIR).join("ios.toml"), "").unwrap();
+        fs::write(bindings_dir.join(MANIFEST_FILE), "{}").unwrap();
+
+        let arch_paths = [ArchPath::new(
+            "jniLibs/arm64-v8a",
+            &["aarch64-linux-android"],
+        )];
+        let manifest = Manifest::compute(
+            "android",
+            "release",
+            &project,
+            &bindings_dir,
+            Some(UNIFFI_VERSION),
+            &arch_paths,
+        )
+        .unwrap();
+        assert_eq!(
+            manifest.crate_info,
+            CrateInfo {
+                name: "my-circuits".to_string(),
+                version: "1.2.3".to_string()
+            }
+        );
+        assert_eq!(manifest.mopro_ffi_version, env!("CARGO_PKG_VERSION"));
+        assert_eq!(
+            manifest.files,
+            [
+                ManifestFile {
+                    path: ".gitignore".to_string(),
+                    sha256: "e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855"
+                        .to_string(),
+                    archs: vec![],
+                },
+                ManifestFile {
+                    path: "build/mopro.h".to_string(),
+                    sha256: "e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855"
+                        .to_string(),
+                    archs: vec![],
+                },
+                ManifestFile {
+                    path: "jniLibs/arm64-v8a/lib.so".to_string(),
+                    sha256: "76b5a357391276b282a516f54f48ef3c207f46d8192dc58c208d5183d38415f8"
+                        .to_string(),
+                    archs: vec!["aarch64-linux-android".to_string()],
+                },
+                ManifestFile {
+                    path: "uniffi/mopro.kt".to_string(),
+                    sha256: "e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855"
+                        .to_string(),
+                    archs: vec![],
+                },
+            ]
+        );
+
+        let path = manifest.save(&bindings_dir).unwrap();
+        let saved: Manifest = serde_json::from_str(&fs::read_to_string(path).unwrap()).unwrap();
+        assert_eq!(saved, manifest);
+    }
+
+    #[test]
+    fn test_uniffi_version_matches_cargo_toml() {
+        let cargo_toml = include_str!("../../Cargo.toml");
+        assert!(cargo_toml.contains(&format!("uniffi = {{ version = \"={UNIFFI_VERSION}\"")));
+    }
+}
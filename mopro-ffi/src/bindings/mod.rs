@@ -1,19 +1,49 @@
 use anyhow::Context;
 use std::fs;
+use std::io::ErrorKind;
 use std::path::{Path, PathBuf};
 use std::process::Command;
-use toml::Value;
 use uuid::Uuid;
 
-use self::constants::{Arch, Mode, PlatformBuilder, BUILD_MODE_ENV};
+use self::config::{FromPlatformConfig, MoproConfig};
+use self::constants::{
+    Arch, BuildContext, BuildOptions, CargoFlags, Mode, PlatformBuilder, BUILD_JOBS_ENV,
+    BUILD_MODE_ENV, CI_ENV, OFFLINE_ENV,
+};
+use self::doctor::ToolCheck;
+use self::error::BindingsError;
+use self::fingerprint::Fingerprint;
+use self::observer::{BuildEvent, ConsoleObserver};
+use self::plan::{BuildPlan, BuildStep};
+use self::project::Project;
+use self::runner::SystemRunner;
 
 #[cfg(feature = "uniffi")]
 pub mod android;
+pub mod config;
 pub mod constants;
+#[cfg(feature = "uniffi")]
+pub mod desktop;
+pub mod doctor;
+pub mod error;
+pub mod fingerprint;
 #[cfg(feature = "flutter")]
 pub mod flutter;
+pub mod guard;
 #[cfg(feature = "uniffi")]
 pub mod ios;
+#[cfg(feature = "uniffi")]
+pub mod jvm;
+pub mod manifest;
+pub mod node;
+pub mod observer;
+pub mod plan;
+pub mod project;
+#[cfg(feature = "uniffi")]
+pub mod python;
+#[cfg(feature = "uniffi")]
+pub mod react_native;
+pub mod runner;
 pub mod web;
 
 /// Stub for feature "uniffi"
@@ -34,6 +64,42 @@ pub mod android {
     }
 }
 
+/// Stub for feature "uniffi"
+#[cfg(not(feature = "uniffi"))]
+pub mod desktop {
+    #[inline]
+    pub fn build() {
+        panic!("\"uniffi\" feature is not enabled, please enable it in your Cargo.toml");
+    }
+}
+
+/// Stub for feature "uniffi"
+#[cfg(not(feature = "uniffi"))]
+pub mod python {
+    #[inline]
+    pub fn build() {
+        panic!("\"uniffi\" feature is not enabled, please enable it in your Cargo.toml");
+    }
+}
+
+/// Stub for feature "uniffi"
+#[cfg(not(feature = "uniffi"))]
+pub mod jvm {
+    #[inline]
+    pub fn build() {
+        panic!("\"uniffi\" feature is not enabled, please enable it in your Cargo.toml");
+    }
+}
+
+/// Stub for feature "uniffi"
+#[cfg(not(feature = "uniffi"))]
+pub mod react_native {
+    #[inline]
+    pub fn build() {
+        panic!("\"uniffi\" feature is not enabled, please enable it in your Cargo.toml");
+    }
+}
+
 /// Stub for feature "flutter"
 #[cfg(not(feature = "flutter"))]
 pub mod flutter {
@@ -43,144 +109,291 @@ pub mod flutter {
     }
 }
 
-/// Builds bindings for the specified platform using environment variables to determine
-/// the build mode, project directory, and target architectures.
-fn build_from_env<Builder: PlatformBuilder>() {
-    let mode = get_build_mode();
+/// Builds bindings for the specified platform using environment variables and `mopro.toml`
+/// to determine the build mode, project directory, target architectures and parameters.
+fn build_from_env<Builder: PlatformBuilder>() -> Result<(), BindingsError> {
     let project_dir = get_project_dir();
-    let target_archs: Vec<Builder::Arch> = get_target_archs();
-    let params = Builder::Params::default();
+    let config = MoproConfig::load(&project_dir).map_err(BindingsError::config)?;
+    let options = BuildOptions {
+        mode: resolve_mode(None, &config).map_err(BindingsError::config)?,
+        jobs: resolve_jobs(None, &config).map_err(BindingsError::config)?,
+        offline: resolve_offline(None, &config).map_err(BindingsError::config)?,
+        cargo: resolve_cargo_flags(&CargoFlags::default(), &config),
+        package: config.package.clone(),
+    };
+    let target_archs =
+        resolve_target_archs::<Builder>(&[], &config).map_err(BindingsError::config)?;
+    let output_dir = resolve_output_dir(None, &project_dir, &config);
+    let params = resolve_params::<Builder>(&config).map_err(BindingsError::config)?;
 
     // Do not build if no target architectures are specified
     if target_archs.is_empty() {
-        return;
+        return Ok(());
     }
 
-    Builder::build(mode, &project_dir, target_archs, params)
-        .context(format!(
-            "Failed to build {} bindings",
-            Builder::identifier()
-        ))
-        .unwrap();
+    build_from_str_arch::<Builder>(
+        &options,
+        &project_dir,
+        &output_dir,
+        target_archs.iter().collect(),
+        params,
+        false,
+        BuildContext::new(&SystemRunner::new(), &ConsoleObserver::default()),
+    )?;
+    Ok(())
 }
 
 /// Builds bindings for the specified platform using a string representation of the target architectures.
+/// The bindings are written into `output_dir`, which is created if it does not exist.
+///
+/// The build is skipped if the fingerprint of its inputs matches the one of the previous build,
+/// and only the stale architecture slices are rebuilt otherwise. `force` rebuilds everything.
+/// External tools are run by the runner of `context`, and the progress is reported to its observer.
 pub fn build_from_str_arch<Builder: PlatformBuilder>(
-    mode: Mode,
+    options: &BuildOptions,
     project_dir: &Path,
+    output_dir: &Path,
     target_archs: Vec<&String>,
     params: Builder::Params,
-) -> anyhow::Result<PathBuf> {
-    if target_archs.is_empty() {
-        return Err(anyhow::anyhow!(
-            "No target architectures specified for {} bindings",
+    force: bool,
+    context: BuildContext<'_>,
+) -> Result<PathBuf, BindingsError> {
+    let inputs = BuildInputs::<Builder>::compute(
+        options,
+        project_dir,
+        output_dir,
+        target_archs,
+        &params,
+        force,
+    )?;
+    if let Some(bindings_dir) = inputs.up_to_date_bindings() {
+        let message = format!(
+            "{} bindings are up to date, skipping the build (use --force to rebuild)",
             Builder::identifier()
+        );
+        context
+            .observer
+            .on_event(&BuildEvent::Message { message: &message });
+        return Ok(bindings_dir.to_path_buf());
+    }
+    let fresh_archs = inputs.fresh_archs();
+    let BuildInputs {
+        project,
+        target_archs,
+        mut fingerprint,
+        ..
+    } = inputs;
+
+    // Offline builds leave out the install steps, so everything must be there already
+    if options.offline && !context.runner.is_dry_run() {
+        let missing: Vec<ToolCheck> = Builder::toolchain_checks(&target_archs)
+            .into_iter()
+            .filter(|check| !check.passed())
+            .collect();
+        if !missing.is_empty() {
+            return Err(BindingsError::MissingTools { missing });
+        }
+    }
+
+    fs::create_dir_all(output_dir).map_err(BindingsError::io(format!(
+        "Failed to create output directory {}",
+        output_dir.display()
+    )))?;
+
+    let bindings_dir = Builder::build(
+        options,
+        &project,
+        output_dir,
+        target_archs,
+        &fresh_archs,
+        params,
+        context,
+    )?;
+
+    // A dry run did not produce the bindings the fingerprint would describe
+    if context.runner.is_dry_run() {
+        return Ok(bindings_dir);
+    }
+
+    fingerprint.bindings_dir = bindings_dir.clone();
+    fingerprint.save(output_dir, Builder::name())?;
+
+    Ok(bindings_dir)
+}
+
+/// Computes the plan of [`build_from_str_arch`] without running anything. The plan of bindings
+/// that are up to date has no steps.
+pub fn plan_from_str_arch<Builder: PlatformBuilder>(
+    options: &BuildOptions,
+    project_dir: &Path,
+    output_dir: &Path,
+    target_archs: Vec<&String>,
+    params: &Builder::Params,
+    force: bool,
+) -> Result<BuildPlan, BindingsError> {
+    let inputs = BuildInputs::<Builder>::compute(
+        options,
+        project_dir,
+        output_dir,
+        target_archs,
+        params,
+        force,
+    )?;
+    if let Some(bindings_dir) = inputs.up_to_date_bindings() {
+        return Ok(BuildPlan::up_to_date(
+            Builder::name(),
+            options,
+            &inputs.project,
+            output_dir,
+            bindings_dir,
         ));
     }
+    let fresh_archs = inputs.fresh_archs();
+    Builder::plan(
+        options,
+        &inputs.project,
+        output_dir,
+        inputs.target_archs,
+        &fresh_archs,
+        params,
+    )
+}
 
-    let target_archs: Vec<Builder::Arch> = target_archs
-        .iter()
-        .map(Builder::Arch::parse_from_str)
-        .collect();
+/// The package, the validated target architectures of a build and the fingerprints of its inputs.
+struct BuildInputs<Builder: PlatformBuilder> {
+    project: Project,
+    target_archs: Vec<Builder::Arch>,
+    fingerprint: Fingerprint,
+    /// Fingerprint of the previous build, unless it is forced or its bindings are gone
+    previous: Option<Fingerprint>,
+}
 
-    Builder::build(mode, project_dir, target_archs, params).context(format!(
-        "Failed to build {} bindings",
-        Builder::identifier()
-    ))
+impl<Builder: PlatformBuilder> BuildInputs<Builder> {
+    fn compute(
+        options: &BuildOptions,
+        project_dir: &Path,
+        output_dir: &Path,
+        target_archs: Vec<&String>,
+        params: &Builder::Params,
+        force: bool,
+    ) -> Result<Self, BindingsError> {
+        if target_archs.is_empty() {
+            return Err(BindingsError::NoTargetArchs {
+                platform: Builder::identifier(),
+            });
+        }
+        let target_archs: Vec<Builder::Arch> = target_archs
+            .iter()
+            .map(Builder::Arch::parse_from_str)
+            .collect::<Result<_, _>>()?;
+        let arch_strs: Vec<&'static str> = target_archs.iter().map(|arch| arch.as_str()).collect();
+        let params_hash = fingerprint::hash_params(params);
+
+        let project = Project::detect(project_dir, options.package.as_deref())?;
+        let config = MoproConfig::load(project_dir).map_err(BindingsError::config)?;
+        let bindings_dirs: Vec<PathBuf> = config
+            .bindings_dirs()
+            .iter()
+            .map(|dir| output_dir.join(dir))
+            .collect();
+        let fingerprint = Fingerprint::compute(
+            &project,
+            &options.mode,
+            &options.cargo,
+            &arch_strs,
+            &params_hash,
+            &bindings_dirs,
+        )?;
+        let previous = Fingerprint::load(output_dir, Builder::name())
+            .filter(|previous| !force && previous.bindings_dir.is_dir());
+
+        Ok(Self {
+            project,
+            target_archs,
+            fingerprint,
+            previous,
+        })
+    }
+
+    /// The bindings of the previous build, if they are up to date.
+    fn up_to_date_bindings(&self) -> Option<&Path> {
+        self.previous
+            .as_ref()
+            .filter(|previous| self.fingerprint.is_up_to_date(previous))
+            .map(|previous| previous.bindings_dir.as_path())
+    }
+
+    fn fresh_archs(&self) -> Vec<Builder::Arch> {
+        self.previous
+            .as_ref()
+            .map(|previous| self.fingerprint.fresh_archs(previous))
+            .unwrap_or_default()
+            .iter()
+            .flat_map(Builder::Arch::parse_from_str)
+            .collect()
+    }
 }
 
-pub fn mktemp() -> PathBuf {
+pub fn mktemp() -> Result<PathBuf, BindingsError> {
     let dir = std::env::temp_dir().join(Path::new(&Uuid::new_v4().to_string()));
-    fs::create_dir(&dir).expect("Failed to create tmpdir");
-    dir
+    fs::create_dir(&dir).map_err(BindingsError::io("Failed to create tmpdir"))?;
+    Ok(dir)
 }
 
-fn tmp_local(build_path: &Path) -> PathBuf {
-    let tmp_path = build_path.join("tmp");
-    if let Ok(metadata) = fs::metadata(&tmp_path) {
-        if !metadata.is_dir() {
-            panic!("non-directory tmp");
+/// Replaces `dest` with the directory `out`, removing a previous directory at `dest`.
+pub(crate) fn move_into_place(out: &Path, dest: &Path) -> Result<(), BindingsError> {
+    if let Ok(info) = fs::metadata(dest) {
+        if !info.is_dir() {
+            return Err(BindingsError::Io {
+                context: format!("{} exists and is not a directory", dest.display()),
+                source: ErrorKind::AlreadyExists.into(),
+            });
         }
-    } else {
-        fs::create_dir_all(&tmp_path).expect("Failed to create local tmpdir");
+        fs::remove_dir_all(dest).map_err(BindingsError::io(format!(
+            "Failed to remove {}",
+            dest.display()
+        )))?;
     }
-    tmp_path
+    fs::rename(out, dest).map_err(BindingsError::io(format!(
+        "Failed to move bindings into {}",
+        dest.display()
+    )))
 }
 
-pub fn mktemp_local(build_path: &Path) -> PathBuf {
-    let dir = tmp_local(build_path).join(Uuid::new_v4().to_string());
-    fs::create_dir(&dir).expect("Failed to create tmpdir");
-    dir
+/// Scratch directory of a build inside the local tmpdir of `build_path`. It is only created
+/// once the build's [`BuildPlan`] is executed, by a [`guard::WorkDir`] that removes it again.
+pub(crate) fn work_dir_path(build_path: &Path) -> PathBuf {
+    build_path.join("tmp").join(Uuid::new_v4().to_string())
 }
 
-pub fn cleanup_tmp_local(build_path: &Path) {
-    fs::remove_dir_all(tmp_local(build_path)).expect("Failed to remove tmpdir");
+/// Cargo target directory the library of `arch` is built in. Architectures compiled in
+/// `parallel` get a directory each, as cargo locks its target directory for a whole build.
+pub fn arch_target_dir(build_path: &Path, arch: &str, parallel: bool) -> PathBuf {
+    if parallel {
+        build_path.join("parallel").join(arch)
+    } else {
+        build_path.to_path_buf()
+    }
 }
 
-pub fn install_ndk() {
-    Command::new("cargo")
-        .arg("install")
-        .arg("cargo-ndk")
-        .spawn()
-        .expect("Failed to spawn cargo, is it installed?")
-        .wait()
-        .expect("Failed to install cargo-ndk");
+pub fn install_ndk() -> BuildStep {
+    BuildStep::command(Command::new("cargo").arg("install").arg("cargo-ndk"))
 }
 
-pub fn install_arch(arch: String) {
-    Command::new("rustup")
-        .arg("target")
-        .arg("add")
-        .arg(arch.clone())
-        .spawn()
-        .expect("Failed to spawn rustup, is it installed?")
-        .wait()
-        .unwrap_or_else(|_| panic!("Failed to install target architecture {arch}"));
+pub fn install_arch(arch: &str) -> BuildStep {
+    BuildStep::command(Command::new("rustup").arg("target").arg("add").arg(arch))
 }
 
+/// Name of the library of the package at `project_dir`, e.g. `my_circuits`
+#[deprecated(note = "use `Project::detect`, which also handles workspaces, and its `lib_name`")]
 pub fn project_name_from_toml(project_dir: &Path) -> anyhow::Result<String> {
-    let cargo_toml_path = project_dir.join("Cargo.toml");
-    let cargo_toml_content =
-        fs::read_to_string(cargo_toml_path).context("Failed to read Cargo.toml")?;
-    let cargo_toml: Value = cargo_toml_content
-        .parse::<Value>()
-        .context("Failed to parse Cargo.toml")?;
-
-    // If the `name` under [lib] section is set, using the `name` as library name.
-    // Otherwise, using the package name.
-    let project_name = cargo_toml
-        .get("lib")
-        .and_then(|lib| lib.get("name"))
-        .and_then(|lib| lib.as_str())
-        .map(|s| s.to_string())
-        .or_else(|| {
-            // '-' in the package name is replaced with '_' if we don't specify the lib name
-            // So we need to replace '-' with '_' as below
-            cargo_toml
-                .get("package")
-                .and_then(|pkg| pkg.get("name"))
-                .and_then(|pkg| pkg.as_str().map(|s| s.replace("-", "_")))
-        });
-
-    project_name.ok_or(anyhow::anyhow!("Failed to find project name in Cargo.toml"))
+    Ok(Project::detect(project_dir, None)?.lib_name)
 }
 
+/// Name of the package at `project_dir`, e.g. `my-circuits`
+#[deprecated(note = "use `Project::detect` and its `package`")]
 pub fn raw_project_name_from_toml(project_dir: &Path) -> anyhow::Result<String> {
-    let cargo_toml_path = project_dir.join("Cargo.toml");
-    let cargo_toml_content =
-        fs::read_to_string(cargo_toml_path).context("Failed to read Cargo.toml")?;
-    let cargo_toml: Value = cargo_toml_content
-        .parse::<Value>()
-        .context("Failed to parse Cargo.toml")?;
-
-    // If the `name` under [lib] section is set, using the `name` as library name.
-    // Otherwise, using the package name.
-    let project_name = cargo_toml
-        .get("package")
-        .and_then(|pkg| pkg.get("name"))
-        .and_then(|pkg| pkg.as_str().map(|s| s.to_string()));
-
-    project_name.ok_or(anyhow::anyhow!("Failed to find project name in Cargo.toml"))
+    Ok(Project::detect(project_dir, None)?.package)
 }
 
 fn get_project_dir() -> PathBuf {
@@ -189,26 +402,150 @@ fn get_project_dir() -> PathBuf {
         .unwrap_or_else(|_| PathBuf::from("."))
 }
 
-fn get_build_mode() -> Mode {
-    Mode::parse_from_str(
-        std::env::var(BUILD_MODE_ENV)
-            .unwrap_or_else(|_| Mode::Debug.as_str().to_string())
-            .as_str(),
-    )
+/// Resolves the build mode: `cli_mode` > `CONFIGURATION` env var > `mopro.toml` > debug.
+pub fn resolve_mode(cli_mode: Option<Mode>, config: &MoproConfig) -> anyhow::Result<Mode> {
+    if let Some(mode) = cli_mode {
+        return Ok(mode);
+    }
+
+    if let Ok(mode) = std::env::var(BUILD_MODE_ENV) {
+        return Mode::parse(&mode).ok_or(anyhow::anyhow!(
+            "Unsupported mode '{mode}' in {BUILD_MODE_ENV}, expected {} or a cargo profile name",
+            Mode::all_strings().join(", ")
+        ));
+    }
+
+    Ok(config.mode.clone().unwrap_or(Mode::Debug))
 }
 
-fn get_target_archs<A: Arch>() -> Vec<A> {
-    if let Ok(archs_str) = std::env::var(A::env_var_name()) {
-        if archs_str.is_empty() {
-            return vec![];
-        }
+/// Resolves the features and RUSTFLAGS of a build. Features and RUSTFLAGS given on the command
+/// line replace the ones of `mopro.toml`, the RUSTFLAGS of single targets always come from it.
+pub fn resolve_cargo_flags(cli: &CargoFlags, config: &MoproConfig) -> CargoFlags {
+    let config = &config.cargo;
+    CargoFlags {
+        features: if cli.features.is_empty() {
+            config.features.clone()
+        } else {
+            cli.features.clone()
+        },
+        no_default_features: cli.no_default_features || config.no_default_features,
+        all_features: cli.all_features || config.all_features,
+        rustflags: if cli.rustflags.is_empty() {
+            config.rustflags.clone()
+        } else {
+            cli.rustflags.clone()
+        },
+        target_rustflags: config.target_rustflags.clone(),
+    }
+}
+
+/// Resolves the number of architectures compiled in parallel:
+/// `cli_jobs` > `MOPRO_JOBS` env var > `mopro.toml` > 1.
+pub fn resolve_jobs(cli_jobs: Option<usize>, config: &MoproConfig) -> anyhow::Result<usize> {
+    let jobs = match cli_jobs {
+        Some(jobs) => jobs,
+        None => match std::env::var(BUILD_JOBS_ENV) {
+            Ok(jobs) => jobs.parse().map_err(|_| {
+                anyhow::anyhow!("Invalid {BUILD_JOBS_ENV} '{jobs}', expected a positive integer")
+            })?,
+            Err(_) => config.jobs.unwrap_or(1),
+        },
+    };
+    if jobs == 0 {
+        return Err(anyhow::anyhow!("The number of jobs must be at least 1"));
+    }
+    Ok(jobs)
+}
+
+/// Resolves whether missing targets and tools fail the build instead of being installed:
+/// `cli_offline` > `MOPRO_OFFLINE` env var > `mopro.toml` > on in CI (`CI` env var) > off.
+pub fn resolve_offline(cli_offline: Option<bool>, config: &MoproConfig) -> anyhow::Result<bool> {
+    if let Some(offline) = cli_offline {
+        return Ok(offline);
+    }
+    if let Ok(offline) = std::env::var(OFFLINE_ENV) {
+        return parse_env_bool(&offline).ok_or(anyhow::anyhow!(
+            "Invalid {OFFLINE_ENV} '{offline}', expected true or false"
+        ));
+    }
+    if let Some(offline) = config.offline {
+        return Ok(offline);
+    }
+    let in_ci = std::env::var(CI_ENV).is_ok_and(|ci| parse_env_bool(&ci) != Some(false));
+    Ok(in_ci)
+}
+
+fn parse_env_bool(value: &str) -> Option<bool> {
+    match value.to_lowercase().as_str() {
+        "1" | "true" | "yes" => Some(true),
+        "" | "0" | "false" | "no" => Some(false),
+        _ => None,
+    }
+}
 
-        archs_str.split(',').map(A::parse_from_str).collect()
+/// Resolves the target architectures of a platform:
+/// `cli_archs` > `<PLATFORM>_ARCHS` env var > `mopro.toml` > the platform's default architectures,
+/// which may be a subset of the supported ones, e.g. only the host for the desktop.
+///
+/// An empty list means that the platform should not be built.
+pub fn resolve_target_archs<Builder: PlatformBuilder>(
+    cli_archs: &[String],
+    config: &MoproConfig,
+) -> anyhow::Result<Vec<String>> {
+    let env_var_name = Builder::Arch::env_var_name();
+    let target_archs: Vec<String> = if !cli_archs.is_empty() {
+        cli_archs.to_vec()
+    } else if let Ok(archs_str) = std::env::var(env_var_name) {
+        archs_str
+            .split(',')
+            .filter(|s| !s.is_empty())
+            .map(|s| s.to_string())
+            .collect()
+    } else if let Some(archs) = config.platform(Builder::name()).archs {
+        archs
     } else {
-        // Default case: select all supported architectures if none are provided
-        A::all_strings()
+        // Default case: select the platform's default architectures if none are provided
+        Builder::Arch::default_strings()
             .iter()
-            .map(|s| A::parse_from_str(s))
+            .map(|s| s.to_string())
             .collect()
+    };
+
+    let supported = Builder::Arch::all_strings();
+    if let Some(arch) = target_archs
+        .iter()
+        .find(|arch| !supported.iter().any(|s| s.eq_ignore_ascii_case(arch)))
+    {
+        return Err(anyhow::anyhow!(
+            "Unsupported {} architecture '{arch}', expected one of: {}",
+            Builder::name(),
+            supported.join(", ")
+        ));
     }
+
+    Ok(target_archs)
+}
+
+/// Resolves the directory the bindings are written into:
+/// `cli_output` > `mopro.toml` (relative to the project directory) > the project directory.
+pub fn resolve_output_dir(
+    cli_output: Option<&Path>,
+    project_dir: &Path,
+    config: &MoproConfig,
+) -> PathBuf {
+    match (cli_output, &config.output_dir) {
+        (Some(output), _) => output.to_path_buf(),
+        (None, Some(output)) => project_dir.join(output),
+        (None, None) => project_dir.to_path_buf(),
+    }
+}
+
+/// Reads the platform parameters from its `mopro.toml` section, falling back to the defaults.
+pub fn resolve_params<Builder: PlatformBuilder>(
+    config: &MoproConfig,
+) -> anyhow::Result<Builder::Params> {
+    Builder::Params::from_platform_config(&config.platform(Builder::name())).context(format!(
+        "Invalid [{}] section in mopro.toml",
+        Builder::name()
+    ))
 }
@@ -0,0 +1,670 @@
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use std::fs;
+use std::io::ErrorKind;
+use std::path::Path;
+use std::process::Command;
+
+use super::PlatformBuilder;
+
+use super::config::{FromPlatformConfig, PlatformConfig};
+use super::constants::{
+    Arch, BuildOptions, NodeArch, NodePlatform, Platform, NODE_BINDINGS_DIR, NODE_PACKAGE_NAME,
+};
+use super::doctor::{check_command, check_rustup_targets, ToolCheck};
+use super::error::BindingsError;
+use super::manifest::ArchPath;
+use super::plan::{BuildPlan, BuildStep};
+use super::project::Project;
+use super::{arch_target_dir, install_arch, work_dir_path};
+
+/// Patch writing `index.d.ts` from the type definitions napi-rs writes while compiling the crate
+const TYPE_DEFINITIONS_PATCH: &str = "type-definitions";
+/// Patch checking that an addon exports the function Node.js registers it with
+const NAPI_ENTRY_PATCH: &str = "napi-entry";
+/// Symbol napi-rs exports from the addon, Node.js calls it when the addon is required
+const NAPI_ENTRY_SYMBOL: &[u8] = b"napi_register_module_v1";
+/// File in the work directory napi-rs writes the type definitions to, see `TYPE_DEF_TMP_PATH`
+const TYPE_DEF_FILE: &str = "type_def.tmp";
+
+#[inline]
+pub fn build() {
+    super::build_from_env::<NodePlatform>().unwrap_or_else(|e| {
+        panic!(
+            "Failed to build {} bindings: {e}",
+            NodePlatform::identifier()
+        )
+    })
+}
+
+#[derive(Hash)]
+pub struct NodeBindingsParams {
+    /// Name of the generated bindings directory
+    pub bindings_dir: String,
+    /// Name of the npm package, `require('<package_name>')`
+    pub package_name: String,
+}
+
+impl Default for NodeBindingsParams {
+    fn default() -> Self {
+        Self {
+            bindings_dir: NODE_BINDINGS_DIR.to_string(),
+            package_name: NODE_PACKAGE_NAME.to_string(),
+        }
+    }
+}
+
+impl FromPlatformConfig for NodeBindingsParams {
+    fn from_platform_config(config: &PlatformConfig) -> anyhow::Result<Self> {
+        config.check_params(&["package_name"])?;
+        let default = Self::default();
+        Ok(Self {
+            bindings_dir: config.bindings_dir.clone().unwrap_or(default.bindings_dir),
+            package_name: config
+                .string_param("package_name")?
+                .unwrap_or(default.package_name),
+        })
+    }
+}
+
+impl PlatformBuilder for NodePlatform {
+    type Arch = NodeArch;
+    type Params = NodeBindingsParams;
+
+    /// Lays the addons of the crate, which exports them with napi-rs, out as an npm package
+    ///
+    /// ```text
+    /// MoproNodeBindings/
+    /// ├── package.json
+    /// ├── index.js                        (loads the addon of the host)
+    /// ├── index.d.ts
+    /// └── <name>.<platform>.node, e.g. my_circuits.linux-x64-gnu.node
+    /// ```
+    fn plan(
+        options: &BuildOptions,
+        project: &Project,
+        output_dir: &Path,
+        target_archs: Vec<Self::Arch>,
+        _fresh_archs: &[Self::Arch],
+        params: &Self::Params,
+    ) -> Result<BuildPlan, BindingsError> {
+        project.require_crate_types(&["cdylib"])?;
+        let project_dir = &project.manifest_dir;
+        let lib_name = &project.lib_name;
+
+        // Paths for the generated files
+        let work_dir = work_dir_path(&project_dir.join("build"));
+        let bindings_out = work_dir.join(&params.bindings_dir);
+        let bindings_dest = output_dir.join(&params.bindings_dir);
+
+        let mut plan = BuildPlan::new(Self::name(), options, project, output_dir, &bindings_dest);
+        plan.work_dir = Some(work_dir.clone());
+        plan.steps.push(BuildStep::CreateDir {
+            path: bindings_out.clone(),
+        });
+
+        let mode = &options.mode;
+        let parallel = options.jobs > 1 && target_archs.len() > 1;
+        let out_lib_path = |arch: &NodeArch| {
+            arch_target_dir(&project.target_dir, arch.as_str(), parallel)
+                .join(arch.as_str())
+                .join(mode.as_str())
+                .join(arch.dylib_file_name(lib_name))
+        };
+
+        // Every architecture is built again, as the type definitions are only written while
+        // the crate compiles
+        if !options.offline {
+            for arch in &target_archs {
+                plan.steps.push(install_arch(arch.as_str()));
+            }
+        }
+        for (index, arch) in target_archs.iter().enumerate() {
+            let mut build_cmd = build_for_arch(
+                *arch,
+                project_dir,
+                &arch_target_dir(&project.target_dir, arch.as_str(), parallel),
+                options,
+            );
+            // The definitions are the same for every architecture, so they are taken from
+            // the first one. napi-build rebuilds the crate when the path changes.
+            if index == 0 {
+                build_cmd.env("TYPE_DEF_TMP_PATH", work_dir.join(TYPE_DEF_FILE));
+            }
+            plan.steps
+                .push(BuildStep::arch_command(arch.as_str(), &build_cmd));
+        }
+        for arch in &target_archs {
+            let addon_path = bindings_out.join(arch.addon_file_name(lib_name));
+            plan.steps.push(BuildStep::Copy {
+                from: out_lib_path(arch),
+                to: addon_path.clone(),
+            });
+            plan.steps
+                .push(BuildStep::patch(NAPI_ENTRY_PATCH, addon_path));
+        }
+
+        plan.steps.push(BuildStep::WriteFile {
+            path: bindings_out.join("index.js"),
+            contents: index_js(lib_name),
+        });
+        plan.steps
+            .push(BuildStep::patch(TYPE_DEFINITIONS_PATCH, &bindings_out));
+        plan.steps.push(BuildStep::WriteFile {
+            path: bindings_out.join("package.json"),
+            contents: package_json(project, &params.package_name),
+        });
+        plan.steps.push(BuildStep::Install {
+            from: bindings_out,
+            to: bindings_dest.clone(),
+        });
+        plan.steps.push(BuildStep::WriteManifest {
+            dir: bindings_dest,
+            uniffi_version: None,
+            arch_paths: target_archs
+                .iter()
+                .map(|arch| ArchPath::new(arch.addon_file_name(lib_name), &[arch.as_str()]))
+                .collect(),
+        });
+
+        Ok(plan)
+    }
+
+    fn apply_patch(
+        plan: &BuildPlan,
+        name: &str,
+        path: &Path,
+        _params: &Self::Params,
+    ) -> Result<(), BindingsError> {
+        match name {
+            NAPI_ENTRY_PATCH => return check_napi_entry(&plan.project, path),
+            TYPE_DEFINITIONS_PATCH => {}
+            _ => return Err(BindingsError::unknown_patch(Self::identifier(), name)),
+        }
+        let type_def_path = plan
+            .work_dir
+            .as_ref()
+            .map(|work_dir| work_dir.join(TYPE_DEF_FILE))
+            .unwrap_or_default();
+        // Crates exporting the addon without napi-rs write no type definitions
+        let type_defs = match fs::read_to_string(&type_def_path) {
+            Ok(type_defs) => type_defs,
+            Err(e) if e.kind() == ErrorKind::NotFound => String::new(),
+            Err(e) => {
+                return Err(BindingsError::io(format!(
+                    "Failed to read {}",
+                    type_def_path.display()
+                ))(e))
+            }
+        };
+        let index_d_ts = path.join("index.d.ts");
+        fs::write(&index_d_ts, type_declarations(&type_defs)?).map_err(BindingsError::io(format!(
+            "Failed to write {}",
+            index_d_ts.display()
+        )))
+    }
+
+    fn toolchain_checks(target_archs: &[Self::Arch]) -> Vec<ToolCheck> {
+        let targets: Vec<&str> = target_archs.iter().map(|arch| arch.as_str()).collect();
+        let mut checks = check_rustup_targets(None, &targets);
+        checks.push(check_command(
+            "node",
+            "node",
+            &["--version"],
+            "Install Node.js from https://nodejs.org",
+        ));
+        checks
+    }
+}
+
+/// Checks that the addon at `path` exports the N-API entry point. A crate exporting its
+/// functions with uniffi only builds a plain library, which Node.js fails to load.
+fn check_napi_entry(project: &Project, path: &Path) -> Result<(), BindingsError> {
+    let addon = fs::read(path).map_err(BindingsError::io(format!(
+        "Failed to read {}",
+        path.display()
+    )))?;
+    // The name is in the export table of every object format, with a leading `_` on macOS
+    if addon
+        .windows(NAPI_ENTRY_SYMBOL.len())
+        .any(|window| window == NAPI_ENTRY_SYMBOL)
+    {
+        return Ok(());
+    }
+    Err(BindingsError::MissingNapiEntry {
+        package: project.package.clone(),
+        addon: path.to_path_buf(),
+    })
+}
+
+/// Command building the addon for `arch`.
+fn build_for_arch(
+    arch: NodeArch,
+    project_dir: &Path,
+    target_dir: &Path,
+    options: &BuildOptions,
+) -> Command {
+    let arch_str = arch.as_str();
+    let mut build_cmd = Command::new("cargo");
+    build_cmd
+        .arg("build")
+        .arg("--lib")
+        .args(options.mode.cargo_args())
+        .args(options.cargo.feature_args());
+    if let Some(rustflags) = options.cargo.rustflags(arch_str) {
+        build_cmd.env("RUSTFLAGS", rustflags);
+    }
+    build_cmd
+        .current_dir(project_dir)
+        .env("CARGO_BUILD_TARGET_DIR", target_dir)
+        .env("CARGO_BUILD_TARGET", arch_str);
+    build_cmd
+}
+
+/// `index.js` requiring the addon built for the platform of the running Node.js process.
+fn index_js(lib_name: &str) -> String {
+    let addons: Vec<String> = NodeArch::all_strings()
+        .into_iter()
+        .flat_map(|arch_str| NodeArch::parse_from_str(arch_str).map(|arch| (arch_str, arch)))
+        .map(|(arch_str, arch)| {
+            let (platform, node_arch) = arch.node_host();
+            format!(
+                "  '{platform}-{node_arch}': ['{}', '{arch_str}'],\n",
+                arch.addon_file_name(lib_name)
+            )
+        })
+        .collect();
+    format!(
+        "// Loads the addon built for the platform of the running process\n\
+         const {{ existsSync }} = require('fs')\n\
+         const {{ join }} = require('path')\n\
+         \n\
+         const addons = {{\n{}}}\n\
+         \n\
+         const host = `${{process.platform}}-${{process.arch}}`\n\
+         if (!addons[host]) {{\n  \
+           throw new Error(`Unsupported platform: ${{host}}`)\n\
+         }}\n\
+         const [addon, target] = addons[host]\n\
+         const path = join(__dirname, addon)\n\
+         if (!existsSync(path)) {{\n  \
+           throw new Error(`No addon for ${{host}}, build it with: mopro build node --arch ${{target}}`)\n\
+         }}\n\
+         module.exports = require(path)\n",
+        addons.concat()
+    )
+}
+
+fn package_json(project: &Project, package_name: &str) -> String {
+    let package = serde_json::json!({
+        "name": package_name,
+        "version": project.version,
+        "description": format!("Mopro bindings of {}", project.package),
+        "main": "index.js",
+        "types": "index.d.ts",
+        "files": ["index.js", "index.d.ts", "*.node"],
+    });
+    let mut contents = serde_json::to_string_pretty(&package).unwrap_or_default();
+    contents.push('\n');
+    contents
+}
+
+/// A line napi-rs writes to `TYPE_DEF_TMP_PATH` for an export of the crate, prefixed with
+/// the name of the package by recent versions, e.g.
+/// `my-circuits:{"kind": "fn", "name": "greet", "js_doc": "", "def": "function greet(name: string): string"}`
+#[derive(Deserialize)]
+struct TypeDef {
+    kind: String,
+    name: String,
+    #[serde(default)]
+    js_doc: String,
+    def: String,
+}
+
+/// Declarations of `index.d.ts` from the type definitions written by napi-rs, the way the
+/// napi-rs CLI writes them: functions, types and constants are exported as they are, structs
+/// become classes with the methods of their impls.
+fn type_declarations(type_defs: &str) -> Result<String, BindingsError> {
+    let mut declarations = Vec::new();
+    let mut classes: BTreeMap<String, (String, Vec<String>)> = BTreeMap::new();
+    for line in type_defs.lines().filter(|line| !line.trim().is_empty()) {
+        let json = line.find('{').map(|start| &line[start..]).unwrap_or(line);
+        let type_def: TypeDef = serde_json::from_str(json).map_err(|e| {
+            BindingsError::bindgen(format!("Invalid napi-rs type definition '{line}': {e}"))
+        })?;
+        let members = || indent(&type_def.def);
+        match type_def.kind.as_str() {
+            "struct" => {
+                let class = classes.entry(type_def.name.clone()).or_default();
+                class.0 = type_def.js_doc.clone();
+                class.1.insert(0, members());
+            }
+            "impl" => classes
+                .entry(type_def.name.clone())
+                .or_default()
+                .1
+                .push(members()),
+            "interface" => declarations.push(format!(
+                "{}export interface {} {{\n{}\n}}",
+                type_def.js_doc,
+                type_def.name,
+                members()
+            )),
+            "enum" => declarations.push(format!(
+                "{}export declare const enum {} {{\n{}\n}}",
+                type_def.js_doc,
+                type_def.name,
+                members()
+            )),
+            _ => declarations.push(format!(
+                "{}export declare {}",
+                type_def.js_doc, type_def.def
+            )),
+        }
+    }
+    for (name, (js_doc, members)) in classes {
+        let members: Vec<String> = members.into_iter().filter(|m| !m.is_empty()).collect();
+        declarations.push(format!(
+            "{js_doc}export declare class {name} {{\n{}\n}}",
+            members.join("\n")
+        ));
+    }
+    if declarations.is_empty() {
+        declarations.push("export {}".to_string());
+    }
+    Ok(format!(
+        "/* Generated by mopro from the napi-rs type definitions */\n\n{}\n",
+        declarations.join("\n\n")
+    ))
+}
+
+fn indent(def: &str) -> String {
+    def.lines()
+        .filter(|line| !line.trim().is_empty())
+        .map(|line| format!("  {}", line.trim()))
+        .collect::<Vec<String>>()
+        .join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bindings::constants::{BuildContext, Mode};
+    use crate::bindings::observer::NoopObserver;
+    use crate::bindings::project::test_project;
+    #[cfg(target_os = "linux")]
+    use crate::bindings::project::TestProject;
+    use crate::bindings::runner::RecordingRunner;
+
+    #[test]
+    fn test_package_layout() {
+        let project = test_project("my-circuits", &["lib", "cdylib"]);
+        let project_dir = project.manifest_dir.clone();
+
+        let runner = RecordingRunner::new();
+        let bindings_dir = NodePlatform::build(
+            &BuildOptions::new(Mode::Release),
+            &project,
+            &project_dir,
+            vec![NodeArch::X8664LinuxGnu, NodeArch::Aarch64Darwin],
+            &[],
+            NodeBindingsParams::default(),
+            BuildContext::new(&runner, &NoopObserver),
+        )
+        .unwrap();
+        assert_eq!(bindings_dir, project_dir.join(NODE_BINDINGS_DIR));
+        let commands = runner.commands();
+        let lines: Vec<String> = commands.iter().map(|cmd| cmd.command()).collect();
+        assert_eq!(
+            lines,
+            [
+                "rustup target add x86_64-unknown-linux-gnu",
+                "rustup target add aarch64-apple-darwin",
+                "cargo build --lib --release",
+                "cargo build --lib --release",
+            ]
+        );
+        // The type definitions are written by the first build only
+        let type_def_paths: Vec<bool> = commands[2..]
+            .iter()
+            .map(|command| {
+                command
+                    .envs
+                    .iter()
+                    .any(|(key, _)| key == "TYPE_DEF_TMP_PATH")
+            })
+            .collect();
+        assert_eq!(type_def_paths, [true, false]);
+
+        let plan = NodePlatform::plan(
+            &BuildOptions::new(Mode::Release),
+            &project,
+            &project_dir,
+            vec![NodeArch::X8664LinuxGnu, NodeArch::Aarch64Darwin],
+            &[],
+            &NodeBindingsParams::default(),
+        )
+        .unwrap();
+        let copies = plan.copies();
+        assert!(copies[0]
+            .0
+            .ends_with("x86_64-unknown-linux-gnu/release/libmy_circuits.so"));
+        assert!(copies[0]
+            .1
+            .ends_with("MoproNodeBindings/my_circuits.linux-x64-gnu.node"));
+        assert!(copies[1]
+            .0
+            .ends_with("aarch64-apple-darwin/release/libmy_circuits.dylib"));
+        assert!(copies[1]
+            .1
+            .ends_with("MoproNodeBindings/my_circuits.darwin-arm64.node"));
+
+        let index_js = plan.written_file("index.js").unwrap();
+        assert!(index_js.contains(
+            "  'linux-x64': ['my_circuits.linux-x64-gnu.node', 'x86_64-unknown-linux-gnu'],\n"
+        ));
+        assert!(index_js.contains(
+            "  'win32-x64': ['my_circuits.win32-x64-msvc.node', 'x86_64-pc-windows-msvc'],\n"
+        ));
+    }
+
+    #[test]
+    fn test_type_declarations() {
+        let type_defs = concat!(
+            "my-circuits:{\"kind\": \"fn\", \"name\": \"greet\", \"js_doc\": \"/** Greets `name` */\\n\", ",
+            "\"def\": \"function greet(name: string): string\"}\n",
+            "my-circuits:{\"kind\": \"impl\", \"name\": \"Prover\", \"js_doc\": \"\", ",
+            "\"def\": \"prove(input: Buffer): Buffer\"}\n",
+            "my-circuits:{\"kind\": \"struct\", \"name\": \"Prover\", \"js_doc\": \"\", ",
+            "\"def\": \"constructor(zkey: string)\"}\n",
+            "{\"kind\": \"interface\", \"name\": \"Proof\", \"def\": \"a: Array<string>\\nb: Array<string>\"}\n",
+        );
+        assert_eq!(
+            type_declarations(type_defs).unwrap(),
+            "/* Generated by mopro from the napi-rs type definitions */\n\n\
+             /** Greets `name` */\n\
+             export declare function greet(name: string): string\n\n\
+             export interface Proof {\n  a: Array<string>\n  b: Array<string>\n}\n\n\
+             export declare class Prover {\n  constructor(zkey: string)\n  prove(input: Buffer): Buffer\n}\n"
+        );
+        assert_eq!(
+            type_declarations("").unwrap(),
+            "/* Generated by mopro from the napi-rs type definitions */\n\nexport {}\n"
+        );
+        assert!(matches!(
+            type_declarations("my-circuits:{"),
+            Err(BindingsError::Bindgen { .. })
+        ));
+    }
+
+    #[test]
+    fn test_napi_entry_check() {
+        let project = test_project("my-circuits", &["lib", "cdylib"]);
+        let project_dir = project.manifest_dir.clone();
+        let plan = NodePlatform::plan(
+            &BuildOptions::new(Mode::Debug),
+            &project,
+            &project_dir,
+            vec![NodeArch::X8664LinuxGnu],
+            &[],
+            &NodeBindingsParams::default(),
+        )
+        .unwrap();
+        let params = NodeBindingsParams::default();
+        let addon = project_dir.join("my_circuits.linux-x64-gnu.node");
+
+        // A library built for uniffi has no entry point for Node.js
+        fs::write(&addon, b"\x7fELF uniffi_my_circuits_fn_func_greet").unwrap();
+        assert!(matches!(
+            NodePlatform::apply_patch(&plan, NAPI_ENTRY_PATCH, &addon, &params),
+            Err(BindingsError::MissingNapiEntry { package, .. }) if package == "my-circuits"
+        ));
+        fs::write(&addon, b"\x7fELF _napi_register_module_v1").unwrap();
+        assert!(NodePlatform::apply_patch(&plan, NAPI_ENTRY_PATCH, &addon, &params).is_ok());
+        assert!(matches!(
+            NodePlatform::apply_patch(&plan, "kotlin-package", &addon, &params),
+            Err(BindingsError::UnknownPatch { .. })
+        ));
+    }
+
+    /// A crate exporting `greet(name: string): string` as an N-API addon. It declares the
+    /// N-API functions itself, which are resolved when Node.js loads it, and writes the type
+    /// definition the way napi-rs does.
+    #[cfg(target_os = "linux")]
+    fn napi_test_project(name: &str) -> TestProject {
+        TestProject::new(&[
+            (
+                "Cargo.toml",
+                &format!(
+                    "[package]\nname = \"{name}\"\nversion = \"0.1.0\"\nedition = \"2021\"\n\n\
+                     [lib]\ncrate-type = [\"cdylib\"]\n\n[workspace]\n"
+                ),
+            ),
+            (
+                "build.rs",
+                &format!(
+                    "fn main() {{\n    \
+                         println!(\"cargo:rerun-if-env-changed=TYPE_DEF_TMP_PATH\");\n    \
+                         if let Ok(path) = std::env::var(\"TYPE_DEF_TMP_PATH\") {{\n        \
+                             std::fs::write(path, {:?}).unwrap();\n    \
+                         }}\n\
+                     }}\n",
+                    format!(
+                        "{name}:{{\"kind\": \"fn\", \"name\": \"greet\", \"js_doc\": \"\", \
+                         \"def\": \"function greet(name: string): string\"}}\n"
+                    )
+                ),
+            ),
+            (
+                "src/lib.rs",
+                r#"use std::ffi::{c_char, c_void};
+use std::ptr;
+
+type Env = *mut c_void;
+type Value = *mut c_void;
+type CallbackInfo = *mut c_void;
+type Callback = unsafe extern "C" fn(Env, CallbackInfo) -> Value;
+
+extern "C" {
+    fn napi_get_cb_info(
+        env: Env,
+        info: CallbackInfo,
+        argc: *mut usize,
+        argv: *mut Value,
+        this: *mut Value,
+        data: *mut *mut c_void,
+    ) -> i32;
+    fn napi_get_value_string_utf8(
+        env: Env,
+        value: Value,
+        buf: *mut c_char,
+        bufsize: usize,
+        result: *mut usize,
+    ) -> i32;
+    fn napi_create_string_utf8(env: Env, string: *const c_char, length: usize, result: *mut Value)
+        -> i32;
+    fn napi_create_function(
+        env: Env,
+        name: *const c_char,
+        length: usize,
+        cb: Callback,
+        data: *mut c_void,
+        result: *mut Value,
+    ) -> i32;
+    fn napi_set_named_property(env: Env, object: Value, name: *const c_char, value: Value) -> i32;
+}
+
+unsafe extern "C" fn greet(env: Env, info: CallbackInfo) -> Value {
+    let mut argc = 1;
+    let mut arg = ptr::null_mut();
+    napi_get_cb_info(env, info, &mut argc, &mut arg, ptr::null_mut(), ptr::null_mut());
+    let mut name = [0u8; 256];
+    let mut length = 0;
+    napi_get_value_string_utf8(env, arg, name.as_mut_ptr().cast(), name.len(), &mut length);
+    let greeting = format!("Hi Mopro, {}!", String::from_utf8_lossy(&name[..length]));
+    let mut result = ptr::null_mut();
+    napi_create_string_utf8(env, greeting.as_ptr().cast(), greeting.len(), &mut result);
+    result
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn napi_register_module_v1(env: Env, exports: Value) -> Value {
+    let mut function = ptr::null_mut();
+    napi_create_function(env, c"greet".as_ptr(), 5, greet, ptr::null_mut(), &mut function);
+    napi_set_named_property(env, exports, c"greet".as_ptr(), function);
+    exports
+}
+"#,
+            ),
+        ])
+    }
+
+    /// Builds the addon of the host and calls it from Node.js, if `node` is installed.
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn test_require_addon() {
+        use crate::bindings::doctor::command_output;
+        use crate::bindings::runner::SystemRunner;
+
+        if command_output("node", &["--version"]).is_err() {
+            eprintln!("node is not installed, skipping");
+            return;
+        }
+        let host = NodeArch::parse_from_str(NodeArch::default_strings()[0]).unwrap();
+        let project = napi_test_project("node-smoke");
+        let project_dir = project.manifest_dir.clone();
+
+        let runner = SystemRunner::new();
+        let bindings_dir = NodePlatform::build(
+            // The host's target is installed with the toolchain
+            &BuildOptions {
+                offline: true,
+                ..BuildOptions::new(Mode::Debug)
+            },
+            &project,
+            &project_dir,
+            vec![host],
+            &[],
+            NodeBindingsParams::default(),
+            BuildContext::new(&runner, &NoopObserver),
+        )
+        .unwrap();
+        let output = Command::new("node")
+            .arg("-e")
+            .arg("console.log(require(process.argv[1]).greet('Node'))")
+            .arg(&bindings_dir)
+            .output()
+            .unwrap();
+        assert!(
+            output.status.success(),
+            "{}",
+            String::from_utf8_lossy(&output.stderr)
+        );
+        assert_eq!(
+            String::from_utf8_lossy(&output.stdout).trim(),
+            "Hi Mopro, Node!"
+        );
+        let index_d_ts = fs::read_to_string(bindings_dir.join("index.d.ts")).unwrap();
+        assert!(index_d_ts.contains("export declare function greet(name: string): string\n"));
+    }
+}
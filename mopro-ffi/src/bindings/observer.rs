@@ -0,0 +1,163 @@
+//! Progress of a bindings build, reported to a [`BuildObserver`] while a
+//! [`BuildPlan`](super::plan::BuildPlan) is executed.
+//!
+//! [`ConsoleObserver`] prints the progress for people, [`JsonLinesObserver`] writes one JSON object
+//! per event for tools, e.g. with `mopro build --message-format json`:
+//!
+//! ```json
+//! {"event":"step_started","index":2,"step":{"kind":"command","arch":"aarch64-apple-ios",...}}
+//! {"event":"arch_finished","arch":"aarch64-apple-ios","duration_secs":41.2}
+//! ```
+
+use serde::{Serialize, Serializer};
+use std::io::Write;
+use std::path::Path;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
+use std::time::Duration;
+
+use super::plan::{BindingsLanguage, BuildStep};
+
+/// Receives the events of a build. Events of architectures compiled in parallel arrive from
+/// several threads.
+pub trait BuildObserver: Sync {
+    fn on_event(&self, event: &BuildEvent<'_>);
+}
+
+/// Something that happened during a build.
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "event", rename_all = "snake_case")]
+pub enum BuildEvent<'a> {
+    /// The plan of a build started executing
+    BuildStarted {
+        platform: &'a str,
+        mode: &'a str,
+        /// Number of steps of the plan
+        steps: usize,
+    },
+    /// A step of the plan started. Steps skipped by a dry run are not reported.
+    StepStarted {
+        index: usize,
+        step: &'a BuildStep,
+    },
+    StepFinished {
+        index: usize,
+        #[serde(rename = "duration_secs", serialize_with = "secs")]
+        duration: Duration,
+    },
+    /// The library started compiling for an architecture
+    ArchStarted {
+        arch: &'a str,
+    },
+    /// The library was compiled for an architecture
+    ArchFinished {
+        arch: &'a str,
+        #[serde(rename = "duration_secs", serialize_with = "secs")]
+        duration: Duration,
+    },
+    /// uniffi is generating the foreign-language bindings of the library
+    GeneratingBindings {
+        language: BindingsLanguage,
+        library: &'a Path,
+    },
+    /// The packaged bindings are being moved into the output directory
+    Packaging {
+        from: &'a Path,
+        to: &'a Path,
+    },
+    /// A build output was written
+    ArtifactWritten {
+        path: &'a Path,
+    },
+    /// Something worth telling, e.g. that a tool was installed already
+    Message {
+        message: &'a str,
+    },
+    BuildFinished {
+        bindings_dir: &'a Path,
+        #[serde(rename = "duration_secs", serialize_with = "secs")]
+        duration: Duration,
+    },
+    BuildFailed {
+        error: String,
+    },
+}
+
+fn secs<S: Serializer>(duration: &Duration, serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.serialize_f64(duration.as_secs_f64())
+}
+
+/// Prints the progress of a build.
+#[derive(Debug, Default)]
+pub struct ConsoleObserver {
+    /// Number of steps of the running build
+    steps: AtomicUsize,
+}
+
+impl BuildObserver for ConsoleObserver {
+    fn on_event(&self, event: &BuildEvent<'_>) {
+        match event {
+            BuildEvent::BuildStarted {
+                platform,
+                mode,
+                steps,
+            } => {
+                self.steps.store(*steps, Ordering::Relaxed);
+                println!("Building {platform} bindings ({mode})");
+            }
+            BuildEvent::StepStarted { index, step } => {
+                println!(
+                    "[{}/{}] {step}",
+                    index + 1,
+                    self.steps.load(Ordering::Relaxed)
+                )
+            }
+            BuildEvent::ArchFinished { arch, duration } => {
+                println!("[{arch}] compiled in {:.1}s", duration.as_secs_f64())
+            }
+            BuildEvent::ArtifactWritten { path } => println!("Wrote {}", path.display()),
+            BuildEvent::Message { message } => println!("{message}"),
+            BuildEvent::BuildFinished { duration, .. } => {
+                println!("Finished in {:.1}s", duration.as_secs_f64())
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Writes every event as a line of JSON.
+#[derive(Debug)]
+pub struct JsonLinesObserver<W> {
+    out: Mutex<W>,
+}
+
+impl<W: Write + Send> JsonLinesObserver<W> {
+    pub fn new(out: W) -> Self {
+        Self {
+            out: Mutex::new(out),
+        }
+    }
+
+    pub fn into_inner(self) -> W {
+        self.out.into_inner().unwrap()
+    }
+}
+
+impl<W: Write + Send> BuildObserver for JsonLinesObserver<W> {
+    fn on_event(&self, event: &BuildEvent<'_>) {
+        let mut out = self.out.lock().unwrap();
+        // Progress reporting does not fail a build
+        let _ = serde_json::to_writer(&mut *out, event)
+            .map_err(std::io::Error::from)
+            .and_then(|()| writeln!(out))
+            .and_then(|()| out.flush());
+    }
+}
+
+/// Ignores the events.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct NoopObserver;
+
+impl BuildObserver for NoopObserver {
+    fn on_event(&self, _event: &BuildEvent<'_>) {}
+}
@@ -0,0 +1,930 @@
+//! Build plans: the steps a [`PlatformBuilder`](super::PlatformBuilder) takes to build the
+//! bindings, computed before anything is run.
+//!
+//! A builder first computes the [`BuildPlan`] of a build from the project and the build
+//! settings, without side effects, and then executes it. Plans serialize to JSON so they
+//! can be reviewed before a build, e.g. with `mopro build --plan --json`:
+//!
+//! ```json
+//! {
+//!   "platform": "ios",
+//!   "mode": "release",
+//!   "arch_groups": [{ "name": "simulator", "archs": ["aarch64-apple-ios-sim"], "library": "..." }],
+//!   "steps": [
+//!     { "kind": "command", "arch": "aarch64-apple-ios-sim", "command": { "program": "cargo", ... } },
+//!     { "kind": "generate_bindings", "language": "swift", "library": "...", "out_dir": "..." },
+//!     ...
+//!   ],
+//!   "bindings_dir": "/path/to/project/MoproiOSBindings",
+//!   ...
+//! }
+//! ```
+
+use serde::Serialize;
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::sync::Mutex;
+use std::thread;
+use std::time::Instant;
+
+use super::constants::{BuildContext, BuildOptions};
+use super::error::BindingsError;
+use super::guard::{DestinationLock, WorkDir};
+use super::manifest::{ArchPath, Manifest, MANIFEST_FILE};
+use super::move_into_place;
+use super::observer::{BuildEvent, BuildObserver};
+use super::project::Project;
+use super::runner::{Cancellation, CommandLine};
+
+/// The steps of a bindings build, in order.
+#[derive(Debug, Clone, Serialize)]
+pub struct BuildPlan {
+    /// Name of the platform, e.g. `ios`
+    pub platform: String,
+    pub mode: String,
+    /// Number of architectures compiled in parallel
+    pub jobs: usize,
+    /// Missing targets and tools are not installed, so the plan has no install steps
+    pub offline: bool,
+    /// The package whose bindings are built
+    pub project: Project,
+    pub output_dir: PathBuf,
+    /// Scratch directory of the build, created before the steps and removed after them, also
+    /// when a step fails
+    pub work_dir: Option<PathBuf>,
+    /// Scratch directories of the plans added with [`BuildPlan::include`], removed together
+    /// with `work_dir`
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub included_work_dirs: Vec<PathBuf>,
+    /// Architectures that are combined into one library, e.g. the device and the simulator
+    /// slices of an XCFramework
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub arch_groups: Vec<ArchGroup>,
+    pub steps: Vec<BuildStep>,
+    /// Directory the bindings are written to
+    pub bindings_dir: PathBuf,
+    /// The bindings of the previous build are up to date, so there is nothing to do
+    pub up_to_date: bool,
+}
+
+/// Architectures built into a single universal library.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ArchGroup {
+    /// Kind of the group, `device` or `simulator`
+    pub name: String,
+    pub archs: Vec<String>,
+    /// Universal library the libraries of the architectures are combined into
+    pub library: PathBuf,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum BuildStep {
+    /// Runs an external tool, for a single architecture if `arch` is set. Consecutive commands
+    /// of different architectures run in parallel if the plan has more than one job.
+    Command {
+        #[serde(skip_serializing_if = "Option::is_none")]
+        arch: Option<String>,
+        command: CommandLine,
+    },
+    /// Runs `install` if `check` cannot find `tool`
+    InstallTool {
+        tool: String,
+        check: CommandLine,
+        install: CommandLine,
+    },
+    CreateDir {
+        path: PathBuf,
+    },
+    /// Copies the file `from` to `to`, or the files of the directory `from` into the directory `to`
+    Copy {
+        from: PathBuf,
+        to: PathBuf,
+    },
+    Rename {
+        from: PathBuf,
+        to: PathBuf,
+    },
+    WriteFile {
+        path: PathBuf,
+        contents: String,
+    },
+    /// Generates the uniffi bindings of the dynamic library `library` into `out_dir`
+    GenerateBindings {
+        language: BindingsLanguage,
+        library: PathBuf,
+        out_dir: PathBuf,
+    },
+    /// Edit of the generated files at `path` specific to the builder, see
+    /// [`PlatformBuilder::apply_patch`](super::PlatformBuilder::apply_patch)
+    Patch {
+        name: String,
+        path: PathBuf,
+    },
+    /// Replaces the bindings at `to` with the directory `from`
+    Install {
+        from: PathBuf,
+        to: PathBuf,
+    },
+    /// Writes the [`Manifest`] of the bindings in `dir`
+    WriteManifest {
+        dir: PathBuf,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        uniffi_version: Option<String>,
+        /// Files and directories of the bindings built for specific architectures
+        #[serde(skip_serializing_if = "Vec::is_empty")]
+        arch_paths: Vec<ArchPath>,
+    },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BindingsLanguage {
+    Swift,
+    Kotlin,
+    Python,
+}
+
+impl BuildPlan {
+    /// An empty plan of a build writing the bindings into `bindings_dir`.
+    pub fn new(
+        platform: &str,
+        options: &BuildOptions,
+        project: &Project,
+        output_dir: &Path,
+        bindings_dir: &Path,
+    ) -> Self {
+        Self {
+            platform: platform.to_string(),
+            mode: options.mode.as_str().to_string(),
+            jobs: options.jobs,
+            offline: options.offline,
+            project: project.clone(),
+            output_dir: output_dir.to_path_buf(),
+            work_dir: None,
+            included_work_dirs: Vec::new(),
+            arch_groups: Vec::new(),
+            steps: Vec::new(),
+            bindings_dir: bindings_dir.to_path_buf(),
+            up_to_date: false,
+        }
+    }
+
+    /// The plan of a build whose bindings at `bindings_dir` are up to date.
+    pub fn up_to_date(
+        platform: &str,
+        options: &BuildOptions,
+        project: &Project,
+        output_dir: &Path,
+        bindings_dir: &Path,
+    ) -> Self {
+        Self {
+            up_to_date: true,
+            ..Self::new(platform, options, project, output_dir, bindings_dir)
+        }
+    }
+
+    /// Adds the steps of `plan`, the plan of another builder whose outputs this build packages.
+    pub fn include(&mut self, plan: BuildPlan) {
+        self.steps.extend(plan.steps);
+        self.arch_groups.extend(plan.arch_groups);
+        self.included_work_dirs.extend(plan.work_dir);
+        self.included_work_dirs.extend(plan.included_work_dirs);
+    }
+
+    /// Runs the steps of the plan and returns the bindings directory.
+    ///
+    /// Commands are run by the runner of `context`. The other steps work on the outputs of the
+    /// commands, so they are skipped if the runner does not execute them. `patch` applies the
+    /// [`BuildStep::Patch`] steps. The progress is reported to the observer of `context`.
+    ///
+    /// The bindings directory is locked while the steps run, so a build waits for another one
+    /// writing the same bindings.
+    pub fn execute(
+        &self,
+        context: BuildContext<'_>,
+        patch: impl Fn(&str, &Path) -> Result<(), BindingsError>,
+    ) -> Result<PathBuf, BindingsError> {
+        let observer = context.observer;
+        observer.on_event(&BuildEvent::BuildStarted {
+            platform: &self.platform,
+            mode: &self.mode,
+            steps: self.steps.len(),
+        });
+        let start = Instant::now();
+        match self.execute_steps(context, patch) {
+            Ok(()) => {
+                observer.on_event(&BuildEvent::BuildFinished {
+                    bindings_dir: &self.bindings_dir,
+                    duration: start.elapsed(),
+                });
+                Ok(self.bindings_dir.clone())
+            }
+            Err(e) => {
+                observer.on_event(&BuildEvent::BuildFailed {
+                    error: e.to_string(),
+                });
+                Err(e)
+            }
+        }
+    }
+
+    fn execute_steps(
+        &self,
+        context: BuildContext<'_>,
+        patch: impl Fn(&str, &Path) -> Result<(), BindingsError>,
+    ) -> Result<(), BindingsError> {
+        let dry_run = context.runner.is_dry_run();
+        // The guards are released when the build ends, also when a step fails
+        let (_lock, _work_dirs) = if dry_run {
+            (None, Vec::new())
+        } else {
+            let lock = DestinationLock::acquire(&self.bindings_dir, context.observer)?;
+            let work_dirs = self
+                .work_dir
+                .iter()
+                .chain(&self.included_work_dirs)
+                .map(|path| WorkDir::create(path))
+                .collect::<Result<Vec<WorkDir>, BindingsError>>()?;
+            (Some(lock), work_dirs)
+        };
+
+        let mut steps = self.steps.iter().enumerate().peekable();
+        while let Some((index, step)) = steps.next() {
+            if let BuildStep::Command {
+                arch: Some(arch),
+                command,
+            } = step
+            {
+                let mut jobs = ArchJobs::default();
+                jobs.push(arch, index, step, command);
+                while let Some(&(
+                    index,
+                    step @ BuildStep::Command {
+                        arch: Some(arch),
+                        command,
+                    },
+                )) = steps.peek()
+                {
+                    jobs.push(arch, index, step, command);
+                    steps.next();
+                }
+                jobs.run(context, self.jobs)?;
+                continue;
+            }
+            // The other steps work on the outputs of the commands
+            let runs_tool = matches!(
+                step,
+                BuildStep::Command { .. } | BuildStep::InstallTool { .. }
+            );
+            if dry_run && !runs_tool {
+                continue;
+            }
+            observe_step(context.observer, index, step, || {
+                self.execute_step(step, context, &patch)
+            })?;
+        }
+        Ok(())
+    }
+
+    /// Runs a step that is not compiling for an architecture.
+    fn execute_step(
+        &self,
+        step: &BuildStep,
+        context: BuildContext<'_>,
+        patch: impl Fn(&str, &Path) -> Result<(), BindingsError>,
+    ) -> Result<(), BindingsError> {
+        let BuildContext { runner, observer } = context;
+        match step {
+            BuildStep::Command { command, .. } => runner.run(&mut command.to_command()),
+            BuildStep::InstallTool {
+                tool,
+                check,
+                install,
+            } => install_tool(context, tool, check, install),
+            BuildStep::CreateDir { path } => fs::create_dir_all(path).map_err(BindingsError::io(
+                format!("Failed to create {}", path.display()),
+            )),
+            BuildStep::Copy { from, to } => copy(from, to),
+            BuildStep::Rename { from, to } => fs::rename(from, to).map_err(BindingsError::io(
+                format!("Failed to rename {}", from.display()),
+            )),
+            BuildStep::WriteFile { path, contents } => fs::write(path, contents).map_err(
+                BindingsError::io(format!("Failed to write {}", path.display())),
+            ),
+            BuildStep::GenerateBindings {
+                language,
+                library,
+                out_dir,
+            } => {
+                observer.on_event(&BuildEvent::GeneratingBindings {
+                    language: *language,
+                    library,
+                });
+                generate_bindings(*language, library, out_dir)
+            }
+            BuildStep::Patch { name, path } => patch(name, path),
+            BuildStep::Install { from, to } => {
+                observer.on_event(&BuildEvent::Packaging { from, to });
+                move_into_place(from, to)?;
+                observer.on_event(&BuildEvent::ArtifactWritten { path: to });
+                Ok(())
+            }
+            BuildStep::WriteManifest {
+                dir,
+                uniffi_version,
+                arch_paths,
+            } => {
+                let manifest = Manifest::compute(
+                    &self.platform,
+                    &self.mode,
+                    &self.project,
+                    dir,
+                    uniffi_version.as_deref(),
+                    arch_paths,
+                )?;
+                let path = manifest.save(dir)?;
+                observer.on_event(&BuildEvent::ArtifactWritten { path: &path });
+                Ok(())
+            }
+        }
+    }
+}
+
+/// Runs step `index` of a plan, reporting when it starts and finishes.
+fn observe_step(
+    observer: &dyn BuildObserver,
+    index: usize,
+    step: &BuildStep,
+    run: impl FnOnce() -> Result<(), BindingsError>,
+) -> Result<(), BindingsError> {
+    observer.on_event(&BuildEvent::StepStarted { index, step });
+    let start = Instant::now();
+    run()?;
+    observer.on_event(&BuildEvent::StepFinished {
+        index,
+        duration: start.elapsed(),
+    });
+    Ok(())
+}
+
+impl BuildStep {
+    pub fn command(cmd: &Command) -> Self {
+        Self::Command {
+            arch: None,
+            command: CommandLine::from_command(cmd),
+        }
+    }
+
+    /// A command building the library for `arch`.
+    pub fn arch_command(arch: &str, cmd: &Command) -> Self {
+        Self::Command {
+            arch: Some(arch.to_string()),
+            command: CommandLine::from_command(cmd),
+        }
+    }
+
+    pub fn patch(name: &str, path: impl Into<PathBuf>) -> Self {
+        Self::Patch {
+            name: name.to_string(),
+            path: path.into(),
+        }
+    }
+}
+
+impl fmt::Display for BuildPlan {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "{} bindings ({}) -> {}",
+            self.platform,
+            self.mode,
+            self.bindings_dir.display()
+        )?;
+        if self.up_to_date {
+            return writeln!(f, "  up to date, nothing to build");
+        }
+        if self.offline {
+            writeln!(f, "  offline, missing targets and tools are not installed")?;
+        }
+        if self.jobs > 1 {
+            writeln!(
+                f,
+                "  up to {} architectures compiled in parallel",
+                self.jobs
+            )?;
+        }
+        for group in &self.arch_groups {
+            writeln!(
+                f,
+                "  {}: {} -> {}",
+                group.name,
+                group.archs.join(", "),
+                group.library.display()
+            )?;
+        }
+        for (i, step) in self.steps.iter().enumerate() {
+            writeln!(f, "  {}. {step}", i + 1)?;
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for BuildStep {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Command {
+                arch: Some(arch),
+                command,
+            } => write!(f, "[{arch}] {command}"),
+            Self::Command {
+                arch: None,
+                command,
+            } => write!(f, "{command}"),
+            Self::InstallTool { tool, install, .. } => {
+                write!(f, "install {tool} if missing: {install}")
+            }
+            Self::CreateDir { path } => write!(f, "create {}", path.display()),
+            Self::Copy { from, to } => write!(f, "copy {} to {}", from.display(), to.display()),
+            Self::Rename { from, to } => {
+                write!(f, "rename {} to {}", from.display(), to.display())
+            }
+            Self::WriteFile { path, .. } => write!(f, "write {}", path.display()),
+            Self::GenerateBindings {
+                language,
+                library,
+                out_dir,
+            } => write!(
+                f,
+                "generate {language} bindings of {} into {}",
+                library.display(),
+                out_dir.display()
+            ),
+            Self::Patch { name, path } => write!(f, "patch {} ({name})", path.display()),
+            Self::Install { from, to } => {
+                write!(f, "install {} to {}", from.display(), to.display())
+            }
+            Self::WriteManifest { dir, .. } => {
+                write!(f, "write {}", dir.join(MANIFEST_FILE).display())
+            }
+        }
+    }
+}
+
+impl fmt::Display for BindingsLanguage {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Swift => write!(f, "swift"),
+            Self::Kotlin => write!(f, "kotlin"),
+            Self::Python => write!(f, "python"),
+        }
+    }
+}
+
+/// Index, step and command line of a per-architecture step.
+type ArchCommand<'a> = (usize, &'a BuildStep, &'a CommandLine);
+
+/// Commands of consecutive per-architecture steps, grouped by architecture in order.
+#[derive(Default)]
+struct ArchJobs<'a>(Vec<(&'a str, Vec<ArchCommand<'a>>)>);
+
+impl<'a> ArchJobs<'a> {
+    fn push(&mut self, arch: &'a str, index: usize, step: &'a BuildStep, command: &'a CommandLine) {
+        let job = (index, step, command);
+        match self.0.iter_mut().find(|(job_arch, _)| *job_arch == arch) {
+            Some((_, commands)) => commands.push(job),
+            None => self.0.push((arch, vec![job])),
+        }
+    }
+
+    /// Runs the commands of up to `jobs` architectures at once, each architecture's in order.
+    /// Once a command fails, the running jobs are cancelled and no new ones are started.
+    fn run(self, context: BuildContext<'_>, jobs: usize) -> Result<(), BindingsError> {
+        let BuildContext { runner, observer } = context;
+        let run_arch =
+            |arch: &str, commands: Vec<ArchCommand<'_>>, cancellation: Option<&Cancellation>| {
+                observer.on_event(&BuildEvent::ArchStarted { arch });
+                let start = Instant::now();
+                for (index, step, command) in commands {
+                    observe_step(observer, index, step, || match cancellation {
+                        Some(cancellation) => {
+                            runner.run_job(&mut command.to_command(), arch, cancellation)
+                        }
+                        None => runner.run(&mut command.to_command()),
+                    })?;
+                }
+                observer.on_event(&BuildEvent::ArchFinished {
+                    arch,
+                    duration: start.elapsed(),
+                });
+                Ok(())
+            };
+
+        if jobs == 1 || self.0.len() == 1 {
+            for (arch, commands) in self.0 {
+                run_arch(arch, commands, None)?;
+            }
+            return Ok(());
+        }
+
+        let workers = jobs.min(self.0.len());
+        let queue = Mutex::new(self.0.into_iter());
+        let cancellation = Cancellation::default();
+        let first_error = Mutex::new(None);
+        thread::scope(|scope| {
+            for _ in 0..workers {
+                scope.spawn(|| {
+                    while !cancellation.is_cancelled() {
+                        let Some((arch, commands)) = queue.lock().unwrap().next() else {
+                            return;
+                        };
+                        if let Err(e) = run_arch(arch, commands, Some(&cancellation)) {
+                            let mut first_error = first_error.lock().unwrap();
+                            if first_error.is_none()
+                                && !matches!(e, BindingsError::Cancelled { .. })
+                            {
+                                *first_error = Some(e);
+                            }
+                            cancellation.cancel();
+                            return;
+                        }
+                    }
+                });
+            }
+        });
+
+        match first_error.into_inner().unwrap() {
+            Some(e) => Err(e),
+            None => Ok(()),
+        }
+    }
+}
+
+fn install_tool(
+    context: BuildContext<'_>,
+    tool: &str,
+    check: &CommandLine,
+    install: &CommandLine,
+) -> Result<(), BindingsError> {
+    let BuildContext { runner, observer } = context;
+    match runner.run(&mut check.to_command()) {
+        Ok(()) => {
+            let message = format!("{tool} already installed.");
+            observer.on_event(&BuildEvent::Message { message: &message });
+            Ok(())
+        }
+        Err(BindingsError::ToolNotFound { .. }) => {
+            let message = format!("{tool} not found, installing...");
+            observer.on_event(&BuildEvent::Message { message: &message });
+            runner.run(&mut install.to_command())
+        }
+        Err(e) => Err(e),
+    }
+}
+
+fn copy(from: &Path, to: &Path) -> Result<(), BindingsError> {
+    if !from.is_dir() {
+        if let Some(parent) = to.parent() {
+            fs::create_dir_all(parent).map_err(BindingsError::io(format!(
+                "Failed to create {}",
+                parent.display()
+            )))?;
+        }
+        fs::copy(from, to).map_err(BindingsError::io(format!(
+            "Failed to copy {}",
+            from.display()
+        )))?;
+        return Ok(());
+    }
+
+    fs::create_dir_all(to).map_err(BindingsError::io(format!(
+        "Failed to create {}",
+        to.display()
+    )))?;
+    let entries = fs::read_dir(from).map_err(BindingsError::io(format!(
+        "Failed to read {}",
+        from.display()
+    )))?;
+    for entry in entries {
+        let entry = entry.map_err(BindingsError::io(format!(
+            "Failed to read {}",
+            from.display()
+        )))?;
+        fs::copy(entry.path(), to.join(entry.file_name())).map_err(BindingsError::io(format!(
+            "Failed to copy {}",
+            entry.path().display()
+        )))?;
+    }
+    Ok(())
+}
+
+#[cfg(feature = "uniffi")]
+fn generate_bindings(
+    language: BindingsLanguage,
+    library: &Path,
+    out_dir: &Path,
+) -> Result<(), BindingsError> {
+    use camino::Utf8Path;
+    use uniffi::{
+        generate_bindings_library_mode, CargoMetadataConfigSupplier, KotlinBindingGenerator,
+        PythonBindingGenerator, SwiftBindingGenerator,
+    };
+
+    let library =
+        Utf8Path::from_path(library).ok_or(BindingsError::bindgen("Invalid dylib path"))?;
+    let out_dir =
+        Utf8Path::from_path(out_dir).ok_or(BindingsError::bindgen("Invalid bindings directory"))?;
+    let config_supplier = CargoMetadataConfigSupplier::default();
+    match language {
+        BindingsLanguage::Swift => generate_bindings_library_mode(
+            library,
+            None,
+            &SwiftBindingGenerator,
+            &config_supplier,
+            None,
+            out_dir,
+            true,
+        ),
+        BindingsLanguage::Kotlin => generate_bindings_library_mode(
+            library,
+            None,
+            &KotlinBindingGenerator,
+            &config_supplier,
+            None,
+            out_dir,
+            true,
+        ),
+        BindingsLanguage::Python => generate_bindings_library_mode(
+            library,
+            None,
+            &PythonBindingGenerator,
+            &config_supplier,
+            None,
+            out_dir,
+            true,
+        ),
+    }
+    .map_err(|e| BindingsError::bindgen(format!("{e:#}")))?;
+    Ok(())
+}
+
+#[cfg(not(feature = "uniffi"))]
+fn generate_bindings(
+    _language: BindingsLanguage,
+    _library: &Path,
+    _out_dir: &Path,
+) -> Result<(), BindingsError> {
+    Err(BindingsError::MissingFeature { feature: "uniffi" })
+}
+
+/// Steps of a plan by kind, for the tests of the builders
+#[cfg(test)]
+impl BuildPlan {
+    /// Sources and destinations of the `Copy` steps, in order
+    pub(crate) fn copies(&self) -> Vec<(&Path, &Path)> {
+        self.steps
+            .iter()
+            .filter_map(|step| match step {
+                BuildStep::Copy { from, to } => Some((from.as_path(), to.as_path())),
+                _ => None,
+            })
+            .collect()
+    }
+
+    /// Paths and contents of the `WriteFile` steps, in order
+    pub(crate) fn written_files(&self) -> Vec<(&Path, &str)> {
+        self.steps
+            .iter()
+            .filter_map(|step| match step {
+                BuildStep::WriteFile { path, contents } => {
+                    Some((path.as_path(), contents.as_str()))
+                }
+                _ => None,
+            })
+            .collect()
+    }
+
+    /// Contents of the first file written at a path ending with `path`
+    pub(crate) fn written_file(&self, path: &str) -> Option<&str> {
+        self.written_files()
+            .into_iter()
+            .find_map(|(file, contents)| file.ends_with(path).then_some(contents))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bindings::constants::Mode;
+    use crate::bindings::observer::{JsonLinesObserver, NoopObserver};
+    use crate::bindings::runner::{RecordingRunner, SystemRunner};
+    use std::time::{Duration, Instant};
+
+    fn project(dir: &Path) -> Project {
+        Project {
+            package: "test".to_string(),
+            version: "0.1.0".to_string(),
+            lib_name: "test".to_string(),
+            crate_types: vec!["cdylib".to_string()],
+            manifest_dir: dir.to_path_buf(),
+            target_dir: dir.join("target"),
+            source_dirs: vec![dir.to_path_buf()],
+        }
+    }
+
+    #[test]
+    fn test_include() {
+        let dir = crate::bindings::mktemp().unwrap();
+        let options = BuildOptions::new(Mode::Debug);
+        let mut included = BuildPlan::new("ios", &options, &project(&dir), &dir, &dir.join("iOS"));
+        included.work_dir = Some(dir.join("build/tmp/ios"));
+        included.steps = vec![BuildStep::WriteFile {
+            path: dir.join("build/tmp/ios/lib.txt"),
+            contents: "lib".to_string(),
+        }];
+
+        let bindings_dir = dir.join("Bindings");
+        let mut plan = BuildPlan::new("test", &options, &project(&dir), &dir, &bindings_dir);
+        plan.work_dir = Some(dir.join("build/tmp/work"));
+        plan.include(included);
+        plan.steps.push(BuildStep::Copy {
+            from: dir.join("build/tmp/ios/lib.txt"),
+            to: bindings_dir.join("lib.txt"),
+        });
+        assert_eq!(plan.included_work_dirs, [dir.join("build/tmp/ios")]);
+
+        plan.execute(
+            BuildContext::new(&SystemRunner::new(), &NoopObserver),
+            |_, _| Ok(()),
+        )
+        .unwrap();
+        assert!(bindings_dir.join("lib.txt").exists());
+        assert!(!dir.join("build/tmp/ios").exists());
+        assert!(!dir.join("build/tmp/work").exists());
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_execute() {
+        let dir = crate::bindings::mktemp().unwrap();
+        let work_dir = dir.join("build/tmp/work");
+        let bindings_out = work_dir.join("Bindings");
+        let bindings_dir = dir.join("Bindings");
+        fs::create_dir_all(&bindings_dir).unwrap();
+        fs::write(bindings_dir.join("stale.txt"), "").unwrap();
+
+        let options = BuildOptions::new(Mode::Debug);
+        let mut plan = BuildPlan::new("test", &options, &project(&dir), &dir, &bindings_dir);
+        plan.work_dir = Some(work_dir.clone());
+        plan.steps = vec![
+            BuildStep::command(Command::new("true").current_dir(&dir)),
+            BuildStep::CreateDir {
+                path: bindings_out.clone(),
+            },
+            BuildStep::WriteFile {
+                path: bindings_out.join("lib.txt"),
+                contents: "lib".to_string(),
+            },
+            BuildStep::patch("append", bindings_out.join("lib.txt")),
+            BuildStep::Install {
+                from: bindings_out.clone(),
+                to: bindings_dir.clone(),
+            },
+        ];
+        let patch = |name: &str, path: &Path| {
+            assert_eq!(name, "append");
+            let contents = fs::read_to_string(path).unwrap();
+            fs::write(path, contents + " patched").map_err(BindingsError::io("patch"))
+        };
+
+        // A dry run only reports the commands
+        let runner = RecordingRunner::new();
+        let context = BuildContext::new(&runner, &NoopObserver);
+        assert_eq!(plan.execute(context, patch).unwrap(), bindings_dir);
+        assert_eq!(runner.commands()[0].command(), "true");
+        assert!(!work_dir.exists());
+        assert!(bindings_dir.join("stale.txt").exists());
+
+        let observer = JsonLinesObserver::new(Vec::new());
+        plan.execute(BuildContext::new(&SystemRunner::new(), &observer), patch)
+            .unwrap();
+        assert_eq!(
+            fs::read_to_string(bindings_dir.join("lib.txt")).unwrap(),
+            "lib patched"
+        );
+        let events: Vec<serde_json::Value> = String::from_utf8(observer.into_inner())
+            .unwrap()
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        let kinds: Vec<&str> = events
+            .iter()
+            .map(|event| event["event"].as_str().unwrap())
+            .collect();
+        assert_eq!(kinds[0], "build_started");
+        assert_eq!(events[0]["steps"], 5);
+        assert_eq!(kinds[1..3], ["step_started", "step_finished"]);
+        assert_eq!(events[1]["step"]["kind"], "command");
+        assert!(events[2]["duration_secs"].is_f64());
+        assert_eq!(
+            kinds[kinds.len() - 5..],
+            [
+                "step_started",
+                "packaging",
+                "artifact_written",
+                "step_finished",
+                "build_finished"
+            ]
+        );
+        assert_eq!(
+            events[kinds.len() - 3]["path"],
+            bindings_dir.to_str().unwrap()
+        );
+        assert!(!bindings_dir.join("stale.txt").exists());
+        assert!(!work_dir.exists());
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_arch_events() {
+        let dir = crate::bindings::mktemp().unwrap();
+        let options = BuildOptions::new(Mode::Debug);
+        let mut plan = BuildPlan::new(
+            "test",
+            &options,
+            &project(&dir),
+            &dir,
+            &dir.join("Bindings"),
+        );
+        plan.steps = vec![
+            BuildStep::arch_command("first", &Command::new("true")),
+            BuildStep::arch_command("second", &Command::new("true")),
+        ];
+
+        let observer = JsonLinesObserver::new(Vec::new());
+        let runner = SystemRunner::new();
+        let context = BuildContext::new(&runner, &observer);
+        plan.execute(context, |_, _| Ok(())).unwrap();
+        let events: Vec<serde_json::Value> = String::from_utf8(observer.into_inner())
+            .unwrap()
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        let arch_events: Vec<String> = events
+            .iter()
+            .filter_map(|event| Some(format!("{} {}", event["event"], event.get("arch")?)))
+            .collect();
+        assert_eq!(
+            arch_events,
+            [
+                r#""arch_started" "first""#,
+                r#""arch_finished" "first""#,
+                r#""arch_started" "second""#,
+                r#""arch_finished" "second""#,
+            ]
+        );
+        assert_eq!(events[2]["index"], 0);
+        assert_eq!(events[6]["index"], 1);
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_parallel_jobs_fail_fast() {
+        let dir = crate::bindings::mktemp().unwrap();
+        let options = BuildOptions {
+            jobs: 2,
+            ..BuildOptions::new(Mode::Debug)
+        };
+        let mut plan = BuildPlan::new(
+            "test",
+            &options,
+            &project(&dir),
+            &dir,
+            &dir.join("Bindings"),
+        );
+        plan.work_dir = Some(dir.join("build/tmp/work"));
+        plan.steps = vec![
+            BuildStep::arch_command("slow", Command::new("sleep").arg("10")),
+            BuildStep::arch_command("failing", Command::new("sh").args(["-c", "exit 2"])),
+            BuildStep::arch_command("queued", Command::new("touch").arg(dir.join("queued"))),
+        ];
+
+        let start = Instant::now();
+        let runner = SystemRunner::new();
+        let context = BuildContext::new(&runner, &NoopObserver);
+        let err = plan.execute(context, |_, _| Ok(())).unwrap_err();
+        assert!(matches!(err, BindingsError::ToolFailed { tool, .. } if tool == "sh"));
+        // The slow job is killed and the queued one never starts
+        assert!(start.elapsed() < Duration::from_secs(5));
+        assert!(!dir.join("queued").exists());
+        // The work dir of a failed build is removed too
+        assert!(!dir.join("build/tmp/work").exists());
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -0,0 +1,464 @@
+//! The crate the bindings are built from, detected with `cargo metadata` so that workspace
+//! members, inherited package settings and workspace target directories are handled the way
+//! cargo handles them.
+
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use super::error::BindingsError;
+
+/// Library crate types the bindings link against
+const LINKABLE_CRATE_TYPES: [&str; 2] = ["cdylib", "staticlib"];
+
+/// A package of the workspace and its library target.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Project {
+    /// Name of the package, e.g. `my-circuits`
+    pub package: String,
+    pub version: String,
+    /// Name of the library target, e.g. `my_circuits`, which names the built libraries and the
+    /// generated uniffi module
+    pub lib_name: String,
+    /// Crate types of the library, e.g. `lib`, `cdylib` and `staticlib`
+    pub crate_types: Vec<String>,
+    /// Directory of the package's `Cargo.toml`
+    pub manifest_dir: PathBuf,
+    /// Target directory of the workspace, which honors `CARGO_TARGET_DIR` and `build.target-dir`
+    pub target_dir: PathBuf,
+    /// Directories of the local packages the library is built from: the package itself and its
+    /// path dependencies, e.g. other members of the workspace
+    pub source_dirs: Vec<PathBuf>,
+}
+
+#[derive(Deserialize)]
+struct Metadata {
+    packages: Vec<Package>,
+    target_directory: PathBuf,
+}
+
+#[derive(Deserialize)]
+struct Package {
+    name: String,
+    version: String,
+    manifest_path: PathBuf,
+    targets: Vec<Target>,
+    dependencies: Vec<Dependency>,
+}
+
+#[derive(Deserialize)]
+struct Dependency {
+    /// Directory of a path dependency
+    path: Option<PathBuf>,
+}
+
+#[derive(Deserialize)]
+struct Target {
+    name: String,
+    kind: Vec<String>,
+    crate_types: Vec<String>,
+}
+
+impl Package {
+    fn lib_target(&self) -> Option<&Target> {
+        self.targets.iter().find(|target| {
+            target.kind.iter().any(|kind| {
+                kind == "lib" || kind == "rlib" || LINKABLE_CRATE_TYPES.contains(&kind.as_str())
+            })
+        })
+    }
+
+    fn manifest_dir(&self) -> PathBuf {
+        self.manifest_path
+            .parent()
+            .map(Path::to_path_buf)
+            .unwrap_or_default()
+    }
+
+    fn path_dependencies(&self) -> impl Iterator<Item = &PathBuf> {
+        self.dependencies.iter().filter_map(|dep| dep.path.as_ref())
+    }
+
+    fn is_linkable(&self) -> bool {
+        self.lib_target().is_some_and(|target| {
+            target
+                .crate_types
+                .iter()
+                .any(|crate_type| LINKABLE_CRATE_TYPES.contains(&crate_type.as_str()))
+        })
+    }
+}
+
+impl Project {
+    /// Detects the package at `project_dir`, or `package` of the workspace `project_dir` is in.
+    /// A workspace root without a package of its own selects its only member with a `cdylib` or
+    /// `staticlib` library.
+    pub fn detect(project_dir: &Path, package: Option<&str>) -> Result<Self, BindingsError> {
+        let manifest_path = project_dir.join("Cargo.toml");
+        let metadata = cargo_metadata(&manifest_path)?;
+        Self::from_metadata(metadata, &manifest_path, package)
+    }
+
+    fn from_metadata(
+        metadata: Metadata,
+        manifest_path: &Path,
+        package: Option<&str>,
+    ) -> Result<Self, BindingsError> {
+        let manifest_path = fs::canonicalize(manifest_path).unwrap_or(manifest_path.to_path_buf());
+        let selected = match package {
+            Some(name) => metadata
+                .packages
+                .iter()
+                .find(|p| p.name == name)
+                .ok_or_else(|| BindingsError::PackageNotFound {
+                    package: name.to_string(),
+                    available: metadata.packages.iter().map(|p| p.name.clone()).collect(),
+                })?,
+            None => match metadata
+                .packages
+                .iter()
+                .find(|p| p.manifest_path == manifest_path)
+            {
+                Some(package) => package,
+                None => {
+                    let linkable: Vec<&Package> = metadata
+                        .packages
+                        .iter()
+                        .filter(|p| p.is_linkable())
+                        .collect();
+                    match linkable[..] {
+                        [package] => package,
+                        _ => {
+                            return Err(BindingsError::PackageRequired {
+                                candidates: linkable.iter().map(|p| p.name.clone()).collect(),
+                            })
+                        }
+                    }
+                }
+            },
+        };
+
+        let lib = selected
+            .lib_target()
+            .ok_or_else(|| BindingsError::MissingLibTarget {
+                package: selected.name.clone(),
+            })?;
+        let source_dirs = source_dirs(&metadata, selected)?;
+        Ok(Self {
+            package: selected.name.clone(),
+            version: selected.version.clone(),
+            // Older cargo versions report the name with the dashes of the package name
+            lib_name: lib.name.replace('-', "_"),
+            crate_types: lib.crate_types.clone(),
+            manifest_dir: selected.manifest_dir(),
+            target_dir: metadata.target_directory,
+            source_dirs,
+        })
+    }
+
+    /// Fails unless the library is built as every one of `crate_types`.
+    pub fn require_crate_types(&self, crate_types: &[&'static str]) -> Result<(), BindingsError> {
+        match crate_types
+            .iter()
+            .copied()
+            .find(|crate_type| !self.crate_types.iter().any(|c| c == crate_type))
+        {
+            Some(crate_type) => Err(BindingsError::MissingCrateType {
+                package: self.package.clone(),
+                crate_type,
+                crate_types: self.crate_types.clone(),
+            }),
+            None => Ok(()),
+        }
+    }
+}
+
+/// Runs `cargo metadata` on the workspace of `manifest_path`, without resolving dependencies.
+fn cargo_metadata(manifest_path: &Path) -> Result<Metadata, BindingsError> {
+    let output = Command::new("cargo")
+        .args([
+            "metadata",
+            "--format-version",
+            "1",
+            "--no-deps",
+            "--manifest-path",
+        ])
+        .arg(manifest_path)
+        .output()
+        .map_err(|source| BindingsError::ToolNotFound {
+            tool: "cargo".to_string(),
+            source,
+        })?;
+    if !output.status.success() {
+        return Err(BindingsError::manifest(
+            manifest_path,
+            String::from_utf8_lossy(&output.stderr).trim(),
+        ));
+    }
+    serde_json::from_slice(&output.stdout).map_err(|e| BindingsError::manifest(manifest_path, e))
+}
+
+/// Directories of `package` and of every package it depends on through a path, transitively.
+/// Path dependencies outside the workspace are looked up in their own workspace.
+fn source_dirs(metadata: &Metadata, package: &Package) -> Result<Vec<PathBuf>, BindingsError> {
+    let mut source_dirs = vec![package.manifest_dir()];
+    let mut pending: Vec<PathBuf> = package.path_dependencies().cloned().collect();
+    while let Some(dir) = pending.pop() {
+        if source_dirs.contains(&dir) {
+            continue;
+        }
+        let in_workspace = metadata.packages.iter().find(|p| p.manifest_dir() == dir);
+        let dependencies: Vec<PathBuf> = match in_workspace {
+            Some(dependency) => dependency.path_dependencies().cloned().collect(),
+            None => {
+                let manifest_path = dir.join("Cargo.toml");
+                cargo_metadata(&manifest_path)?
+                    .packages
+                    .iter()
+                    .find(|p| p.manifest_path == manifest_path)
+                    .map(|dependency| dependency.path_dependencies().cloned().collect())
+                    .unwrap_or_default()
+            }
+        };
+        pending.extend(dependencies);
+        source_dirs.push(dir);
+    }
+    Ok(source_dirs)
+}
+
+/// A crate written to a temporary directory for a test, removed with the directory when the
+/// test ends, also when it fails.
+#[cfg(test)]
+pub(crate) struct TestProject {
+    project: Project,
+    dir: PathBuf,
+}
+
+#[cfg(test)]
+impl TestProject {
+    /// Writes `files`, with paths relative to the crate root, and detects the crate.
+    pub(crate) fn new(files: &[(&str, &str)]) -> Self {
+        Self::with_crate_dir(files, "")
+    }
+
+    /// Writes `files`, with paths relative to the temporary directory, and detects the crate
+    /// in its subdirectory `crate_dir`.
+    pub(crate) fn with_crate_dir(files: &[(&str, &str)], crate_dir: &str) -> Self {
+        let dir = super::mktemp().unwrap();
+        for (path, contents) in files {
+            let path = dir.join(path);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(path, contents).unwrap();
+        }
+        let project = Project::detect(&dir.join(crate_dir), None).unwrap();
+        Self { project, dir }
+    }
+
+    /// The temporary directory the files were written to
+    pub(crate) fn dir(&self) -> &Path {
+        &self.dir
+    }
+}
+
+#[cfg(test)]
+impl std::ops::Deref for TestProject {
+    type Target = Project;
+
+    fn deref(&self) -> &Project {
+        &self.project
+    }
+}
+
+#[cfg(test)]
+impl Drop for TestProject {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.dir);
+    }
+}
+
+/// A crate named `name` with a library of `crate_types` in a new temporary directory.
+#[cfg(test)]
+pub(crate) fn test_project(name: &str, crate_types: &[&str]) -> TestProject {
+    let crate_types: Vec<String> = crate_types.iter().map(|c| format!("\"{c}\"")).collect();
+    TestProject::new(&[
+        (
+            "Cargo.toml",
+            &format!(
+                "[package]\nname = \"{name}\"\nversion = \"0.1.0\"\nedition = \"2021\"\n\n\
+                 [lib]\ncrate-type = [{}]\n",
+                crate_types.join(", ")
+            ),
+        ),
+        ("src/lib.rs", ""),
+    ])
+}
+
+/// A crate named `name` exporting `greet(name: String) -> String` through this `mopro-ffi`,
+/// for tests that build bindings and call them. The crates share a target directory in the
+/// temporary directory, so only the first build compiles uniffi and the dependencies.
+#[cfg(all(test, feature = "uniffi"))]
+pub(crate) fn uniffi_test_project(name: &str) -> TestProject {
+    TestProject::new(&[
+        (
+            "Cargo.toml",
+            &format!(
+                "[package]\nname = \"{name}\"\nversion = \"0.1.0\"\nedition = \"2021\"\n\n\
+                 [lib]\ncrate-type = [\"lib\", \"cdylib\", \"staticlib\"]\n\n\
+                 [dependencies]\nmopro-ffi = {{ path = {:?}, features = [\"uniffi\"] }}\n\n\
+                 [workspace]\n",
+                env!("CARGO_MANIFEST_DIR")
+            ),
+        ),
+        (
+            "src/lib.rs",
+            "mopro_ffi::uniffi_setup!();\n\n\
+             #[uniffi::export]\n\
+             pub fn greet(name: String) -> String {\n    format!(\"Hi Mopro, {name}!\")\n}\n",
+        ),
+        (
+            ".cargo/config.toml",
+            &format!(
+                "[build]\ntarget-dir = {:?}\n",
+                std::env::temp_dir().join("mopro-uniffi-tests")
+            ),
+        ),
+    ])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn member_manifest(name: &str, crate_types: &str) -> String {
+        format!(
+            "[package]\nname = \"{name}\"\nversion.workspace = true\nedition = \"2021\"\n\n\
+             [lib]\ncrate-type = [{crate_types}]\n"
+        )
+    }
+
+    #[test]
+    fn test_detect_workspace_member() {
+        let fixture = TestProject::new(&[
+            (
+                "Cargo.toml",
+                "[workspace]\nmembers = [\"circuits\", \"utils\"]\nresolver = \"2\"\n\n\
+                 [workspace.package]\nversion = \"1.2.3\"\n",
+            ),
+            (
+                "circuits/Cargo.toml",
+                &member_manifest("my-circuits", "\"lib\", \"cdylib\", \"staticlib\""),
+            ),
+            ("circuits/src/lib.rs", ""),
+            ("utils/Cargo.toml", &member_manifest("utils", "\"lib\"")),
+            ("utils/src/lib.rs", ""),
+        ]);
+        let workspace = fs::canonicalize(fixture.dir()).unwrap();
+
+        // The only linkable member of the workspace root, with the inherited version
+        let project = Project::detect(&workspace, None).unwrap();
+        assert_eq!(project.package, "my-circuits");
+        assert_eq!(project.version, "1.2.3");
+        assert_eq!(project.lib_name, "my_circuits");
+        assert_eq!(project.manifest_dir, workspace.join("circuits"));
+        assert_eq!(project.target_dir, workspace.join("target"));
+        assert!(project
+            .require_crate_types(&["cdylib", "staticlib"])
+            .is_ok());
+
+        // A member's directory selects the member
+        let utils = Project::detect(&workspace.join("utils"), None).unwrap();
+        assert_eq!(utils.package, "utils");
+        assert_eq!(utils.target_dir, workspace.join("target"));
+        assert!(matches!(
+            utils.require_crate_types(&["cdylib"]),
+            Err(BindingsError::MissingCrateType {
+                crate_type: "cdylib",
+                ..
+            })
+        ));
+
+        let selected = Project::detect(&workspace, Some("utils")).unwrap();
+        assert_eq!(selected, utils);
+        assert!(matches!(
+            Project::detect(&workspace, Some("missing")),
+            Err(BindingsError::PackageNotFound { .. })
+        ));
+
+        // Several linkable members need a package to be selected
+        fs::write(
+            workspace.join("utils/Cargo.toml"),
+            member_manifest("utils", "\"cdylib\""),
+        )
+        .unwrap();
+        assert!(matches!(
+            Project::detect(&workspace, None),
+            Err(BindingsError::PackageRequired { candidates }) if candidates.len() == 2
+        ));
+    }
+
+    #[test]
+    fn test_source_dirs_follow_path_dependencies() {
+        let workspace_fixture = TestProject::new(&[
+            (
+                "Cargo.toml",
+                "[workspace]\nmembers = [\"circuits\", \"utils\", \"cli\"]\nresolver = \"2\"\n\n\
+                 [workspace.package]\nversion = \"0.1.0\"\n",
+            ),
+            (
+                "circuits/Cargo.toml",
+                &member_manifest("circuits", "\"cdylib\""),
+            ),
+            ("circuits/src/lib.rs", ""),
+            ("utils/Cargo.toml", &member_manifest("utils", "\"lib\"")),
+            ("utils/src/lib.rs", ""),
+            ("cli/Cargo.toml", &member_manifest("cli", "\"lib\"")),
+            ("cli/src/lib.rs", ""),
+        ]);
+        let external_fixture = TestProject::new(&[
+            (
+                "Cargo.toml",
+                "[package]\nname = \"external\"\nversion = \"0.1.0\"\nedition = \"2021\"\n",
+            ),
+            ("src/lib.rs", ""),
+        ]);
+        let workspace = fs::canonicalize(workspace_fixture.dir()).unwrap();
+        let external = fs::canonicalize(external_fixture.dir()).unwrap();
+        let add_dependency = |member: &str, dependency: &str, path: &Path| {
+            let manifest = workspace.join(member).join("Cargo.toml");
+            let content = fs::read_to_string(&manifest).unwrap();
+            fs::write(
+                &manifest,
+                format!("{content}\n[dependencies]\n{dependency} = {{ path = {path:?} }}\n"),
+            )
+            .unwrap();
+        };
+        add_dependency("circuits", "utils", &workspace.join("utils"));
+        add_dependency("utils", "external", &external);
+
+        // Members the package does not depend on are not sources of its build
+        let project = Project::detect(&workspace, Some("circuits")).unwrap();
+        assert_eq!(
+            project.source_dirs,
+            [
+                workspace.join("circuits"),
+                workspace.join("utils"),
+                external.clone()
+            ]
+        );
+    }
+
+    #[test]
+    #[allow(deprecated)]
+    fn test_deprecated_name_helpers() {
+        let project = test_project("my-circuits", &["lib", "cdylib"]);
+        assert_eq!(
+            crate::bindings::project_name_from_toml(&project.manifest_dir).unwrap(),
+            "my_circuits"
+        );
+        assert_eq!(
+            crate::bindings::raw_project_name_from_toml(&project.manifest_dir).unwrap(),
+            "my-circuits"
+        );
+    }
+}
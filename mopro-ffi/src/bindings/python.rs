@@ -0,0 +1,312 @@
+use std::path::Path;
+use std::process::Command;
+
+use super::PlatformBuilder;
+
+use super::config::{FromPlatformConfig, PlatformConfig};
+use super::constants::{
+    Arch, BuildOptions, Platform, PythonArch, PythonPlatform, PYTHON_BINDINGS_DIR,
+    PYTHON_MODULE_FILE, PYTHON_PACKAGE_NAME,
+};
+use super::doctor::{check_command, ToolCheck};
+use super::error::BindingsError;
+use super::manifest::{ArchPath, UNIFFI_VERSION};
+use super::plan::{BindingsLanguage, BuildPlan, BuildStep};
+use super::project::Project;
+use super::work_dir_path;
+
+#[inline]
+pub fn build() {
+    super::build_from_env::<PythonPlatform>().unwrap_or_else(|e| {
+        panic!(
+            "Failed to build {} bindings: {e}",
+            PythonPlatform::identifier()
+        )
+    })
+}
+
+#[derive(Hash)]
+pub struct PythonBindingsParams {
+    /// Name of the generated bindings directory
+    pub bindings_dir: String,
+    /// Name of the Python package the bindings are imported from, `import <package_name>`
+    pub package_name: String,
+}
+
+impl Default for PythonBindingsParams {
+    fn default() -> Self {
+        Self {
+            bindings_dir: PYTHON_BINDINGS_DIR.to_string(),
+            package_name: PYTHON_PACKAGE_NAME.to_string(),
+        }
+    }
+}
+
+impl FromPlatformConfig for PythonBindingsParams {
+    fn from_platform_config(config: &PlatformConfig) -> anyhow::Result<Self> {
+        config.check_params(&["package_name"])?;
+        let default = Self::default();
+        Ok(Self {
+            bindings_dir: config.bindings_dir.clone().unwrap_or(default.bindings_dir),
+            package_name: config
+                .string_param("package_name")?
+                .unwrap_or(default.package_name),
+        })
+    }
+}
+
+impl PlatformBuilder for PythonPlatform {
+    type Arch = PythonArch;
+    type Params = PythonBindingsParams;
+
+    /// Lays the bindings out as a package `pip install` accepts
+    ///
+    /// ```text
+    /// MoproPythonBindings/
+    /// ├── pyproject.toml
+    /// └── mopro/
+    ///     ├── __init__.py
+    ///     ├── mopro.py
+    ///     └── lib<name>.so
+    /// ```
+    fn plan(
+        options: &BuildOptions,
+        project: &Project,
+        output_dir: &Path,
+        _target_archs: Vec<Self::Arch>,
+        fresh_archs: &[Self::Arch],
+        params: &Self::Params,
+    ) -> Result<BuildPlan, BindingsError> {
+        if !cfg!(feature = "uniffi") {
+            return Err(BindingsError::MissingFeature { feature: "uniffi" });
+        }
+
+        project.require_crate_types(&["cdylib"])?;
+        let project_dir = &project.manifest_dir;
+        let uniffi_style_identifier = &project.lib_name;
+
+        let bindings_dir_name = &params.bindings_dir;
+        let package_name = &params.package_name;
+
+        // Names for the generated files by uniffi
+        let gen_module_file_name = format!("{uniffi_style_identifier}.py");
+        let lib_name = format!(
+            "{}{uniffi_style_identifier}{}",
+            std::env::consts::DLL_PREFIX,
+            std::env::consts::DLL_SUFFIX
+        );
+
+        // Paths for the generated files
+        let work_dir = work_dir_path(&project_dir.join("build"));
+        let python_bindings_dir = work_dir.join("PythonBindings");
+        let bindings_out = work_dir.join(bindings_dir_name);
+        let bindings_dest = output_dir.join(bindings_dir_name);
+        let package_out = bindings_out.join(package_name);
+
+        let mut plan = BuildPlan::new(Self::name(), options, project, output_dir, &bindings_dest);
+        plan.work_dir = Some(work_dir.clone());
+        plan.steps.push(BuildStep::CreateDir {
+            path: package_out.clone(),
+        });
+
+        // The library is built for the host, next to the one of a plain `cargo build`
+        let out_lib_path = project
+            .target_dir
+            .join(options.mode.as_str())
+            .join(&lib_name);
+        let arch = PythonArch;
+        if fresh_archs.is_empty() || !out_lib_path.exists() {
+            let mut build_cmd = Command::new("cargo");
+            build_cmd
+                .arg("build")
+                .arg("--lib")
+                .args(options.mode.cargo_args())
+                .args(options.cargo.feature_args());
+            if let Some(rustflags) = options.cargo.rustflags(arch.as_str()) {
+                build_cmd.env("RUSTFLAGS", rustflags);
+            }
+            build_cmd
+                .current_dir(project_dir)
+                .env("CARGO_BUILD_TARGET_DIR", &project.target_dir);
+            plan.steps
+                .push(BuildStep::arch_command(arch.as_str(), &build_cmd));
+        }
+
+        // The generated module loads the library from its own directory
+        plan.steps.push(BuildStep::Copy {
+            from: out_lib_path.clone(),
+            to: package_out.join(&lib_name),
+        });
+        plan.steps.push(BuildStep::GenerateBindings {
+            language: BindingsLanguage::Python,
+            library: out_lib_path,
+            out_dir: python_bindings_dir.clone(),
+        });
+        plan.steps.push(BuildStep::Rename {
+            from: python_bindings_dir.join(gen_module_file_name),
+            to: package_out.join(PYTHON_MODULE_FILE),
+        });
+        plan.steps.push(BuildStep::WriteFile {
+            path: package_out.join("__init__.py"),
+            contents: format!(
+                "from .{} import *  # noqa: F401,F403\n",
+                PYTHON_MODULE_FILE.trim_end_matches(".py")
+            ),
+        });
+        plan.steps.push(BuildStep::WriteFile {
+            path: bindings_out.join("pyproject.toml"),
+            contents: pyproject_toml(project, package_name, &lib_name),
+        });
+        plan.steps.push(BuildStep::Install {
+            from: bindings_out,
+            to: bindings_dest.clone(),
+        });
+        plan.steps.push(BuildStep::WriteManifest {
+            dir: bindings_dest,
+            uniffi_version: Some(UNIFFI_VERSION.to_string()),
+            arch_paths: vec![ArchPath::new(
+                Path::new(package_name).join(&lib_name),
+                &[arch.as_str()],
+            )],
+        });
+
+        Ok(plan)
+    }
+
+    fn toolchain_checks(_target_archs: &[Self::Arch]) -> Vec<ToolCheck> {
+        vec![check_command(
+            "python3",
+            "python3",
+            &["--version"],
+            "Install Python 3 from https://www.python.org/downloads or the system package manager",
+        )]
+    }
+}
+
+/// `pyproject.toml` shipping the native library as package data of `package_name`.
+fn pyproject_toml(project: &Project, package_name: &str, lib_name: &str) -> String {
+    format!(
+        "[build-system]\n\
+         requires = [\"setuptools>=61\"]\n\
+         build-backend = \"setuptools.build_meta\"\n\
+         \n\
+         [project]\n\
+         name = \"{package_name}\"\n\
+         version = \"{}\"\n\
+         description = \"Mopro bindings of {}\"\n\
+         requires-python = \">=3.8\"\n\
+         \n\
+         [tool.setuptools]\n\
+         packages = [\"{package_name}\"]\n\
+         \n\
+         [tool.setuptools.package-data]\n\
+         {package_name} = [\"{lib_name}\"]\n",
+        project.version, project.package
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bindings::constants::{BuildContext, Mode};
+    use crate::bindings::observer::NoopObserver;
+    use crate::bindings::project::test_project;
+    use crate::bindings::runner::RecordingRunner;
+
+    #[test]
+    fn test_package_layout() {
+        let project = test_project("my-circuits", &["lib", "cdylib"]);
+        let project_dir = project.manifest_dir.clone();
+
+        let runner = RecordingRunner::new();
+        let bindings_dir = PythonPlatform::build(
+            &BuildOptions::new(Mode::Release),
+            &project,
+            &project_dir,
+            vec![PythonArch],
+            &[],
+            PythonBindingsParams::default(),
+            BuildContext::new(&runner, &NoopObserver),
+        )
+        .unwrap();
+        assert_eq!(bindings_dir, project_dir.join(PYTHON_BINDINGS_DIR));
+        let lines: Vec<String> = runner.commands().iter().map(|cmd| cmd.command()).collect();
+        assert_eq!(lines, ["cargo build --lib --release"]);
+
+        let plan = PythonPlatform::plan(
+            &BuildOptions::new(Mode::Release),
+            &project,
+            &project_dir,
+            vec![PythonArch],
+            &[],
+            &PythonBindingsParams::default(),
+        )
+        .unwrap();
+        let package_out = plan
+            .work_dir
+            .clone()
+            .unwrap()
+            .join(PYTHON_BINDINGS_DIR)
+            .join("mopro");
+        let lib_name = format!(
+            "{}my_circuits{}",
+            std::env::consts::DLL_PREFIX,
+            std::env::consts::DLL_SUFFIX
+        );
+        // The library is shipped inside the package, next to the module loading it
+        let copies = plan.copies();
+        assert_eq!(copies.len(), 1);
+        assert_eq!(copies[0].1, package_out.join(&lib_name));
+
+        let written = plan.written_files();
+        assert_eq!(written[0].0, package_out.join("__init__.py"));
+        assert_eq!(written[0].1, "from .mopro import *  # noqa: F401,F403\n");
+        let pyproject = plan
+            .written_file("MoproPythonBindings/pyproject.toml")
+            .unwrap();
+        assert!(pyproject.contains("name = \"mopro\"\nversion = \"0.1.0\"\n"));
+        assert!(pyproject.contains(&format!("mopro = [\"{lib_name}\"]")));
+    }
+
+    /// Builds the package of a uniffi crate and calls it from Python, if `python3` is installed.
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn test_import_package() {
+        use crate::bindings::doctor::command_output;
+        use crate::bindings::project::uniffi_test_project;
+        use crate::bindings::runner::SystemRunner;
+
+        if command_output("python3", &["--version"]).is_err() {
+            eprintln!("python3 is not installed, skipping");
+            return;
+        }
+        let project = uniffi_test_project("python-smoke");
+        let project_dir = project.manifest_dir.clone();
+
+        let runner = SystemRunner::new();
+        let bindings_dir = PythonPlatform::build(
+            &BuildOptions::new(Mode::Debug),
+            &project,
+            &project_dir,
+            vec![PythonArch],
+            &[],
+            PythonBindingsParams::default(),
+            BuildContext::new(&runner, &NoopObserver),
+        )
+        .unwrap();
+        let output = Command::new("python3")
+            .args(["-c", "import mopro; print(mopro.greet('Python'))"])
+            .env("PYTHONPATH", &bindings_dir)
+            .output()
+            .unwrap();
+        assert!(
+            output.status.success(),
+            "{}",
+            String::from_utf8_lossy(&output.stderr)
+        );
+        assert_eq!(
+            String::from_utf8_lossy(&output.stdout).trim(),
+            "Hi Mopro, Python!"
+        );
+    }
+}
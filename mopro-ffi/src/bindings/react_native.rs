@@ -0,0 +1,535 @@
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use super::PlatformBuilder;
+
+use super::android::{AndroidBindingsParams, KOTLIN_PACKAGE_PATCH};
+use super::config::{FromPlatformConfig, PlatformConfig};
+use super::constants::{
+    AndroidArch, AndroidPlatform, BuildOptions, IosArch, IosPlatform, Platform, ReactNativeArch,
+    ReactNativePlatform, ANDROID_JNILIBS_DIR, IOS_XCFRAMEWORKS_DIR, REACT_NATIVE_BINDINGS_DIR,
+    REACT_NATIVE_PACKAGE_NAME, UNIFFI_BINDGEN_REACT_NATIVE_VERSION,
+};
+use super::doctor::{check_command, command_output, ToolCheck};
+use super::error::BindingsError;
+use super::ios::{IosBindingsParams, REGROUP_HEADERS_PATCH};
+use super::manifest::{ArchPath, UNIFFI_VERSION};
+use super::plan::{BuildPlan, BuildStep};
+use super::project::Project;
+use super::runner::CommandLine;
+use super::work_dir_path;
+
+const UNIFFI_BINDGEN_REACT_NATIVE: &str = "uniffi-bindgen-react-native";
+/// Configuration of the turbo module read by uniffi-bindgen-react-native
+const UBRN_CONFIG_FILE: &str = "ubrn.config.yaml";
+// Directories of the package, relative to its root
+const IOS_DIR: &str = "ios";
+const ANDROID_DIR: &str = "android";
+/// Directory of the Android sources, which contains the `jniLibs`
+const ANDROID_MAIN_DIR: &str = "src/main";
+const GENERATED_TS_DIR: &str = "src/generated";
+const GENERATED_CPP_DIR: &str = "cpp/generated";
+
+#[inline]
+pub fn build() {
+    super::build_from_env::<ReactNativePlatform>().unwrap_or_else(|e| {
+        panic!(
+            "Failed to build {} bindings: {e}",
+            ReactNativePlatform::identifier()
+        )
+    })
+}
+
+#[derive(Hash)]
+pub struct ReactNativeBindingsParams {
+    /// Name of the generated bindings directory
+    pub bindings_dir: String,
+    /// Name of the npm package of the turbo module
+    pub package_name: String,
+    /// Parameters of the XCFramework the package links on iOS
+    pub ios: IosBindingsParams,
+    /// Parameters of the `jniLibs` the package loads on Android
+    pub android: AndroidBindingsParams,
+}
+
+impl Default for ReactNativeBindingsParams {
+    fn default() -> Self {
+        Self {
+            bindings_dir: REACT_NATIVE_BINDINGS_DIR.to_string(),
+            package_name: REACT_NATIVE_PACKAGE_NAME.to_string(),
+            ios: IosBindingsParams::default(),
+            android: AndroidBindingsParams::default(),
+        }
+    }
+}
+
+impl FromPlatformConfig for ReactNativeBindingsParams {
+    fn from_platform_config(config: &PlatformConfig) -> anyhow::Result<Self> {
+        config.check_params(&["package_name", "using_noir"])?;
+        let default = Self::default();
+        Ok(Self {
+            bindings_dir: config.bindings_dir.clone().unwrap_or(default.bindings_dir),
+            package_name: config
+                .string_param("package_name")?
+                .unwrap_or(default.package_name),
+            ios: IosBindingsParams {
+                using_noir: config
+                    .bool_param("using_noir")?
+                    .unwrap_or(default.ios.using_noir),
+                ..default.ios
+            },
+            android: default.android,
+        })
+    }
+}
+
+impl PlatformBuilder for ReactNativePlatform {
+    type Arch = ReactNativeArch;
+    type Params = ReactNativeBindingsParams;
+
+    /// Builds the native libraries with the iOS and Android builders and lays them out as a
+    /// React Native turbo module, whose JSI bindings and TypeScript types are generated by
+    /// uniffi-bindgen-react-native from the uniffi exports of the library
+    ///
+    /// ```text
+    /// MoproReactNativeBindings/
+    /// ├── package.json
+    /// ├── ubrn.config.yaml
+    /// ├── src/
+    /// │   ├── index.tsx
+    /// │   └── generated/<name>.ts
+    /// ├── cpp/
+    /// │   └── generated/<name>.cpp
+    /// ├── ios/
+    /// │   └── MoproBindings.xcframework
+    /// └── android/
+    ///     └── src/main/jniLibs/<abi>/lib<name>.so
+    /// ```
+    fn plan(
+        options: &BuildOptions,
+        project: &Project,
+        output_dir: &Path,
+        target_archs: Vec<Self::Arch>,
+        fresh_archs: &[Self::Arch],
+        params: &Self::Params,
+    ) -> Result<BuildPlan, BindingsError> {
+        if !cfg!(feature = "uniffi") {
+            return Err(BindingsError::MissingFeature { feature: "uniffi" });
+        }
+
+        let project_dir = &project.manifest_dir;
+        let uniffi_style_identifier = &project.lib_name;
+
+        // Paths for the generated files
+        let work_dir = work_dir_path(&project_dir.join("build"));
+        let bindings_out = work_dir.join(&params.bindings_dir);
+        let bindings_dest = output_dir.join(&params.bindings_dir);
+
+        let mut plan = BuildPlan::new(Self::name(), options, project, output_dir, &bindings_dest);
+        plan.work_dir = Some(work_dir.clone());
+        if !options.offline {
+            plan.steps.push(install_uniffi_bindgen_react_native());
+        }
+        plan.steps.push(BuildStep::CreateDir {
+            path: bindings_out.clone(),
+        });
+
+        // The native libraries are the bindings of the iOS and Android builders, built into the
+        // work directory
+        let (ios_archs, android_archs) = split_archs(&target_archs);
+        let (fresh_ios_archs, fresh_android_archs) = split_archs(fresh_archs);
+        let mut library = None;
+        let mut arch_paths = Vec::new();
+        if !ios_archs.is_empty() {
+            let ios_plan = IosPlatform::plan(
+                options,
+                project,
+                &work_dir,
+                ios_archs,
+                &fresh_ios_archs,
+                &params.ios,
+            )?;
+            let framework = ios_plan.bindings_dir.join(IOS_XCFRAMEWORKS_DIR);
+            include_native_plan(
+                &mut plan,
+                ios_plan,
+                Path::new(IOS_DIR),
+                &mut library,
+                &mut arch_paths,
+            );
+            plan.steps.push(BuildStep::Copy {
+                from: framework,
+                to: bindings_out.join(IOS_DIR).join(IOS_XCFRAMEWORKS_DIR),
+            });
+        }
+        if !android_archs.is_empty() {
+            let android_plan = AndroidPlatform::plan(
+                options,
+                project,
+                &work_dir,
+                android_archs,
+                &fresh_android_archs,
+                &params.android,
+            )?;
+            let jni_libs = android_plan.bindings_dir.join(ANDROID_JNILIBS_DIR);
+            let android_main = Path::new(ANDROID_DIR).join(ANDROID_MAIN_DIR);
+            include_native_plan(
+                &mut plan,
+                android_plan,
+                &android_main,
+                &mut library,
+                &mut arch_paths,
+            );
+            plan.steps.push(BuildStep::Copy {
+                from: jni_libs,
+                to: bindings_out.join(android_main).join(ANDROID_JNILIBS_DIR),
+            });
+        }
+        let library = library.ok_or(BindingsError::NoTargetArchs {
+            platform: Self::name(),
+        })?;
+
+        plan.steps.push(BuildStep::WriteFile {
+            path: bindings_out.join("package.json"),
+            contents: package_json(project, &params.package_name),
+        });
+        plan.steps.push(BuildStep::WriteFile {
+            path: bindings_out.join(UBRN_CONFIG_FILE),
+            contents: ubrn_config(project, &params.package_name),
+        });
+
+        // The JSI bindings and their TypeScript types are generated from the uniffi metadata of
+        // the library, so they match the signatures of the Rust exports
+        plan.steps.push(BuildStep::command(
+            Command::new(UNIFFI_BINDGEN_REACT_NATIVE)
+                .args(["generate", "jsi", "bindings", "--library"])
+                .args(["--ts-dir", GENERATED_TS_DIR])
+                .args(["--cpp-dir", GENERATED_CPP_DIR])
+                .arg(&library)
+                .current_dir(&bindings_out),
+        ));
+        // Installs the bindings into the JS runtime when the turbo module is loaded
+        plan.steps.push(BuildStep::command(
+            Command::new(UNIFFI_BINDGEN_REACT_NATIVE)
+                .args([
+                    "generate",
+                    "jsi",
+                    "turbo-module",
+                    "--config",
+                    UBRN_CONFIG_FILE,
+                ])
+                .arg(uniffi_style_identifier)
+                .current_dir(&bindings_out),
+        ));
+        plan.steps.push(BuildStep::Install {
+            from: bindings_out,
+            to: bindings_dest.clone(),
+        });
+        plan.steps.push(BuildStep::WriteManifest {
+            dir: bindings_dest,
+            uniffi_version: Some(UNIFFI_VERSION.to_string()),
+            arch_paths,
+        });
+
+        Ok(plan)
+    }
+
+    fn apply_patch(
+        plan: &BuildPlan,
+        name: &str,
+        path: &Path,
+        params: &Self::Params,
+    ) -> Result<(), BindingsError> {
+        match name {
+            REGROUP_HEADERS_PATCH => IosPlatform::apply_patch(plan, name, path, &params.ios),
+            KOTLIN_PACKAGE_PATCH => AndroidPlatform::apply_patch(plan, name, path, &params.android),
+            _ => Err(BindingsError::unknown_patch(Self::identifier(), name)),
+        }
+    }
+
+    fn toolchain_checks(target_archs: &[Self::Arch]) -> Vec<ToolCheck> {
+        let (ios_archs, android_archs) = split_archs(target_archs);
+        let mut checks = Vec::new();
+        if !ios_archs.is_empty() {
+            checks.extend(IosPlatform::toolchain_checks(&ios_archs));
+        }
+        if !android_archs.is_empty() {
+            checks.extend(AndroidPlatform::toolchain_checks(&android_archs));
+        }
+        checks.push(check_command(
+            "node",
+            "node",
+            &["--version"],
+            "Install Node.js from https://nodejs.org",
+        ));
+        checks.push(ToolCheck::new(
+            UNIFFI_BINDGEN_REACT_NATIVE,
+            command_output(UNIFFI_BINDGEN_REACT_NATIVE, &["--version"]).and_then(|version| {
+                if version.ends_with(UNIFFI_BINDGEN_REACT_NATIVE_VERSION) {
+                    Ok(version)
+                } else {
+                    Err(format!(
+                        "found {version}, expected {UNIFFI_BINDGEN_REACT_NATIVE_VERSION}"
+                    ))
+                }
+            }),
+            format!(
+                "npm install --global {UNIFFI_BINDGEN_REACT_NATIVE}@{UNIFFI_BINDGEN_REACT_NATIVE_VERSION}"
+            ),
+        ));
+        checks
+    }
+}
+
+fn split_archs(archs: &[ReactNativeArch]) -> (Vec<IosArch>, Vec<AndroidArch>) {
+    let mut ios_archs = Vec::new();
+    let mut android_archs = Vec::new();
+    for arch in archs {
+        match *arch {
+            ReactNativeArch::Ios(arch) => ios_archs.push(arch),
+            ReactNativeArch::Android(arch) => android_archs.push(arch),
+        }
+    }
+    (ios_archs, android_archs)
+}
+
+/// Adds the steps of the plan of a native builder except its manifest, whose architecture
+/// paths are kept relative to `prefix` of the package. The library the native builder
+/// generates its bindings from is kept as `library`, if there is none yet.
+fn include_native_plan(
+    plan: &mut BuildPlan,
+    mut native_plan: BuildPlan,
+    prefix: &Path,
+    library: &mut Option<PathBuf>,
+    arch_paths: &mut Vec<ArchPath>,
+) {
+    native_plan.steps.retain(|step| match step {
+        BuildStep::GenerateBindings { library: lib, .. } => {
+            library.get_or_insert_with(|| lib.clone());
+            true
+        }
+        BuildStep::WriteManifest {
+            arch_paths: paths, ..
+        } => {
+            arch_paths.extend(paths.iter().map(|arch_path| ArchPath {
+                path: prefix.join(&arch_path.path),
+                archs: arch_path.archs.clone(),
+            }));
+            false
+        }
+        _ => true,
+    });
+    plan.include(native_plan);
+}
+
+fn install_uniffi_bindgen_react_native() -> BuildStep {
+    BuildStep::InstallTool {
+        tool: UNIFFI_BINDGEN_REACT_NATIVE.to_string(),
+        check: CommandLine::from_command(
+            Command::new(UNIFFI_BINDGEN_REACT_NATIVE).arg("--version"),
+        ),
+        install: CommandLine::from_command(Command::new("npm").args([
+            "install",
+            "--global",
+            &format!("{UNIFFI_BINDGEN_REACT_NATIVE}@{UNIFFI_BINDGEN_REACT_NATIVE_VERSION}"),
+        ])),
+    }
+}
+
+/// `package.json` of the turbo module, whose entry point is generated by uniffi-bindgen-react-native.
+fn package_json(project: &Project, package_name: &str) -> String {
+    let package = serde_json::json!({
+        "name": package_name,
+        "version": project.version,
+        "description": format!("Mopro bindings of {}", project.package),
+        "main": "src/index.tsx",
+        "types": "src/index.tsx",
+        "react-native": "src/index.tsx",
+        "files": ["src", "cpp", IOS_DIR, ANDROID_DIR, "*.podspec"],
+        "peerDependencies": {
+            "react": "*",
+            "react-native": "*",
+        },
+        "dependencies": {
+            UNIFFI_BINDGEN_REACT_NATIVE: UNIFFI_BINDGEN_REACT_NATIVE_VERSION,
+        },
+        "codegenConfig": {
+            "name": format!("{}Spec", pascal_case(package_name)),
+            "type": "modules",
+            "jsSrcsDir": "src",
+        },
+    });
+    let mut contents = serde_json::to_string_pretty(&package).unwrap_or_default();
+    contents.push('\n');
+    contents
+}
+
+/// `ubrn.config.yaml` pointing uniffi-bindgen-react-native at the crate and the native
+/// libraries of the package.
+fn ubrn_config(project: &Project, package_name: &str) -> String {
+    format!(
+        "name: {package_name:?}\n\
+         rust:\n  directory: {:?}\n  manifestPath: Cargo.toml\n\
+         android:\n  directory: {ANDROID_DIR}\n  jniLibs: {ANDROID_MAIN_DIR}/{ANDROID_JNILIBS_DIR}\n\
+         ios:\n  directory: {IOS_DIR}\n  frameworkName: {}\n\
+         bindings:\n  cpp: {GENERATED_CPP_DIR}\n  ts: {GENERATED_TS_DIR}\n\
+         turboModule:\n  cpp: cpp\n  ts: src\n",
+        project.manifest_dir,
+        IOS_XCFRAMEWORKS_DIR.trim_end_matches(".xcframework"),
+    )
+}
+
+/// `mopro-react-native` to `MoproReactNative`
+fn pascal_case(name: &str) -> String {
+    name.split(|c: char| !c.is_ascii_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .map(|word| {
+            let mut chars = word.chars();
+            chars
+                .next()
+                .map(|first| first.to_ascii_uppercase().to_string() + chars.as_str())
+                .unwrap_or_default()
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bindings::constants::Mode;
+    use crate::bindings::project::test_project;
+
+    #[test]
+    fn test_package_layout() {
+        let project = test_project("my-circuits", &["lib", "cdylib", "staticlib"]);
+        let project_dir = project.manifest_dir.clone();
+
+        let plan = ReactNativePlatform::plan(
+            &BuildOptions::new(Mode::Release),
+            &project,
+            &project_dir,
+            vec![
+                ReactNativeArch::Ios(IosArch::Aarch64Apple),
+                ReactNativeArch::Android(AndroidArch::Aarch64Linux),
+            ],
+            &[],
+            &ReactNativeBindingsParams::default(),
+        )
+        .unwrap();
+        let work_dir = plan.work_dir.clone().unwrap();
+        let package_out = work_dir.join(REACT_NATIVE_BINDINGS_DIR);
+        // The work directories of the iOS and Android builders are removed with this one
+        assert_eq!(plan.included_work_dirs.len(), 2);
+
+        let commands: Vec<String> = plan
+            .steps
+            .iter()
+            .filter_map(|step| match step {
+                BuildStep::Command { command, .. } => Some(command.command()),
+                _ => None,
+            })
+            .collect();
+        assert!(commands
+            .iter()
+            .any(|command| command.starts_with("cargo build --release --lib")));
+        assert!(commands
+            .iter()
+            .any(|command| command.starts_with("cargo ndk -t aarch64-linux-android build")));
+        let generate = commands
+            .iter()
+            .position(|command| {
+                command.starts_with(
+                    "uniffi-bindgen-react-native generate jsi bindings --library \
+                     --ts-dir src/generated --cpp-dir cpp/generated ",
+                )
+            })
+            .unwrap();
+        assert!(commands[generate].ends_with("/aarch64-apple-ios/release/libmy_circuits.dylib"));
+        assert_eq!(
+            commands[generate + 1],
+            "uniffi-bindgen-react-native generate jsi turbo-module --config ubrn.config.yaml \
+             my_circuits"
+        );
+
+        let copies: Vec<&Path> = plan
+            .copies()
+            .into_iter()
+            .map(|(_, to)| to)
+            .filter(|to| to.starts_with(&package_out))
+            .collect();
+        assert_eq!(
+            copies,
+            [
+                package_out.join("ios/MoproBindings.xcframework"),
+                package_out.join("android/src/main/jniLibs"),
+            ]
+        );
+
+        // Only the manifest of the package is written, with the slices at their new paths
+        let manifests: Vec<&BuildStep> = plan
+            .steps
+            .iter()
+            .filter(|step| matches!(step, BuildStep::WriteManifest { .. }))
+            .collect();
+        match manifests[..] {
+            [BuildStep::WriteManifest {
+                dir, arch_paths, ..
+            }] => {
+                assert_eq!(dir, &project_dir.join(REACT_NATIVE_BINDINGS_DIR));
+                assert_eq!(
+                    arch_paths,
+                    &[
+                        ArchPath::new(
+                            "ios/MoproBindings.xcframework/ios-arm64",
+                            &["aarch64-apple-ios"]
+                        ),
+                        ArchPath::new(
+                            "android/src/main/jniLibs/arm64-v8a",
+                            &["aarch64-linux-android"]
+                        ),
+                    ]
+                );
+            }
+            _ => panic!("expected one manifest, found {manifests:?}"),
+        }
+
+        let package = plan.written_file("package.json").unwrap();
+        let package: serde_json::Value = serde_json::from_str(package).unwrap();
+        assert_eq!(package["name"], "mopro-react-native");
+        assert_eq!(package["version"], "0.1.0");
+        assert_eq!(package["codegenConfig"]["name"], "MoproReactNativeSpec");
+    }
+
+    #[test]
+    fn test_android_only() {
+        let project = test_project("my-circuits", &["lib", "cdylib"]);
+        let project_dir = project.manifest_dir.clone();
+
+        // The static library is only needed by the XCFramework
+        let plan = ReactNativePlatform::plan(
+            &BuildOptions::new(Mode::Debug),
+            &project,
+            &project_dir,
+            vec![ReactNativeArch::Android(AndroidArch::X8664Linux)],
+            &[],
+            &ReactNativeBindingsParams::default(),
+        )
+        .unwrap();
+        assert!(plan.steps.iter().any(|step| matches!(
+            step,
+            BuildStep::GenerateBindings { library, .. }
+                if library.ends_with("x86_64-linux-android/debug/libmy_circuits.so")
+        )));
+        assert!(matches!(
+            ReactNativePlatform::plan(
+                &BuildOptions::new(Mode::Debug),
+                &project,
+                &project_dir,
+                Vec::new(),
+                &[],
+                &ReactNativeBindingsParams::default(),
+            ),
+            Err(BindingsError::NoTargetArchs { .. })
+        ));
+    }
+}
@@ -0,0 +1,408 @@
+//! Runners for the external tools the builders invoke (`cargo`, `rustup`, `lipo`, `xcodebuild`,
+//! `wasm-pack`, `flutter_rust_bridge_codegen`).
+//!
+//! Builders never spawn processes themselves, so their command sequences can be recorded in
+//! tests or printed in a dry run without any of the tools installed.
+
+use std::collections::VecDeque;
+use std::fmt;
+use std::io::{self, BufRead, BufReader, ErrorKind, Read};
+use std::path::{Path, PathBuf};
+use std::process::{Child, Command, ExitStatus, Stdio};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+use std::thread;
+use std::time::Duration;
+
+use serde::Serialize;
+
+use super::error::BindingsError;
+
+/// Runs the external commands of a build.
+pub trait CommandRunner: Sync {
+    /// Runs `cmd` to completion, failing if it cannot be started or exits unsuccessfully.
+    fn run(&self, cmd: &mut Command) -> Result<(), BindingsError>;
+
+    /// Runs `cmd` as one of the parallel jobs of a build. Its output is prefixed with `label`,
+    /// and it is stopped with [`BindingsError::Cancelled`] once `cancellation` is set.
+    fn run_job(
+        &self,
+        cmd: &mut Command,
+        label: &str,
+        cancellation: &Cancellation,
+    ) -> Result<(), BindingsError> {
+        let _ = (label, cancellation);
+        self.run(cmd)
+    }
+
+    /// Whether commands are reported instead of executed. Builders then skip the file system
+    /// steps that depend on the outputs of the commands.
+    fn is_dry_run(&self) -> bool {
+        false
+    }
+}
+
+/// Set once a job of a parallel build failed, to stop the others.
+#[derive(Debug, Default)]
+pub struct Cancellation(AtomicBool);
+
+impl Cancellation {
+    pub fn cancel(&self) {
+        self.0.store(true, Ordering::SeqCst);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.0.load(Ordering::SeqCst)
+    }
+}
+
+/// Number of stderr lines of a failed tool kept in [`BindingsError::ToolFailed`].
+const STDERR_TAIL_LINES: usize = 50;
+
+/// How often a running job checks whether it was cancelled.
+const CANCELLATION_POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Executes the commands. Their stderr is passed through, and the last lines are kept in the
+/// error if the tool fails.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct SystemRunner {
+    stdout_to_stderr: bool,
+}
+
+impl CommandRunner for SystemRunner {
+    fn run(&self, cmd: &mut Command) -> Result<(), BindingsError> {
+        self.run_command(cmd, None)
+    }
+
+    fn run_job(
+        &self,
+        cmd: &mut Command,
+        label: &str,
+        cancellation: &Cancellation,
+    ) -> Result<(), BindingsError> {
+        self.run_command(cmd, Some((label, cancellation)))
+    }
+}
+
+impl SystemRunner {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Passes the stdout of the tools to stderr, to keep stdout for machine-readable output.
+    pub fn stdout_to_stderr(self) -> Self {
+        Self {
+            stdout_to_stderr: true,
+        }
+    }
+
+    /// Runs `cmd` to completion. The output of a job is prefixed with its label, and the job is
+    /// killed once it is cancelled.
+    fn run_command(
+        &self,
+        cmd: &mut Command,
+        job: Option<(&str, &Cancellation)>,
+    ) -> Result<(), BindingsError> {
+        let tool = tool_name(cmd);
+        let missing_dir = cmd
+            .get_current_dir()
+            .filter(|dir| !dir.is_dir())
+            .map(Path::to_path_buf);
+        if job.is_some() {
+            cmd.stdout(Stdio::piped());
+        } else if self.stdout_to_stderr {
+            cmd.stdout(io::stderr());
+        }
+        let mut child = cmd.stderr(Stdio::piped()).spawn().map_err(|source| {
+            match (source.kind(), missing_dir) {
+                // Spawning also fails with `NotFound` when the working directory is missing
+                (ErrorKind::NotFound, Some(dir)) => BindingsError::io(format!(
+                    "Failed to run `{tool}` in {}, the directory does not exist",
+                    dir.display()
+                ))(source),
+                (ErrorKind::NotFound, None) => BindingsError::ToolNotFound {
+                    tool: tool.clone(),
+                    source,
+                },
+                _ => BindingsError::io(format!("Failed to run `{tool}`"))(source),
+            }
+        })?;
+
+        let prefix = job
+            .map(|(label, _)| format!("[{label}] "))
+            .unwrap_or_default();
+        let stdout_reader = child.stdout.take().map(|stdout| {
+            let prefix = prefix.clone();
+            let stdout_to_stderr = self.stdout_to_stderr;
+            thread::spawn(move || {
+                for line in lines(stdout) {
+                    if stdout_to_stderr {
+                        eprintln!("{prefix}{line}");
+                    } else {
+                        println!("{prefix}{line}");
+                    }
+                }
+            })
+        });
+        let stderr_reader = child.stderr.take().map(|stderr| {
+            thread::spawn(move || {
+                let mut stderr_tail = VecDeque::with_capacity(STDERR_TAIL_LINES);
+                for line in lines(stderr) {
+                    eprintln!("{prefix}{line}");
+                    if stderr_tail.len() == STDERR_TAIL_LINES {
+                        stderr_tail.pop_front();
+                    }
+                    stderr_tail.push_back(line);
+                }
+                Vec::from(stderr_tail).join("\n")
+            })
+        });
+
+        let status = match job {
+            Some((_, cancellation)) => wait_unless_cancelled(&mut child, cancellation),
+            None => child.wait().map(Some),
+        }
+        .map_err(BindingsError::io(format!("Failed to wait for `{tool}`")))?;
+        // The readers are left behind, as processes started by the tool may keep the pipes open
+        let Some(status) = status else {
+            return Err(BindingsError::Cancelled { tool });
+        };
+
+        if let Some(reader) = stdout_reader {
+            let _ = reader.join();
+        }
+        let stderr = stderr_reader
+            .and_then(|reader| reader.join().ok())
+            .unwrap_or_default();
+        if !status.success() {
+            return Err(BindingsError::ToolFailed {
+                tool,
+                status,
+                stderr,
+            });
+        }
+        Ok(())
+    }
+}
+
+/// Waits for `child` to exit, or kills it once `cancellation` is set and returns `None`.
+fn wait_unless_cancelled(
+    child: &mut Child,
+    cancellation: &Cancellation,
+) -> std::io::Result<Option<ExitStatus>> {
+    loop {
+        if let Some(status) = child.try_wait()? {
+            return Ok(Some(status));
+        }
+        if cancellation.is_cancelled() {
+            child.kill()?;
+            child.wait()?;
+            return Ok(None);
+        }
+        thread::sleep(CANCELLATION_POLL_INTERVAL);
+    }
+}
+
+fn lines(output: impl Read) -> impl Iterator<Item = String> {
+    BufReader::new(output)
+        .split(b'\n')
+        .map_while(Result::ok)
+        .map(|line| String::from_utf8_lossy(&line).into_owned())
+}
+
+/// Prints every command line with its environment and working directory instead of running it.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct DryRunRunner;
+
+impl CommandRunner for DryRunRunner {
+    fn run(&self, cmd: &mut Command) -> Result<(), BindingsError> {
+        println!("{}", CommandLine::from_command(cmd));
+        Ok(())
+    }
+
+    fn is_dry_run(&self) -> bool {
+        true
+    }
+}
+
+/// Records the commands instead of running them, for tests.
+#[derive(Debug, Default)]
+pub struct RecordingRunner {
+    commands: Mutex<Vec<CommandLine>>,
+}
+
+impl RecordingRunner {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The commands run so far, in order.
+    pub fn commands(&self) -> Vec<CommandLine> {
+        self.commands.lock().unwrap().clone()
+    }
+}
+
+impl CommandRunner for RecordingRunner {
+    fn run(&self, cmd: &mut Command) -> Result<(), BindingsError> {
+        self.commands
+            .lock()
+            .unwrap()
+            .push(CommandLine::from_command(cmd));
+        Ok(())
+    }
+
+    fn is_dry_run(&self) -> bool {
+        true
+    }
+}
+
+/// A command with the environment variables and working directory it was given.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct CommandLine {
+    pub program: String,
+    pub args: Vec<String>,
+    /// Variables set on the command, `None` for the ones removed
+    pub envs: Vec<(String, Option<String>)>,
+    pub current_dir: Option<PathBuf>,
+}
+
+impl CommandLine {
+    pub fn from_command(cmd: &Command) -> Self {
+        Self {
+            program: cmd.get_program().to_string_lossy().into_owned(),
+            args: cmd
+                .get_args()
+                .map(|arg| arg.to_string_lossy().into_owned())
+                .collect(),
+            envs: cmd
+                .get_envs()
+                .map(|(key, value)| {
+                    (
+                        key.to_string_lossy().into_owned(),
+                        value.map(|value| value.to_string_lossy().into_owned()),
+                    )
+                })
+                .collect(),
+            current_dir: cmd.get_current_dir().map(PathBuf::from),
+        }
+    }
+
+    /// Rebuilds the command, e.g. to run a command of a [`BuildPlan`](super::plan::BuildPlan).
+    pub fn to_command(&self) -> Command {
+        let mut cmd = Command::new(&self.program);
+        cmd.args(&self.args);
+        for (key, value) in &self.envs {
+            match value {
+                Some(value) => cmd.env(key, value),
+                None => cmd.env_remove(key),
+            };
+        }
+        if let Some(dir) = &self.current_dir {
+            cmd.current_dir(dir);
+        }
+        cmd
+    }
+
+    /// The program and its arguments, e.g. `cargo build --lib`.
+    pub fn command(&self) -> String {
+        std::iter::once(&self.program)
+            .chain(&self.args)
+            .map(|word| shell_quote(word))
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+}
+
+/// Formats as a shell line, e.g. `(cd /project && CARGO_BUILD_TARGET=aarch64-apple-ios cargo build --lib)`.
+impl fmt::Display for CommandLine {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut words = Vec::new();
+        for (key, value) in &self.envs {
+            match value {
+                Some(value) => words.push(format!("{key}={}", shell_quote(value))),
+                None => words.push(format!("-u {key}")),
+            }
+        }
+        if !words.is_empty() {
+            words.insert(0, "env".to_string());
+        }
+        words.push(self.command());
+        let line = words.join(" ");
+
+        match &self.current_dir {
+            Some(dir) => write!(f, "(cd {} && {line})", shell_quote(&dir.to_string_lossy())),
+            None => write!(f, "{line}"),
+        }
+    }
+}
+
+fn shell_quote(word: &str) -> String {
+    let is_plain = !word.is_empty()
+        && word
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || "-_./=:,+@%".contains(c));
+    if is_plain {
+        word.to_string()
+    } else {
+        format!("'{}'", word.replace('\'', r"'\''"))
+    }
+}
+
+/// Name of the tool run by `cmd` for error messages, with its subcommand, e.g. `cargo ndk`.
+fn tool_name(cmd: &Command) -> String {
+    let program = cmd.get_program().to_string_lossy();
+    match cmd.get_args().next().map(|arg| arg.to_string_lossy()) {
+        Some(subcommand) if !subcommand.starts_with('-') => format!("{program} {subcommand}"),
+        _ => program.into_owned(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_system_runner_errors() {
+        let err = SystemRunner::new()
+            .run(&mut Command::new("mopro-missing-tool"))
+            .unwrap_err();
+        assert!(
+            matches!(err, BindingsError::ToolNotFound { tool, .. } if tool == "mopro-missing-tool")
+        );
+
+        // A missing working directory is not reported as a missing tool
+        let err = SystemRunner::new()
+            .run(Command::new("sh").current_dir("/nonexistent/mopro-dir"))
+            .unwrap_err();
+        assert!(matches!(err, BindingsError::Io { .. }));
+        assert!(err.to_string().contains("/nonexistent/mopro-dir"));
+
+        let err = SystemRunner::new()
+            .run(Command::new("sh").args(["-c", "echo first >&2; echo second >&2; exit 3"]))
+            .unwrap_err();
+        match err {
+            BindingsError::ToolFailed {
+                tool,
+                status,
+                stderr,
+            } => {
+                assert_eq!(tool, "sh");
+                assert_eq!(status.code(), Some(3));
+                assert_eq!(stderr, "first\nsecond");
+            }
+            err => panic!("unexpected error: {err}"),
+        }
+    }
+
+    #[test]
+    fn test_command_line_display() {
+        let mut cmd = Command::new("cargo");
+        cmd.args(["build", "--lib"])
+            .env("RUSTFLAGS", "-C target-cpu=native")
+            .current_dir("/project");
+        assert_eq!(
+            CommandLine::from_command(&cmd).to_string(),
+            "(cd /project && env RUSTFLAGS='-C target-cpu=native' cargo build --lib)"
+        );
+    }
+}
@@ -1,89 +1,151 @@
 use std::path::Path;
 use std::process::Command;
-use std::{fs, path::PathBuf};
 
 use super::{
-    cleanup_tmp_local,
-    constants::{Mode, PlatformBuilder, WebArch, WebPlatform, WEB_BINDINGS_DIR},
+    config::{FromPlatformConfig, PlatformConfig},
+    constants::{
+        Arch, BuildOptions, Mode, Platform, PlatformBuilder, WebArch, WebPlatform,
+        WEB_BINDINGS_DIR, WEB_NIGHTLY_TOOLCHAIN,
+    },
+    doctor::{check_command, check_rustup_targets, check_rustup_toolchain, ToolCheck},
 };
-use super::{mktemp_local, project_name_from_toml};
+use super::{error::BindingsError, manifest::ArchPath, plan::BuildPlan, plan::BuildStep};
+use super::{project::Project, work_dir_path};
 
 // Maintained for backwards compatibility
 #[inline]
 pub fn build() {
-    super::build_from_env::<WebPlatform>()
+    super::build_from_env::<WebPlatform>().unwrap_or_else(|e| {
+        panic!(
+            "Failed to build {} bindings: {e}",
+            WebPlatform::identifier()
+        )
+    })
+}
+
+#[derive(Hash)]
+pub struct WebBindingsParams {
+    /// Name of the generated bindings directory
+    pub bindings_dir: String,
+}
+
+impl Default for WebBindingsParams {
+    fn default() -> Self {
+        Self {
+            bindings_dir: WEB_BINDINGS_DIR.to_string(),
+        }
+    }
+}
+
+impl FromPlatformConfig for WebBindingsParams {
+    fn from_platform_config(config: &PlatformConfig) -> anyhow::Result<Self> {
+        config.check_params(&[])?;
+        Ok(Self {
+            bindings_dir: config
+                .bindings_dir
+                .clone()
+                .unwrap_or_else(|| WEB_BINDINGS_DIR.to_string()),
+        })
+    }
 }
 
 impl PlatformBuilder for WebPlatform {
     type Arch = WebArch;
-    type Params = ();
+    type Params = WebBindingsParams;
 
-    fn build(
-        mode: Mode,
-        project_dir: &Path,
+    fn plan(
+        options: &BuildOptions,
+        project: &Project,
+        output_dir: &Path,
         _target_archs: Vec<Self::Arch>,
-        _params: Self::Params,
-    ) -> anyhow::Result<PathBuf> {
+        _fresh_archs: &[Self::Arch],
+        params: &Self::Params,
+    ) -> Result<BuildPlan, BindingsError> {
         if !cfg!(feature = "wasm") {
-            panic!("wasm feature is not enabled, please enable it in your Cargo.toml");
+            return Err(BindingsError::MissingFeature { feature: "wasm" });
         }
 
-        let _wasm_style_identifier = project_name_from_toml(project_dir)
-            .expect("Failed to get project name from Cargo.toml");
-        let build_dir_path = project_dir.join("build");
-        let work_dir = mktemp_local(&build_dir_path);
-        let bindings_out = work_dir.join(WEB_BINDINGS_DIR);
-        fs::create_dir(&bindings_out).expect("Failed to create bindings out directory");
-        let bindings_dest = Path::new(&project_dir).join(WEB_BINDINGS_DIR);
+        // wasm-pack only builds cdylib crates
+        project.require_crate_types(&["cdylib"])?;
+        let project_dir = &project.manifest_dir;
+        let wasm_style_identifier = &project.lib_name;
+        let work_dir = work_dir_path(&project_dir.join("build"));
+        let bindings_out = work_dir.join(&params.bindings_dir);
+        let bindings_dest = output_dir.join(&params.bindings_dir);
 
-        if !project_dir.join("Cargo.toml").exists() {
-            panic!("No Cargo.toml found in {:?}", project_dir);
-        }
+        let mut plan = BuildPlan::new(Self::name(), options, project, output_dir, &bindings_dest);
+        plan.work_dir = Some(work_dir);
+        plan.steps.push(BuildStep::CreateDir {
+            path: bindings_out.clone(),
+        });
 
-        let mode_cmd = match mode {
+        // wasm-pack picks the profile itself and only knows the built-in ones
+        let mode_cmd = match &options.mode {
             Mode::Release => "--release",
             Mode::Debug => "--dev",
+            Mode::Profile(profile) => {
+                return Err(BindingsError::UnsupportedProfile {
+                    platform: Self::name(),
+                    profile: profile.clone(),
+                })
+            }
         };
 
         let mut cmd = Command::new("rustup");
         cmd.args([
             "run",
-            "nightly-2024-07-18",
+            WEB_NIGHTLY_TOOLCHAIN,
             "wasm-pack",
             "build",
             "--target",
             "web",
             mode_cmd,
             "--out-dir",
-            bindings_out.to_str().unwrap(),
-        ]);
-
-        cmd.env(
-            "RUSTFLAGS",
-            "-C target-feature=+atomics,+bulk-memory -C link-arg=--max-memory=4294967296",
-        );
-        cmd.current_dir(&project_dir);
-
-        let status = cmd.status().expect("Failed to run wasm-pack");
-
-        if status.success() {
-            println!("mopro-ffi wasm package build completed successfully.");
-        } else {
-            eprintln!("mopro-ffi wasm package build failed.");
-            std::process::exit(1);
+        ])
+        .arg(&bindings_out);
+        let feature_args = options.cargo.feature_args();
+        if !feature_args.is_empty() {
+            // Arguments after `--` are passed on to cargo
+            cmd.arg("--").args(feature_args);
         }
 
-        if let Ok(info) = fs::metadata(&bindings_dest) {
-            if !info.is_dir() {
-                panic!("framework directory exists and is not a directory");
-            }
-            fs::remove_dir_all(&bindings_dest).expect("Failed to remove framework directory");
+        let mut rustflags =
+            "-C target-feature=+atomics,+bulk-memory -C link-arg=--max-memory=4294967296"
+                .to_string();
+        if let Some(flags) = options.cargo.rustflags(WebArch.as_str()) {
+            rustflags = format!("{rustflags} {flags}");
         }
+        cmd.env("RUSTFLAGS", rustflags);
+        cmd.current_dir(project_dir);
+        plan.steps
+            .push(BuildStep::arch_command(WebArch.as_str(), &cmd));
 
-        fs::rename(&bindings_out, &bindings_dest).expect("Failed to move framework into place");
+        plan.steps.push(BuildStep::Install {
+            from: bindings_out,
+            to: bindings_dest.clone(),
+        });
+        plan.steps.push(BuildStep::WriteManifest {
+            dir: bindings_dest,
+            uniffi_version: None,
+            arch_paths: vec![ArchPath::new(
+                format!("{wasm_style_identifier}_bg.wasm"),
+                &[WebArch.as_str()],
+            )],
+        });
 
-        cleanup_tmp_local(&build_dir_path);
+        Ok(plan)
+    }
 
-        Ok(bindings_dest)
+    fn toolchain_checks(target_archs: &[Self::Arch]) -> Vec<ToolCheck> {
+        let mut checks = check_rustup_toolchain(WEB_NIGHTLY_TOOLCHAIN, &["rust-src"]);
+        let targets: Vec<&str> = target_archs.iter().map(|arch| arch.as_str()).collect();
+        checks.extend(check_rustup_targets(Some(WEB_NIGHTLY_TOOLCHAIN), &targets));
+        checks.push(check_command(
+            "wasm-pack",
+            "wasm-pack",
+            &["--version"],
+            "cargo install wasm-pack",
+        ));
+        checks
     }
 }